@@ -0,0 +1,135 @@
+//! Persists an [`AliasTable`] in the memory of a device on the same bus (e.g. a DS2431 or
+//! DS2433 EEPROM), so a sensor harness carries its own configuration
+//!
+//! The stored record consists of a magic number, the length of the serialized table, the
+//! table itself and an inverted CRC16 over all preceding bytes.
+
+use core::fmt::Debug;
+use hal::blocking::delay::DelayUs;
+
+use crate::alias::{AliasError, AliasTable};
+use crate::memory::Memory;
+use crate::{compute_partial_crc16, CRC16_RESIDUE};
+use crate::{Error, OneWireMaster};
+
+const MAGIC: [u8; 2] = [b'O', b'W'];
+const HEADER_BYTES: usize = 4;
+const CRC_BYTES: usize = 2;
+
+#[derive(Debug)]
+pub enum AddressBookError<E: Debug> {
+    Bus(Error<E>),
+    Alias(AliasError),
+    /// The memory does not contain an address book (or it is corrupted)
+    NotFound,
+}
+
+impl<E: Debug> From<Error<E>> for AddressBookError<E> {
+    fn from(e: Error<E>) -> Self {
+        AddressBookError::Bus(e)
+    }
+}
+
+impl<E: Debug> From<AliasError> for AddressBookError<E> {
+    fn from(e: AliasError) -> Self {
+        AddressBookError::Alias(e)
+    }
+}
+
+/// Writes the table at `address` into the memory, `buffer` is used to serialize the
+/// record and has to be large enough for it
+pub fn store<M: Memory, W: OneWireMaster, const N: usize>(
+    memory: &M,
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    address: u16,
+    table: &AliasTable<N>,
+    buffer: &mut [u8],
+) -> Result<usize, AddressBookError<W::Error>> {
+    let (header, rest) = split_at_mut(buffer, HEADER_BYTES)?;
+    let len = table.serialize(rest)?;
+    let len_bytes = (len as u16).to_le_bytes();
+    header.copy_from_slice(&[MAGIC[0], MAGIC[1], len_bytes[0], len_bytes[1]]);
+
+    let record_len = HEADER_BYTES + len;
+    let (record, rest) = split_at_mut(buffer, record_len)?;
+    let crc = !compute_partial_crc16(0, record);
+    let (crc_bytes, _) = split_at_mut(rest, CRC_BYTES)?;
+    crc_bytes.copy_from_slice(&crc.to_le_bytes());
+
+    let total = record_len + CRC_BYTES;
+    let record = buffer.get(..total).ok_or(AliasError::BufferTooSmall)?;
+    if usize::from(address) + total > usize::from(memory.size()) {
+        return Err(AliasError::BufferTooSmall.into());
+    }
+    memory.write_memory(wire, delay, address, record)?;
+    Ok(total)
+}
+
+/// Reads a table written by [`store`] from `address`, `buffer` is used to read the record
+pub fn restore<M: Memory, W: OneWireMaster, const N: usize>(
+    memory: &M,
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    address: u16,
+    buffer: &mut [u8],
+) -> Result<AliasTable<N>, AddressBookError<W::Error>> {
+    let mut header = [0u8; HEADER_BYTES];
+    memory.read_memory(wire, delay, address, &mut header)?;
+    let [m0, m1, len_lo, len_hi] = header;
+    if [m0, m1] != MAGIC {
+        return Err(AddressBookError::NotFound);
+    }
+    let len = usize::from(u16::from_le_bytes([len_lo, len_hi]));
+    let record = buffer
+        .get_mut(..len + CRC_BYTES)
+        .ok_or(AliasError::BufferTooSmall)?;
+    memory.read_memory(wire, delay, address + HEADER_BYTES as u16, record)?;
+
+    if compute_partial_crc16(compute_partial_crc16(0, &header), record) != CRC16_RESIDUE {
+        return Err(AddressBookError::NotFound);
+    }
+    let table = record.get(..len).ok_or(AliasError::Malformed)?;
+    Ok(AliasTable::deserialize(table)?)
+}
+
+fn split_at_mut<E: Debug>(
+    buffer: &mut [u8],
+    mid: usize,
+) -> Result<(&mut [u8], &mut [u8]), AddressBookError<E>> {
+    if buffer.len() < mid {
+        Err(AliasError::BufferTooSmall.into())
+    } else {
+        Ok(buffer.split_at_mut(mid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{restore, store};
+    use crate::alias::AliasTable;
+    use crate::test_support::{Delay, Pin, Ram};
+    use crate::{Device, OneWire};
+    use core::cell::RefCell;
+
+    #[test]
+    fn test_store_restore() {
+        let device = Device {
+            address: [0x28, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let mut table = AliasTable::<4>::new();
+        table.assign(&device).unwrap();
+
+        let ram = Ram(RefCell::new([0xFF; 64]));
+        let mut wire = OneWire::new(Pin, false);
+        let mut buffer = [0u8; 32];
+        assert!(restore::<_, _, 4>(&ram, &mut wire, &mut Delay, 8, &mut buffer).is_err());
+        store(&ram, &mut wire, &mut Delay, 8, &table, &mut buffer).unwrap();
+
+        let restored = restore::<_, _, 4>(&ram, &mut wire, &mut Delay, 8, &mut buffer).unwrap();
+        assert_eq!(restored.device_of(0), Some(&device));
+
+        ram.0.borrow_mut()[14] ^= 0x01;
+        assert!(restore::<_, _, 4>(&ram, &mut wire, &mut Delay, 8, &mut buffer).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use core::convert::TryInto;
+
+use crate::Device;
+use crate::ADDRESS_BYTES;
+
+const ENTRY_BYTES: usize = 1 + ADDRESS_BYTES as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasError {
+    /// All `N` aliases are already assigned
+    TableFull,
+    BufferTooSmall,
+    /// The serialized data is inconsistent, e.g. an alias is out of range
+    Malformed,
+}
+
+/// Assigns compact and stable `u8` aliases to devices, so that a reading can be transmitted
+/// with its alias instead of the 8 byte address. Aliases are handed out in the order the
+/// devices are registered and are kept until removed, so persisting the table with
+/// [`AliasTable::serialize`] keeps them stable across restarts.
+#[derive(Debug, Clone)]
+pub struct AliasTable<const N: usize> {
+    devices: [Option<Device>; N],
+}
+
+impl<const N: usize> AliasTable<N> {
+    const EMPTY: Option<Device> = None;
+
+    pub fn new() -> Self {
+        AliasTable {
+            devices: [Self::EMPTY; N],
+        }
+    }
+
+    /// Returns the alias of the device, assigning the lowest free one if it has none yet
+    pub fn assign(&mut self, device: &Device) -> Result<u8, AliasError> {
+        if let Some(alias) = self.alias_of(device) {
+            return Ok(alias);
+        }
+        let (alias, slot) = self
+            .devices
+            .iter_mut()
+            .enumerate()
+            .take(usize::from(u8::MAX))
+            .find(|(_, slot)| slot.is_none())
+            .ok_or(AliasError::TableFull)?;
+        *slot = Some(device.clone());
+        Ok(alias as u8)
+    }
+
+    pub fn alias_of(&self, device: &Device) -> Option<u8> {
+        self.devices
+            .iter()
+            .position(|d| d.as_ref() == Some(device))
+            .map(|alias| alias as u8)
+    }
+
+    pub fn device_of(&self, alias: u8) -> Option<&Device> {
+        self.devices.get(usize::from(alias))?.as_ref()
+    }
+
+    pub fn remove(&mut self, alias: u8) -> Option<Device> {
+        self.devices.get_mut(usize::from(alias))?.take()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &Device)> {
+        self.devices
+            .iter()
+            .enumerate()
+            .filter_map(|(alias, device)| Some((alias as u8, device.as_ref()?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bytes [`AliasTable::serialize`] is going to write
+    pub fn serialized_len(&self) -> usize {
+        1 + self.len() * ENTRY_BYTES
+    }
+
+    /// Writes the number of entries followed by the alias and address of each entry,
+    /// returns the number of bytes written
+    pub fn serialize(&self, buffer: &mut [u8]) -> Result<usize, AliasError> {
+        let len = self.serialized_len();
+        if buffer.len() < len {
+            return Err(AliasError::BufferTooSmall);
+        }
+        let (count, entries) = buffer.split_first_mut().ok_or(AliasError::BufferTooSmall)?;
+        *count = self.len() as u8;
+        for ((alias, device), entry) in self.iter().zip(entries.chunks_exact_mut(ENTRY_BYTES)) {
+            if let Some((entry_alias, address)) = entry.split_first_mut() {
+                *entry_alias = alias;
+                for (dst, src) in address.iter_mut().zip(device.address.iter()) {
+                    *dst = *src;
+                }
+            }
+        }
+        Ok(len)
+    }
+
+    /// Restores a table written by [`AliasTable::serialize`]
+    pub fn deserialize(buffer: &[u8]) -> Result<Self, AliasError> {
+        let count = usize::from(*buffer.first().ok_or(AliasError::BufferTooSmall)?);
+        let entries = buffer
+            .get(1..1 + count * ENTRY_BYTES)
+            .ok_or(AliasError::BufferTooSmall)?;
+        let mut table = Self::new();
+        for entry in entries.chunks_exact(ENTRY_BYTES) {
+            let (alias, address) = entry.split_first().ok_or(AliasError::Malformed)?;
+            let address = address.try_into().map_err(|_| AliasError::Malformed)?;
+            let slot = table
+                .devices
+                .get_mut(usize::from(*alias))
+                .ok_or(AliasError::Malformed)?;
+            if slot.is_some() {
+                return Err(AliasError::Malformed);
+            }
+            *slot = Some(Device { address });
+        }
+        Ok(table)
+    }
+}
+
+impl<const N: usize> Default for AliasTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AliasTable;
+    use crate::Device;
+
+    #[test]
+    fn test_serialization() {
+        let a = Device {
+            address: [0x28, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let b = Device {
+            address: [0x28, 7, 6, 5, 4, 3, 2, 1],
+        };
+        let mut table = AliasTable::<4>::new();
+        assert_eq!(table.assign(&a), Ok(0));
+        assert_eq!(table.assign(&b), Ok(1));
+        assert_eq!(table.assign(&a), Ok(0));
+        assert_eq!(table.remove(0), Some(a.clone()));
+
+        let mut buffer = [0u8; 32];
+        let len = table.serialize(&mut buffer).unwrap();
+        assert_eq!(len, 10);
+
+        let mut table = AliasTable::<4>::deserialize(&buffer[..len]).unwrap();
+        assert_eq!(table.device_of(1), Some(&b));
+        assert_eq!(table.assign(&a), Ok(0));
+    }
+}
@@ -0,0 +1,474 @@
+//! Async counterparts of the bus operations, waiting with an
+//! [`embedded_hal_async::delay::DelayNs`] so executors like embassy are not blocked during
+//! the long reset and recovery delays
+//!
+//! The low pulses and sample windows within a slot last only a few microseconds and are
+//! waited for blocking, as an executor switching tasks there would stretch them beyond
+//! what the devices accept, see [`SlotDelay`].
+//!
+//! Only standard speed is supported, see [`OneWire::transaction_with_speed`] for overdrive.
+
+use core::fmt::Debug;
+
+use embedded_hal_async::delay::DelayNs;
+use hal::blocking::delay::DelayUs;
+
+use crate::Command;
+use crate::Device;
+use crate::DeviceSearch;
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::ADDRESS_BITS;
+use crate::{Quality, Reading};
+
+/// A delay waiting asynchronously for resets and recovery times, and blocking within the
+/// slots, e.g. the `Delay` of embassy-time
+pub trait SlotDelay: DelayNs + DelayUs<u16> {}
+
+impl<D: DelayNs + DelayUs<u16>> SlotDelay for D {}
+
+/// Wraps a [`OneWire`], sharing its configuration (timing, parasite mode, checks)
+pub struct AsyncOneWire<ODO: OpenDrainOutput> {
+    wire: OneWire<ODO>,
+}
+
+impl<E: Debug, ODO: OpenDrainOutput<Error = E>> AsyncOneWire<ODO> {
+    pub fn new(wire: OneWire<ODO>) -> Self {
+        AsyncOneWire { wire }
+    }
+
+    pub fn inner(&self) -> &OneWire<ODO> {
+        &self.wire
+    }
+
+    pub fn inner_mut(&mut self) -> &mut OneWire<ODO> {
+        &mut self.wire
+    }
+
+    pub fn into_inner(self) -> OneWire<ODO> {
+        self.wire
+    }
+
+    pub async fn reset_select_write_read(
+        &mut self,
+        delay: &mut impl SlotDelay,
+        device: &Device,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        self.wire.output.begin_transaction()?;
+        let result = async {
+            self.reset(delay).await?;
+            self.select(delay, device).await?;
+            self.write_bytes(delay, write).await?;
+            self.read_bytes(delay, read).await?;
+            Ok(())
+        }
+        .await;
+        self.end_transaction(result)
+    }
+
+    pub async fn reset_select_read_only(
+        &mut self,
+        delay: &mut impl SlotDelay,
+        device: &Device,
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        self.reset_select_write_read(delay, device, &[], read).await
+    }
+
+    pub async fn reset_select_write_only(
+        &mut self,
+        delay: &mut impl SlotDelay,
+        device: &Device,
+        write: &[u8],
+    ) -> Result<(), Error<E>> {
+        self.reset_select_write_read(delay, device, write, &mut [])
+            .await
+    }
+
+    /// See [`OneWire::transaction`]
+    fn end_transaction<T>(&mut self, result: Result<T, Error<E>>) -> Result<T, Error<E>> {
+        if result.is_err() {
+            self.wire.release_line();
+        }
+        self.wire.output.end_transaction()?;
+        if let Some(e) = self.wire.take_slot_error() {
+            return Err(e);
+        }
+        result
+    }
+
+    pub async fn select(
+        &mut self,
+        delay: &mut impl SlotDelay,
+        device: &Device,
+    ) -> Result<(), Error<E>> {
+        let parasite_mode = self.wire.parasite_mode();
+        self.write_byte(delay, Command::SelectRom as u8, parasite_mode)
+            .await?;
+        for (i, byte) in device.address.iter().enumerate() {
+            let last = i == device.address.len() - 1;
+            self.write_byte(delay, *byte, parasite_mode && last).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn search_next(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl SlotDelay,
+    ) -> Result<Option<Device>, Error<E>> {
+        self.wire.output.begin_transaction()?;
+        let result = self.search(search, delay, Command::SearchNext).await;
+        self.end_transaction(result)
+    }
+
+    pub async fn search_next_alarmed(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl SlotDelay,
+    ) -> Result<Option<Device>, Error<E>> {
+        self.wire.output.begin_transaction()?;
+        let result = self.search(search, delay, Command::SearchNextAlarmed).await;
+        self.end_transaction(result)
+    }
+
+    async fn search(
+        &mut self,
+        rom: &mut DeviceSearch,
+        delay: &mut impl SlotDelay,
+        cmd: Command,
+    ) -> Result<Option<Device>, Error<E>> {
+        rom.progress = None;
+        let last_discrepancy = match rom.search_prepare() {
+            Some(last_discrepancy) => last_discrepancy,
+            None => return Ok(None),
+        };
+        if !self.reset(delay).await? {
+            return Ok(None);
+        }
+        self.write_byte(delay, cmd as u8, false).await?;
+        let last_discrepancy = match rom.search_continues(last_discrepancy) {
+            Some(last_discrepancy) => last_discrepancy,
+            None => return Ok(None),
+        };
+
+        let mut discrepancy_found = false;
+        for i in 0..ADDRESS_BITS {
+            let bit0 = self.read_bit(delay).await?; // normal bit
+            let bit1 = self.read_bit(delay).await?; // complementar bit
+            match rom.search_direction(i, last_discrepancy, &mut discrepancy_found, bit0, bit1) {
+                Some(bit) => self.write_bit(delay, bit).await?,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(rom.search_finish(discrepancy_found)))
+    }
+
+    /// Performs a reset and listens for a presence pulse
+    /// Returns Err(WireNotHigh) if the wire seems to be shortened,
+    /// Ok(true) if presence pulse has been received and Ok(false)
+    /// if no other device was detected but the wire seems to be ok
+    pub async fn reset(&mut self, delay: &mut impl SlotDelay) -> Result<bool, Error<E>> {
+        if let Some(e) = self.wire.take_slot_error() {
+            return Err(e);
+        }
+        self.wire.set_input()?;
+        self.ensure_wire_high(delay).await?;
+        if self.wire.collision_detection && !self.wire.read()? {
+            return Err(Error::BusContention);
+        }
+        self.wire.write_low()?;
+        self.wire.set_output()?;
+
+        let timing = *self.wire.active_timing();
+        DelayNs::delay_us(delay, u32::from(timing.reset_low)).await;
+        self.wire.set_input()?;
+
+        let mut val = false;
+        for _ in 0..timing.presence_polls {
+            DelayNs::delay_us(delay, u32::from(timing.presence_poll)).await;
+            val |= !self.wire.read()?;
+        }
+        DelayNs::delay_us(delay, u32::from(timing.reset_recovery)).await;
+        Ok(val)
+    }
+
+    async fn ensure_wire_high(&mut self, delay: &mut impl SlotDelay) -> Result<(), Error<E>> {
+        for _ in 0..125 {
+            if self.wire.read()? {
+                return Ok(());
+            }
+            DelayNs::delay_us(delay, 2).await;
+        }
+        Err(Error::WireNotHigh)
+    }
+
+    pub async fn read_bytes(
+        &mut self,
+        delay: &mut impl SlotDelay,
+        dst: &mut [u8],
+    ) -> Result<(), E> {
+        for d in dst {
+            *d = self.read_byte(delay).await?;
+        }
+        Ok(())
+    }
+
+    async fn read_byte(&mut self, delay: &mut impl SlotDelay) -> Result<u8, E> {
+        let mut byte = 0_u8;
+        for _ in 0..8 {
+            byte >>= 1;
+            if self.read_bit(delay).await? {
+                byte |= 0x80;
+            }
+        }
+        Ok(byte)
+    }
+
+    async fn read_bit(&mut self, delay: &mut impl SlotDelay) -> Result<bool, E> {
+        let timing = *self.wire.active_timing();
+        let start = self.wire.slot_timestamp();
+        self.wire.set_output()?;
+        self.wire.write_low()?;
+        DelayUs::delay_us(delay, timing.read_low);
+        self.wire.set_input()?;
+        DelayUs::delay_us(delay, timing.read_sample);
+        let val = self.wire.read();
+        self.wire.check_sample_latency(start);
+        DelayNs::delay_us(delay, u32::from(timing.read_recovery)).await;
+        val
+    }
+
+    pub async fn write_bytes(&mut self, delay: &mut impl SlotDelay, bytes: &[u8]) -> Result<(), E> {
+        for b in bytes {
+            self.write_byte(delay, *b, false).await?;
+        }
+        if !self.wire.parasite_mode() {
+            self.wire.disable_parasite_mode()?;
+        } else if !bytes.is_empty() {
+            self.wire.enable_strong_pull_up()?;
+        }
+        Ok(())
+    }
+
+    async fn write_byte(
+        &mut self,
+        delay: &mut impl SlotDelay,
+        mut byte: u8,
+        parasite_mode: bool,
+    ) -> Result<(), E> {
+        for _ in 0..8 {
+            self.write_bit(delay, (byte & 0x01) == 0x01).await?;
+            byte >>= 1;
+        }
+        if !parasite_mode {
+            self.wire.disable_parasite_mode()?;
+        } else {
+            self.wire.enable_strong_pull_up()?;
+        }
+        Ok(())
+    }
+
+    async fn write_bit(&mut self, delay: &mut impl SlotDelay, high: bool) -> Result<(), E> {
+        if self.wire.collides()? {
+            return Ok(());
+        }
+        let (low, recovery) = self.wire.write_slot(high);
+        self.wire.write_low()?;
+        self.wire.set_output()?;
+        DelayUs::delay_us(delay, low);
+        self.wire.write_high()?;
+        DelayNs::delay_us(delay, u32::from(recovery)).await;
+        Ok(())
+    }
+}
+
+impl DeviceSearch {
+    /// Async counterpart of [`DeviceSearch::into_iter`]
+    pub fn into_async_iter<'a, ODO: OpenDrainOutput, Delay: SlotDelay>(
+        self,
+        wire: &'a mut AsyncOneWire<ODO>,
+        delay: &'a mut Delay,
+    ) -> AsyncDeviceSearchIter<'a, ODO, Delay> {
+        AsyncDeviceSearchIter {
+            search: Some(self),
+            wire,
+            delay,
+        }
+    }
+}
+
+/// Enumerates the devices on the bus like [`crate::DeviceSearchIter`], but awaits the
+/// delays
+///
+/// ```ignore
+/// let mut devices = DeviceSearch::new().into_async_iter(&mut wire, &mut delay);
+/// while let Some(device) = devices.next().await {
+///     let device = device?;
+/// }
+/// ```
+pub struct AsyncDeviceSearchIter<'a, ODO: OpenDrainOutput, Delay: SlotDelay> {
+    search: Option<DeviceSearch>,
+    wire: &'a mut AsyncOneWire<ODO>,
+    delay: &'a mut Delay,
+}
+
+impl<'a, ODO: OpenDrainOutput, Delay: SlotDelay> AsyncDeviceSearchIter<'a, ODO, Delay> {
+    /// The next device, `None` once all devices were found
+    pub async fn next(&mut self) -> Option<Result<Device, Error<ODO::Error>>> {
+        let mut search = self.search.take()?;
+        let result = self
+            .wire
+            .search_next(&mut search, &mut *self.delay)
+            .await
+            .transpose()?;
+        self.search = Some(search);
+        Some(result)
+    }
+}
+
+/// Async counterpart of [`Sensor`]
+#[allow(async_fn_in_trait)]
+pub trait AsyncSensor: Sensor {
+    /// returns the milliseconds required to wait until the measurement finished
+    async fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<u16, Error<O::Error>>;
+
+    /// returns the measured value
+    #[cfg(not(feature = "no-float"))]
+    async fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<f32, Error<O::Error>>;
+
+    async fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<u16, Error<O::Error>>;
+
+    /// returns the measured value in thousandths of its unit (e.g. millidegrees)
+    async fn read_measurement_milli<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<i32, Error<O::Error>> {
+        let raw = AsyncSensor::read_measurement_raw(self, wire, delay).await?;
+        Ok(self.raw_to_milli(raw))
+    }
+
+    /// See [`Sensor::read`]
+    async fn read<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<Reading, Error<O::Error>> {
+        let (raw, quality) = match AsyncSensor::read_measurement_raw(self, wire, delay).await {
+            Ok(raw) => (raw, Quality::Fresh),
+            Err(Error::CrcMismatch(..)) | Err(Error::Crc16Mismatch(..)) => (
+                AsyncSensor::read_measurement_raw(self, wire, delay).await?,
+                Quality::CrcRetried,
+            ),
+            Err(e) => return Err(e),
+        };
+        Ok(Reading {
+            device: self.device().clone(),
+            raw,
+            milli: self.raw_to_milli(raw),
+            unit: self.unit(),
+            quality,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, Seeded, SimDelay, SimPin, Simulator};
+
+    /// Counts the asynchronous waits of a [`SimDelay`]
+    struct CountingDelay<'a, const N: usize> {
+        delay: SimDelay<'a, N>,
+        awaits: usize,
+    }
+
+    impl<const N: usize> DelayUs<u16> for CountingDelay<'_, N> {
+        fn delay_us(&mut self, us: u16) {
+            DelayUs::delay_us(&mut self.delay, us);
+        }
+    }
+
+    impl<const N: usize> DelayNs for CountingDelay<'_, N> {
+        async fn delay_ns(&mut self, ns: u32) {
+            self.awaits += 1;
+            self.delay.delay_ns(ns).await;
+        }
+
+        async fn delay_us(&mut self, us: u32) {
+            self.delay_ns(us.saturating_mul(1000)).await;
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let sim = Simulator::<16>::new(&mut Seeded {
+            family: 0x28,
+            state: 7,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut wire = AsyncOneWire::new(OneWire::new(SimPin(&sim), false));
+        let mut delay = SimDelay(&sim);
+        let mut search = DeviceSearch::new();
+        let mut count = 0;
+        while let Some(device) = block_on(wire.search_next(&mut search, &mut delay)).unwrap() {
+            assert!(addresses.contains(&device.address));
+            count += 1;
+        }
+        assert_eq!(16, count);
+    }
+
+    #[test]
+    fn test_search_iter() {
+        let sim = Simulator::<5>::new(&mut Seeded {
+            family: 0x10,
+            state: 11,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut wire = AsyncOneWire::new(OneWire::new(SimPin(&sim), false));
+        let mut delay = SimDelay(&sim);
+        let mut devices = DeviceSearch::new().into_async_iter(&mut wire, &mut delay);
+        let mut count = 0;
+        while let Some(device) = block_on(devices.next()) {
+            assert!(addresses.contains(&device.unwrap().address));
+            count += 1;
+        }
+        assert_eq!(5, count);
+        assert!(block_on(devices.next()).is_none());
+    }
+
+    #[test]
+    fn test_awaits_only_recovery_within_slots() {
+        let sim = Simulator::<1>::new(&mut Seeded {
+            family: 0x28,
+            state: 3,
+        });
+        let mut wire = AsyncOneWire::new(OneWire::new(SimPin(&sim), false));
+        let mut delay = CountingDelay {
+            delay: SimDelay(&sim),
+            awaits: 0,
+        };
+        block_on(wire.write_bytes(&mut delay, &[0xA5])).unwrap();
+        assert_eq!(8, delay.awaits);
+        let mut byte = [0u8; 1];
+        block_on(wire.read_bytes(&mut delay, &mut byte)).unwrap();
+        assert_eq!(16, delay.awaits);
+    }
+}
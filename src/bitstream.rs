@@ -0,0 +1,213 @@
+//! Clocks slow bit streams out of the PIO outputs of addressable switches like the DS2413
+//! or DS2408, e.g. to drive a shift register at the far end of the wire. Each change of
+//! the outputs is a bus transaction, which also determines the timing of the stream.
+
+use hal::blocking::delay::DelayUs;
+
+use crate::Error;
+use crate::OneWireMaster;
+
+/// Answered by the dual channel switches after a PIO access write
+#[cfg(any(feature = "ds2413", feature = "ds28ea00"))]
+const CONFIRMATION: u8 = 0xAA;
+
+/// The state of the two PIO pins of the DS2413 and DS28EA00, `true` meaning high
+/// (respectively the output transistor off)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PioState {
+    pub level_a: bool,
+    pub latch_a: bool,
+    pub level_b: bool,
+    pub latch_b: bool,
+}
+
+impl PioState {
+    /// Decodes a status byte, whose upper nibble is the complement of the lower one
+    pub fn decode<E: core::fmt::Debug>(status: u8) -> Result<PioState, Error<E>> {
+        if status >> 4 != !status & 0x0F {
+            return Err(Error::CommandFailed(status));
+        }
+        Ok(PioState {
+            level_a: status & 0x01 != 0,
+            latch_a: status & 0x02 != 0,
+            level_b: status & 0x04 != 0,
+            latch_b: status & 0x08 != 0,
+        })
+    }
+}
+
+/// Reads the status of a dual channel switch with its PIO access read command
+#[cfg(any(feature = "ds2413", feature = "ds28ea00"))]
+pub(crate) fn read_dual_pio<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    device: &crate::Device,
+    command: u8,
+) -> Result<PioState, Error<W::Error>> {
+    let mut status = [0u8; 1];
+    wire.reset_select_write_read(delay, device, &[command], &mut status)?;
+    let [status] = status;
+    PioState::decode(status)
+}
+
+/// Writes the latches of a dual channel switch with its PIO access write command, which is
+/// followed by the output byte and its complement and confirmed before the new status
+#[cfg(any(feature = "ds2413", feature = "ds28ea00"))]
+pub(crate) fn write_dual_pio<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    device: &crate::Device,
+    command: u8,
+    latch_a: bool,
+    latch_b: bool,
+) -> Result<PioState, Error<W::Error>> {
+    let output = 0xFC | u8::from(latch_a) | u8::from(latch_b) << 1;
+    let mut answer = [0u8; 2];
+    wire.reset_select_write_read(delay, device, &[command, output, !output], &mut answer)?;
+    match answer {
+        [CONFIRMATION, status] => PioState::decode(status),
+        [other, _] => Err(Error::CommandFailed(other)),
+    }
+}
+
+/// Devices with outputs that can be written with a single transaction
+pub trait PioWrite {
+    /// Sets the output latches to `state`, a set bit releases the output (it is pulled
+    /// high externally) and a cleared bit pulls it low
+    fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        state: u8,
+    ) -> Result<(), Error<W::Error>>;
+}
+
+/// Writes each state of the pattern to the outputs in order
+pub fn write_pattern<P: PioWrite, W: OneWireMaster>(
+    device: &P,
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    pattern: &[u8],
+) -> Result<(), Error<W::Error>> {
+    for state in pattern {
+        device.write_pio(wire, delay, *state)?;
+    }
+    Ok(())
+}
+
+/// Serial output on a data and a clock output, with an optional latch output which is
+/// pulsed after all bytes were shifted out (like the storage register clock of a 74HC595)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShiftOut {
+    pub data_mask: u8,
+    pub clock_mask: u8,
+    pub latch_mask: u8,
+    /// State of all other outputs
+    pub idle: u8,
+    pub msb_first: bool,
+}
+
+impl ShiftOut {
+    pub fn new(data_mask: u8, clock_mask: u8) -> Self {
+        ShiftOut {
+            data_mask,
+            clock_mask,
+            latch_mask: 0,
+            idle: !(data_mask | clock_mask),
+            msb_first: true,
+        }
+    }
+
+    pub fn with_latch(mut self, latch_mask: u8) -> Self {
+        self.latch_mask = latch_mask;
+        self.idle &= !latch_mask;
+        self
+    }
+
+    fn state(&self, data: bool, clock: bool, latch: bool) -> u8 {
+        let mut state = self.idle;
+        if data {
+            state |= self.data_mask;
+        }
+        if clock {
+            state |= self.clock_mask;
+        }
+        if latch {
+            state |= self.latch_mask;
+        }
+        state
+    }
+
+    /// Shifts out the bytes, each bit is set up with the clock low and taken over on the
+    /// rising edge of the clock
+    pub fn write<P: PioWrite, W: OneWireMaster>(
+        &self,
+        device: &P,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        bytes: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        for byte in bytes {
+            for i in 0..8 {
+                let bit = if self.msb_first { 7 - i } else { i };
+                let data = byte & (1 << bit) != 0;
+                device.write_pio(wire, delay, self.state(data, false, false))?;
+                device.write_pio(wire, delay, self.state(data, true, false))?;
+            }
+        }
+        device.write_pio(wire, delay, self.state(false, false, false))?;
+        if self.latch_mask != 0 {
+            device.write_pio(wire, delay, self.state(false, false, true))?;
+            device.write_pio(wire, delay, self.state(false, false, false))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PioState, PioWrite, ShiftOut};
+    use crate::test_support::{Delay, Pin};
+    use crate::{Error, OneWire, OneWireMaster};
+    use core::cell::RefCell;
+    use hal::blocking::delay::DelayUs;
+
+    struct Recorder(RefCell<([u8; 32], usize)>);
+
+    impl PioWrite for Recorder {
+        fn write_pio<W: OneWireMaster>(
+            &self,
+            _wire: &mut W,
+            _delay: &mut impl DelayUs<u16>,
+            state: u8,
+        ) -> Result<(), Error<W::Error>> {
+            let mut recorded = self.0.borrow_mut();
+            let index = recorded.1;
+            recorded.0[index] = state;
+            recorded.1 += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_pio_status() {
+        let state = PioState::decode::<core::convert::Infallible>(0xA5).unwrap();
+        assert!(state.level_a && !state.latch_a && state.level_b && !state.latch_b);
+        assert!(PioState::decode::<core::convert::Infallible>(0xA6).is_err());
+    }
+
+    #[test]
+    fn test_shift_out() {
+        let recorder = Recorder(RefCell::new(([0u8; 32], 0)));
+        let shift = ShiftOut::new(0x01, 0x02).with_latch(0x04);
+        let mut wire = OneWire::new(Pin, false);
+        shift
+            .write(&recorder, &mut wire, &mut Delay, &[0x81])
+            .unwrap();
+
+        let (states, len) = *recorder.0.borrow();
+        assert_eq!(len, 19);
+        assert_eq!(&states[..4], &[0xF9, 0xFB, 0xF8, 0xFA]);
+        assert_eq!(&states[14..19], &[0xF9, 0xFB, 0xF8, 0xFC, 0xF8]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Adapting the timing to delays that cannot wait single microseconds
+
+use core::fmt::Debug;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::Timing;
+
+/// Number of 1us delays measured by [`OneWire::calibrate`]
+const SAMPLES: u32 = 16;
+
+/// Delays above this granularity in microseconds need [`Timing::RELAXED`]
+pub const PRECISE_GRANULARITY_US: u32 = 4;
+
+/// Delays above this granularity in microseconds cannot meet the read slot window at all,
+/// as the read slot is sampled after two delays
+pub const RELAXED_GRANULARITY_US: u32 = 7;
+
+/// Outcome of [`OneWire::calibrate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationStatus {
+    /// The pin provides no timestamps, the timing is left as it is
+    Unmeasured,
+    /// The delay is fine grained enough for the standard timing
+    Precise { granularity_us: u32 },
+    /// The delay is coarse, the bus switched to [`Timing::RELAXED`]. Should be reported as
+    /// warning, communication might still be unreliable.
+    Relaxed { granularity_us: u32 },
+    /// The delay is too coarse to ever sample a read slot in time, the timing is left as
+    /// it is
+    TooCoarse { granularity_us: u32 },
+}
+
+impl<E: Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
+    /// Measures with [`OpenDrainOutput::timestamp_us`] how long the shortest delay
+    /// actually takes and switches to [`Timing::RELAXED`] if it is too coarse for the
+    /// standard timing.
+    pub fn calibrate(&mut self, delay: &mut impl DelayUs<u16>) -> CalibrationStatus {
+        let start = match self.output.timestamp_us() {
+            Some(start) => start,
+            None => return CalibrationStatus::Unmeasured,
+        };
+        for _ in 0..SAMPLES {
+            delay.delay_us(1);
+        }
+        let end = match self.output.timestamp_us() {
+            Some(end) => end,
+            None => return CalibrationStatus::Unmeasured,
+        };
+        let granularity_us = end.wrapping_sub(start) / SAMPLES;
+
+        if granularity_us <= PRECISE_GRANULARITY_US {
+            CalibrationStatus::Precise { granularity_us }
+        } else if granularity_us <= RELAXED_GRANULARITY_US {
+            // validated at compile time
+            self.timing = Timing::RELAXED;
+            CalibrationStatus::Relaxed { granularity_us }
+        } else {
+            CalibrationStatus::TooCoarse { granularity_us }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    struct Clock<'a> {
+        now: &'a Cell<u32>,
+        step: u32,
+    }
+
+    impl DelayUs<u16> for Clock<'_> {
+        fn delay_us(&mut self, us: u16) {
+            let us = u32::from(us).max(self.step);
+            self.now.set(self.now.get().wrapping_add(us));
+        }
+    }
+
+    struct TimedPin<'a>(&'a Cell<u32>);
+
+    impl OpenDrainOutput for TimedPin<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(true)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(false)
+        }
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn timestamp_us(&self) -> Option<u32> {
+            Some(self.0.get())
+        }
+    }
+
+    fn calibrate(step: u32) -> (CalibrationStatus, Timing) {
+        let now = Cell::new(u32::MAX - 20);
+        let mut wire = OneWire::new(TimedPin(&now), false);
+        let status = wire.calibrate(&mut Clock { now: &now, step });
+        (status, *wire.timing())
+    }
+
+    #[test]
+    fn test_calibrate() {
+        assert_eq!(
+            calibrate(1),
+            (
+                CalibrationStatus::Precise { granularity_us: 1 },
+                Timing::STANDARD
+            )
+        );
+        assert_eq!(
+            calibrate(5),
+            (
+                CalibrationStatus::Relaxed { granularity_us: 5 },
+                Timing::RELAXED
+            )
+        );
+        assert_eq!(
+            calibrate(20),
+            (
+                CalibrationStatus::TooCoarse { granularity_us: 20 },
+                Timing::STANDARD
+            )
+        );
+    }
+}
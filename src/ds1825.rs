@@ -0,0 +1,277 @@
+//! The DS1825 thermometer and the MAX31850/MAX31851 thermocouple converters share the family
+//! code 0x3B. Both report the level of their address pins AD0-AD3 - hard-wired on the board -
+//! so a device can be told apart by its location instead of its ROM address, but their
+//! scratchpads differ from the one of the DS18B20: the MAX31850 stores a thermocouple
+//! temperature in quarter degrees together with fault bits and the cold-junction temperature.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::check_crc8;
+use crate::family::Capabilities;
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OneWireMaster;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::Unit;
+
+pub const FAMILY_CODE: u8 = crate::family::DS1825;
+
+/// The longest conversion of both chips, the DS1825 at 12 bit resolution
+pub const CONVERSION_MS: u16 = 750;
+
+#[repr(u8)]
+pub enum Command {
+    Convert = 0x44,
+    ReadScratchpad = 0xBE,
+}
+
+/// Bits the MAX31850 sets in its scratchpad if the thermocouple is faulty
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Faults {
+    pub open_circuit: bool,
+    pub short_to_ground: bool,
+    pub short_to_vdd: bool,
+}
+
+/// The decoded scratchpad of either chip, told apart by the most significant bit of the
+/// configuration register, which is always 0 on the DS1825 and 1 on the MAX31850
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scratchpad {
+    DS1825 {
+        /// In sixteenths of a degree celsius, like the raw temperature of the DS18B20
+        temperature_raw: u16,
+        location: u8,
+    },
+    MAX31850 {
+        /// The thermocouple temperature in quarters of a degree celsius
+        thermocouple: i16,
+        /// The temperature of the cold junction in sixteenths of a degree celsius
+        cold_junction: i16,
+        location: u8,
+        /// `None` unless the fault bit is set
+        faults: Option<Faults>,
+    },
+}
+
+impl Faults {
+    /// The fault bits as stored in the lower bits of the scratchpad
+    pub fn bits(&self) -> u8 {
+        u8::from(self.open_circuit)
+            | u8::from(self.short_to_ground) << 1
+            | u8::from(self.short_to_vdd) << 2
+    }
+}
+
+impl Scratchpad {
+    pub fn decode(bytes: &[u8; 9]) -> Self {
+        let [low, high, cj_low, cj_high, config, ..] = *bytes;
+        let location = config & 0x0F;
+        if config & 0x80 == 0 {
+            return Scratchpad::DS1825 {
+                temperature_raw: u16::from_le_bytes([low, high]),
+                location,
+            };
+        }
+        Scratchpad::MAX31850 {
+            thermocouple: i16::from_le_bytes([low, high]) >> 2,
+            cold_junction: i16::from_le_bytes([cj_low, cj_high]) >> 4,
+            location,
+            faults: if low & 0x01 != 0 {
+                Some(Faults {
+                    open_circuit: cj_low & 0x01 != 0,
+                    short_to_ground: cj_low & 0x02 != 0,
+                    short_to_vdd: cj_low & 0x04 != 0,
+                })
+            } else {
+                None
+            },
+        }
+    }
+
+    /// The level of the address pins AD3-AD0 in the lower four bits
+    pub fn location(&self) -> u8 {
+        match self {
+            Scratchpad::DS1825 { location, .. } | Scratchpad::MAX31850 { location, .. } => {
+                *location
+            }
+        }
+    }
+
+    /// The measured temperature, `None` for a faulty thermocouple
+    pub fn temperature_millidegrees(&self) -> Option<i32> {
+        match self {
+            Scratchpad::DS1825 {
+                temperature_raw, ..
+            } => Some(i32::from(*temperature_raw as i16) * 625 / 10),
+            Scratchpad::MAX31850 {
+                faults: Some(_), ..
+            } => None,
+            Scratchpad::MAX31850 { thermocouple, .. } => Some(i32::from(*thermocouple) * 250),
+        }
+    }
+}
+
+pub struct DS1825 {
+    device: Device,
+}
+
+impl DS1825 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: false,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS1825, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS1825 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Starts a conversion, returns the milliseconds to wait until it finished
+    pub fn measure_temperature<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<W::Error>> {
+        wire.reset_select_write_only(delay, &self.device, &[Command::Convert as u8])?;
+        Ok(CONVERSION_MS)
+    }
+
+    /// Reads the scratchpad and checks its CRC
+    pub fn read_scratchpad<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Scratchpad, Error<W::Error>> {
+        let mut bytes = [0u8; 9];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadScratchpad as u8],
+            &mut bytes[..],
+        )?;
+        let [data @ .., crc] = bytes;
+        check_crc8(&data, crc)?;
+        Ok(Scratchpad::decode(&bytes))
+    }
+}
+
+/// The raw values are in sixteenths of a degree celsius for both chips, the thermocouple
+/// temperature of the MAX31850 is scaled up accordingly. A faulty thermocouple fails the read
+/// with [`Error::CommandFailed`] and its [`Faults::bits`].
+impl Sensor for DS1825 {
+    fn family_code() -> u8 {
+        FAMILY_CODE
+    }
+
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Celsius
+    }
+
+    fn raw_to_milli(&self, raw: u16) -> i32 {
+        i32::from(raw as i16) * 625 / 10
+    }
+
+    fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        self.measure_temperature(wire, delay)
+    }
+
+    #[cfg(not(feature = "no-float"))]
+    fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<f32, Error<O::Error>> {
+        self.read_measurement_raw(wire, delay)
+            .map(|raw| raw as i16 as f32 / 16_f32)
+    }
+
+    fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        match self.read_scratchpad(wire, delay)? {
+            Scratchpad::DS1825 {
+                temperature_raw, ..
+            } => Ok(temperature_raw),
+            Scratchpad::MAX31850 {
+                faults: Some(faults),
+                ..
+            } => Err(Error::CommandFailed(faults.bits())),
+            Scratchpad::MAX31850 { thermocouple, .. } => Ok((thermocouple << 2) as u16),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_crc(mut bytes: [u8; 9]) -> [u8; 9] {
+        bytes[8] = crate::compute_partial_crc8(0, &bytes[..8]);
+        bytes
+    }
+
+    #[test]
+    fn test_decode_ds1825() {
+        let scratchpad = Scratchpad::decode(&with_crc([
+            0x72, 0x01, 0x4b, 0x46, 0x75, 0xff, 0x0e, 0x10, 0,
+        ]));
+        assert_eq!(5, scratchpad.location());
+        assert_eq!(Some(23_125), scratchpad.temperature_millidegrees());
+    }
+
+    #[test]
+    fn test_decode_max31850() {
+        let mut bytes = with_crc([0x90, 0x01, 0x10, 0x15, 0xF3, 0xff, 0xff, 0xff, 0]);
+        let scratchpad = Scratchpad::decode(&bytes);
+        assert_eq!(
+            Scratchpad::MAX31850 {
+                thermocouple: 100,
+                cold_junction: 337,
+                location: 3,
+                faults: None,
+            },
+            scratchpad
+        );
+        assert_eq!(Some(25_000), scratchpad.temperature_millidegrees());
+
+        bytes[0] |= 0x01;
+        bytes[2] |= 0x01;
+        let scratchpad = Scratchpad::decode(&bytes);
+        assert_eq!(None, scratchpad.temperature_millidegrees());
+        assert!(matches!(
+            scratchpad,
+            Scratchpad::MAX31850 {
+                faults: Some(Faults {
+                    open_circuit: true,
+                    ..
+                }),
+                ..
+            }
+        ));
+    }
+}
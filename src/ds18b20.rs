@@ -1,15 +1,28 @@
 use byteorder::ByteOrder;
 use byteorder::LittleEndian;
 use core::fmt::Debug;
+use core::task::Poll;
+use hal::blocking::delay::DelayMs;
 use hal::blocking::delay::DelayUs;
 
+#[cfg(feature = "async")]
+use crate::asynch::{AsyncOneWire, AsyncSensor, SlotDelay};
+use crate::family::Capabilities;
+use crate::ConditionalSearch;
 use crate::Error;
 use crate::OneWire;
+use crate::OneWireMaster;
 use crate::Sensor;
+use crate::Unit;
 use crate::{Device, OpenDrainOutput};
 use core::convert::Infallible;
 
-pub const FAMILY_CODE: u8 = 0x28;
+pub const FAMILY_CODE: u8 = crate::family::DS18B20;
+
+/// How long copying the scratchpad into the EEPROM takes at most
+pub const EEPROM_WRITE_MS: u16 = 10;
+/// How many read slots are polled for the end of a recall of the EEPROM
+pub const RECALL_POLLS: usize = 100;
 
 #[repr(u8)]
 pub enum Command {
@@ -22,7 +35,7 @@ pub enum Command {
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum MeasureResolution {
     TC8 = 0b0001_1111,
     TC4 = 0b0011_1111,
@@ -31,6 +44,14 @@ pub enum MeasureResolution {
 }
 
 impl MeasureResolution {
+    /// All resolutions, ordered from the fastest to the slowest conversion
+    pub const ALL: [MeasureResolution; 4] = [
+        MeasureResolution::TC8,
+        MeasureResolution::TC4,
+        MeasureResolution::TC2,
+        MeasureResolution::TC,
+    ];
+
     pub fn time_ms(&self) -> u16 {
         match self {
             MeasureResolution::TC8 => 94,
@@ -41,12 +62,30 @@ impl MeasureResolution {
     }
 }
 
+/// Alarm thresholds in whole degrees celsius, the device answers a conditional search if
+/// the last measured temperature is higher than `high` or lower than or equal to `low`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlarmThresholds {
+    pub high: i8,
+    pub low: i8,
+}
+
 pub struct DS18B20 {
     device: Device,
     resolution: MeasureResolution,
+    offset: i16,
 }
 
 impl DS18B20 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: false,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
     pub fn new(device: Device) -> Result<DS18B20, Error<Infallible>> {
         if device.address[0] != FAMILY_CODE {
             Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
@@ -54,6 +93,7 @@ impl DS18B20 {
             Ok(DS18B20 {
                 device,
                 resolution: MeasureResolution::TC,
+                offset: 0,
             })
         }
     }
@@ -66,36 +106,355 @@ impl DS18B20 {
         DS18B20 {
             device,
             resolution: MeasureResolution::TC,
+            offset: 0,
         }
     }
 
-    pub fn measure_temperature<O: OpenDrainOutput>(
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn resolution(&self) -> MeasureResolution {
+        self.resolution
+    }
+
+    /// Writes the resolution into the configuration register, keeping the alarm
+    /// thresholds. Like them it is lost on power loss unless copied into the EEPROM with
+    /// [`DS18B20::save_to_eeprom`].
+    pub fn set_resolution<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        resolution: MeasureResolution,
+    ) -> Result<(), Error<W::Error>> {
+        let mut buffer = [0u8; 9];
+        let [_, _, high, low, ..] = *self.read_scratchpad(wire, delay, &mut buffer)?.as_bytes();
+        self.write_registers(wire, delay, high, low, resolution as u8)?;
+        self.resolution = resolution;
+        Ok(())
+    }
+
+    /// Takes over the resolution from the configuration register, e.g. one set by a
+    /// previous run and restored from the EEPROM at power up
+    pub fn load_resolution<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<MeasureResolution, Error<W::Error>> {
+        let mut buffer = [0u8; 9];
+        if let Some(resolution) = self.read_scratchpad(wire, delay, &mut buffer)?.resolution() {
+            self.resolution = resolution;
+        }
+        Ok(self.resolution)
+    }
+
+    /// The calibration offset in sixteenths of a degree celsius (the resolution of the raw
+    /// temperature), which is added to the values read through [`Sensor`]
+    pub fn offset(&self) -> i16 {
+        self.offset
+    }
+
+    /// Trims the values read through [`Sensor`] against a reference, the readings of the
+    /// methods of the sensor itself (e.g. [`DS18B20::read_temperature`]) stay uncalibrated
+    pub fn set_offset(&mut self, offset: i16) {
+        self.offset = offset;
+    }
+
+    /// Loads the calibration offset stored in the user bytes by [`DS18B20::store_offset`]
+    pub fn load_offset<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<i16, Error<W::Error>> {
+        self.offset = i16::from_le_bytes(self.read_user_bytes(wire, delay)?);
+        Ok(self.offset)
+    }
+
+    /// Stores the calibration offset in the user bytes (see [`DS18B20::write_user_bytes`]),
+    /// which therefore can not be used for the alarm thresholds anymore
+    pub fn store_offset<W: OneWireMaster>(
         &self,
-        wire: &mut OneWire<O>,
+        wire: &mut W,
         delay: &mut impl DelayUs<u16>,
-    ) -> Result<MeasureResolution, Error<O::Error>> {
+    ) -> Result<(), Error<W::Error>> {
+        self.write_user_bytes(wire, delay, self.offset.to_le_bytes())
+    }
+
+    pub(crate) fn calibrated(&self, reading: TemperatureReading) -> u16 {
+        (reading.raw() as i16).saturating_add(self.offset) as u16
+    }
+
+    pub fn measure_temperature<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<MeasureResolution, Error<W::Error>> {
         wire.reset_select_write_only(delay, &self.device, &[Command::Convert as u8])?;
         Ok(self.resolution)
     }
 
-    pub fn read_temperature<O: OpenDrainOutput>(
+    pub fn read_temperature<W: OneWireMaster>(
         &self,
-        wire: &mut OneWire<O>,
+        wire: &mut W,
         delay: &mut impl DelayUs<u16>,
-    ) -> Result<u16, Error<O::Error>> {
+    ) -> Result<TemperatureReading, Error<W::Error>> {
+        let mut scratchpad = [0u8; 9];
+        Ok(self.read_scratchpad(wire, delay, &mut scratchpad)?.into())
+    }
+
+    /// Reads and decodes the scratchpad, see [`DS18B20::read_scratchpad`]
+    pub fn read_scratchpad_fields<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Scratchpad, Error<W::Error>> {
+        let mut buffer = [0u8; 9];
+        Ok(self.read_scratchpad(wire, delay, &mut buffer)?.into())
+    }
+
+    /// Like [`DS18B20::measure_temperature`], but the sensor is only handed back together
+    /// with a temperature read after the conversion finished, so an outdated value (or the
+    /// 85 °C of the power-on reset) can not be read by accident
+    pub fn measure<W: OneWireMaster>(
+        self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Measuring<DS18B20>, (DS18B20, Error<W::Error>)> {
+        match self.measure_temperature(wire, delay) {
+            Ok(resolution) => Ok(Measuring {
+                sensor: self,
+                resolution,
+            }),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Polls for the end of the conversion started by [`DS18B20::measure_temperature`]
+    /// without waiting for its worst case duration: returns [`Poll::Pending`] while the
+    /// sensor is still converting and reads the temperature once it is done. Has to be
+    /// called right after starting the conversion without other bus activity in between,
+    /// and only works for sensors powered through VDD: a parasite powered sensor needs the
+    /// wire held high instead of being polled.
+    pub fn poll_temperature<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Poll<TemperatureReading>, Error<W::Error>> {
+        // the sensor answers read slots with 0 while converting
+        if !wire.read_bit(delay)? {
+            return Ok(Poll::Pending);
+        }
+        self.read_temperature(wire, delay).map(Poll::Ready)
+    }
+
+    /// Waits for the end of the conversion started by [`DS18B20::measure_temperature`] by
+    /// polling a read slot every millisecond, which usually ends well before the worst case
+    /// duration of [`MeasureResolution::time_ms`]. The same restrictions as for
+    /// [`DS18B20::poll_temperature`] apply. Fails with [`Error::Timeout`] if the sensor is
+    /// still converting after `timeout_ms`.
+    pub fn wait_for_conversion<W: OneWireMaster, D: DelayUs<u16> + DelayMs<u16>>(
+        &self,
+        wire: &mut W,
+        delay: &mut D,
+        timeout_ms: u16,
+    ) -> Result<(), Error<W::Error>> {
+        for _ in 0..=timeout_ms {
+            if wire.read_bit(delay)? {
+                return Ok(());
+            }
+            delay.delay_ms(1);
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Reads the scratchpad into `buffer` and checks its CRC, the returned view decodes
+    /// the fields on access
+    pub fn read_scratchpad<'b, W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        buffer: &'b mut [u8; 9],
+    ) -> Result<ScratchpadView<'b>, Error<W::Error>> {
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadScratchpad as u8],
+            &mut buffer[..],
+        )?;
+        let view = ScratchpadView::new(buffer);
+        view.ensure_correct_crc()?;
+        Ok(view)
+    }
+
+    /// See [`DS18B20::measure_temperature`]
+    #[cfg(feature = "async")]
+    pub async fn measure_temperature_async<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<MeasureResolution, Error<O::Error>> {
+        wire.reset_select_write_only(delay, &self.device, &[Command::Convert as u8])
+            .await?;
+        Ok(self.resolution)
+    }
+
+    /// See [`DS18B20::read_temperature`]
+    #[cfg(feature = "async")]
+    pub async fn read_temperature_async<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<TemperatureReading, Error<O::Error>> {
         let mut scratchpad = [0u8; 9];
         wire.reset_select_write_read(
             delay,
             &self.device,
             &[Command::ReadScratchpad as u8],
             &mut scratchpad[..],
-        )?;
-        super::ensure_correct_rcr8(&self.device, &scratchpad[..8], scratchpad[8])?;
-        Ok(DS18B20::read_temperature_from_scratchpad(&scratchpad))
+        )
+        .await?;
+        let view = ScratchpadView::new(&scratchpad);
+        view.ensure_correct_crc()?;
+        Ok(view.into())
     }
 
-    fn read_temperature_from_scratchpad(scratchpad: &[u8]) -> u16 {
-        LittleEndian::read_u16(&scratchpad[0..2])
+    /// Writes the thresholds of the alarm search (see [`crate::OneWire::search_next_alarmed`])
+    /// into the scratchpad, they are lost on power loss unless copied into the EEPROM
+    pub fn set_alarm_thresholds<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        thresholds: AlarmThresholds,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_scratchpad(wire, delay, thresholds.high as u8, thresholds.low as u8)
+    }
+
+    pub fn read_alarm_thresholds<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<AlarmThresholds, Error<W::Error>> {
+        let mut buffer = [0u8; 9];
+        Ok(self
+            .read_scratchpad(wire, delay, &mut buffer)?
+            .alarm_thresholds())
+    }
+
+    /// Writes two bytes of user data into the bytes of the alarm thresholds, which are free
+    /// for other uses if the alarm search is not used. Like the thresholds, they are lost on
+    /// power loss unless copied into the EEPROM with [`DS18B20::save_to_eeprom`].
+    pub fn write_user_bytes<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        bytes: [u8; 2],
+    ) -> Result<(), Error<W::Error>> {
+        let [high, low] = bytes;
+        self.write_scratchpad(wire, delay, high, low)
+    }
+
+    pub fn read_user_bytes<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<[u8; 2], Error<W::Error>> {
+        let mut buffer = [0u8; 9];
+        let [_, _, high, low, ..] = *self.read_scratchpad(wire, delay, &mut buffer)?.as_bytes();
+        Ok([high, low])
+    }
+
+    /// Copies the alarm thresholds and the configuration from the scratchpad into the
+    /// EEPROM and waits until they are written. With a [`OneWire`] in parasite mode, the wire
+    /// is held high (and a strong pull-up enabled) until the next bus activity, as the write
+    /// fails if the sensor runs out of power.
+    pub fn save_to_eeprom<W: OneWireMaster, D: DelayUs<u16> + DelayMs<u16>>(
+        &self,
+        wire: &mut W,
+        delay: &mut D,
+    ) -> Result<(), Error<W::Error>> {
+        wire.reset_select_write_only(delay, &self.device, &[Command::CopyScratchpad as u8])?;
+        delay.delay_ms(EEPROM_WRITE_MS);
+        Ok(())
+    }
+
+    /// Restores the alarm thresholds and the configuration from the EEPROM into the
+    /// scratchpad, e.g. after a brownout, and polls until the sensor signals completion
+    pub fn recall_eeprom<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            wire.write_bytes(delay, &[Command::RecallE2 as u8])?;
+            // the sensor answers read slots with 0 until the recall is done
+            for _ in 0..RECALL_POLLS {
+                if wire.read_bit(delay)? {
+                    return Ok(());
+                }
+            }
+            Err(Error::Timeout)
+        })
+    }
+
+    /// Writes the bytes of the alarm thresholds, keeping the configuration the sensor holds
+    fn write_scratchpad<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        high: u8,
+        low: u8,
+    ) -> Result<(), Error<W::Error>> {
+        let mut buffer = [0u8; 9];
+        let config = self.read_scratchpad(wire, delay, &mut buffer)?.config();
+        self.write_registers(wire, delay, high, low, config)
+    }
+
+    fn write_registers<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        high: u8,
+        low: u8,
+        config: u8,
+    ) -> Result<(), Error<W::Error>> {
+        wire.reset_select_write_only(
+            delay,
+            &self.device,
+            &[Command::WriteScratchpad as u8, high, low, config],
+        )
+    }
+
+    /// Asks this sensor whether it is parasite powered, so buses mixing parasite and
+    /// externally powered sensors can be handled per sensor
+    pub fn is_parasite_powered<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<bool, Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            wire.write_bytes(delay, &[Command::ReadPowerSupply as u8])?;
+            // parasite powered devices pull the bus low during the read slot
+            Ok(!wire.read_bit(delay)?)
+        })
+    }
+}
+
+impl ConditionalSearch for DS18B20 {
+    type Condition = AlarmThresholds;
+
+    /// Writes the thresholds into the scratchpad (they are lost on power loss unless
+    /// copied to the EEPROM)
+    fn configure_conditional_search<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        condition: &AlarmThresholds,
+    ) -> Result<(), Error<O::Error>> {
+        self.set_alarm_thresholds(wire, delay, *condition)
     }
 }
 
@@ -104,6 +463,18 @@ impl Sensor for DS18B20 {
         FAMILY_CODE
     }
 
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Celsius
+    }
+
+    fn raw_to_milli(&self, raw: u16) -> i32 {
+        raw_to_millidegrees(raw)
+    }
+
     fn start_measurement<O: OpenDrainOutput>(
         &self,
         wire: &mut OneWire<O>,
@@ -112,13 +483,14 @@ impl Sensor for DS18B20 {
         Ok(self.measure_temperature(wire, delay)?.time_ms())
     }
 
+    #[cfg(not(feature = "no-float"))]
     fn read_measurement<O: OpenDrainOutput>(
         &self,
         wire: &mut OneWire<O>,
         delay: &mut impl DelayUs<u16>,
     ) -> Result<f32, Error<O::Error>> {
         self.read_temperature(wire, delay)
-            .map(|t| t as i16 as f32 / 16_f32)
+            .map(|t| self.calibrated(t) as i16 as f32 / 16_f32)
     }
 
     fn read_measurement_raw<O: OpenDrainOutput>(
@@ -127,23 +499,791 @@ impl Sensor for DS18B20 {
         delay: &mut impl DelayUs<u16>,
     ) -> Result<u16, Error<O::Error>> {
         self.read_temperature(wire, delay)
+            .map(|t| self.calibrated(t))
     }
 }
 
+#[cfg(feature = "async")]
+impl AsyncSensor for DS18B20 {
+    async fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<u16, Error<O::Error>> {
+        Ok(self.measure_temperature_async(wire, delay).await?.time_ms())
+    }
+
+    #[cfg(not(feature = "no-float"))]
+    async fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<f32, Error<O::Error>> {
+        self.read_temperature_async(wire, delay)
+            .await
+            .map(|t| self.calibrated(t) as i16 as f32 / 16_f32)
+    }
+
+    async fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        wire: &mut AsyncOneWire<O>,
+        delay: &mut impl SlotDelay,
+    ) -> Result<u16, Error<O::Error>> {
+        self.read_temperature_async(wire, delay)
+            .await
+            .map(|t| self.calibrated(t))
+    }
+}
+
+/// Periodically samples the temperature of a list of sensors, handling the
+/// convert/wait/read cycle internally
+pub struct TemperatureSampler<'a> {
+    sensors: &'a [DS18B20],
+    interval_ms: u16,
+    started: bool,
+}
+
+impl<'a> TemperatureSampler<'a> {
+    /// The interval is the time between the start of two consecutive samples
+    pub fn new(sensors: &'a [DS18B20], interval_ms: u16) -> Self {
+        TemperatureSampler {
+            sensors,
+            interval_ms,
+            started: false,
+        }
+    }
+
+    pub fn sensors(&self) -> &'a [DS18B20] {
+        self.sensors
+    }
+
+    /// Waits for the next sample to be due, measures the temperature of all sensors
+    /// and stores the raw values in the slot of `readings` with the index of the sensor.
+    /// Sensors that could not be read - e.g. because of a CRC mismatch - are set to `None`.
+    pub fn next<O: OpenDrainOutput, D: DelayUs<u16> + DelayMs<u16>>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut D,
+        readings: &mut [Option<u16>],
+    ) -> Result<(), Error<O::Error>> {
+        let conversion_ms = self
+            .sensors
+            .iter()
+            .map(|sensor| sensor.resolution.time_ms())
+            .max()
+            .unwrap_or(0);
+
+        if self.started {
+            delay.delay_ms(self.interval_ms.saturating_sub(conversion_ms));
+        }
+        self.started = true;
+
+        for sensor in self.sensors {
+            sensor.measure_temperature(wire, delay)?;
+        }
+        delay.delay_ms(conversion_ms);
+
+        for (sensor, reading) in self.sensors.iter().zip(readings.iter_mut()) {
+            *reading = sensor.read_temperature(wire, delay).ok().map(|t| t.raw());
+        }
+        Ok(())
+    }
+}
+
+/// A conversion in progress, see [`DS18B20::measure`]
+#[must_use]
+pub struct Measuring<S> {
+    sensor: S,
+    resolution: MeasureResolution,
+}
+
+impl Measuring<DS18B20> {
+    pub fn resolution(&self) -> MeasureResolution {
+        self.resolution
+    }
+
+    /// Abandons the conversion
+    pub fn into_sensor(self) -> DS18B20 {
+        self.sensor
+    }
+
+    /// Waits for the conversion to finish and reads the temperature
+    pub fn read_temperature<W: OneWireMaster, D: DelayUs<u16> + DelayMs<u16>>(
+        self,
+        wire: &mut W,
+        delay: &mut D,
+    ) -> (DS18B20, Result<TemperatureReading, Error<W::Error>>) {
+        delay.delay_ms(self.resolution.time_ms());
+        let temperature = self.sensor.read_temperature(wire, delay);
+        (self.sensor, temperature)
+    }
+}
+
+/// Returns the resolutions used by the given sensors in the order their conversions
+/// finish when started at the same time
+pub fn conversion_schedule(sensors: &[DS18B20]) -> impl Iterator<Item = MeasureResolution> + '_ {
+    MeasureResolution::ALL
+        .iter()
+        .copied()
+        .filter(move |resolution| sensors.iter().any(|s| s.resolution == *resolution))
+}
+
+/// Starts the conversion of every thermometer on the bus with a single broadcast, other
+/// devices on the bus receive the command as well and must not interpret it differently
+pub fn measure_temperature_all<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+) -> Result<(), Error<W::Error>> {
+    wire.reset_skip_write_only(delay, &[Command::Convert as u8])
+}
+
+/// Converts the temperature of all thermometers on the bus at once with
+/// [`measure_temperature_all`], waits for the slowest resolution of the given sensors and
+/// reads them. The raw values are stored in the slot of `readings` with the index of the
+/// sensor, sensors that could not be read are set to `None`.
+pub fn measure_all<W: OneWireMaster, D: DelayUs<u16> + DelayMs<u16>>(
+    wire: &mut W,
+    delay: &mut D,
+    sensors: &[DS18B20],
+    readings: &mut [Option<u16>],
+) -> Result<(), Error<W::Error>> {
+    measure_temperature_all(wire, delay)?;
+    if let Some(resolution) = conversion_schedule(sensors).last() {
+        delay.delay_ms(resolution.time_ms());
+    }
+    for (sensor, reading) in sensors.iter().zip(readings.iter_mut()) {
+        *reading = sensor.read_temperature(wire, delay).ok().map(|t| t.raw());
+    }
+    Ok(())
+}
+
+/// Starts the conversion of all sensors and reads each group of sensors with the same
+/// resolution as soon as its conversion has finished, so fast low-resolution sensors
+/// do not have to wait for slow high-resolution ones. The raw values are stored in the
+/// slot of `readings` with the index of the sensor, sensors that could not be read are
+/// set to `None`. Returns the total time waited in milliseconds.
+pub fn measure_grouped<W: OneWireMaster, D: DelayUs<u16> + DelayMs<u16>>(
+    wire: &mut W,
+    delay: &mut D,
+    sensors: &[DS18B20],
+    readings: &mut [Option<u16>],
+) -> Result<u16, Error<W::Error>> {
+    for sensor in sensors {
+        sensor.measure_temperature(wire, delay)?;
+    }
+
+    let mut elapsed_ms = 0;
+    for resolution in conversion_schedule(sensors) {
+        delay.delay_ms(resolution.time_ms().saturating_sub(elapsed_ms));
+        elapsed_ms = resolution.time_ms();
+
+        for (sensor, reading) in sensors.iter().zip(readings.iter_mut()) {
+            if sensor.resolution == resolution {
+                *reading = sensor.read_temperature(wire, delay).ok().map(|t| t.raw());
+            }
+        }
+    }
+    Ok(elapsed_ms)
+}
+
+/// Queries the power supply of each sensor and stores whether it is parasite powered in
+/// the slot of `parasite` with the index of the sensor. Returns the number of parasite
+/// powered sensors or [`Error::ParasiteModeMismatch`] if the parasite mode of the
+/// [`OneWire`] does not match the hardware - `parasite` is filled in either case.
+pub fn audit_power_supply<O: OpenDrainOutput>(
+    wire: &mut OneWire<O>,
+    delay: &mut impl DelayUs<u16>,
+    sensors: &[DS18B20],
+    parasite: &mut [bool],
+) -> Result<usize, Error<O::Error>> {
+    let mut count = 0;
+    for (sensor, parasite) in sensors.iter().zip(parasite.iter_mut()) {
+        *parasite = sensor.is_parasite_powered(wire, delay)?;
+        if *parasite {
+            count += 1;
+        }
+    }
+    if wire.parasite_mode() != (count > 0) {
+        Err(Error::ParasiteModeMismatch(wire.parasite_mode(), count > 0))
+    } else {
+        Ok(count)
+    }
+}
+
+/// Converts a raw temperature value into millidegrees celsius
+pub fn raw_to_millidegrees(temperature: u16) -> i32 {
+    i32::from(temperature as i16) * 625 / 10
+}
+
+fn read_raw_scratchpad<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    device: &Device,
+) -> Result<[u8; 9], Error<W::Error>> {
+    let mut scratchpad = [0u8; 9];
+    wire.reset_select_write_read(
+        delay,
+        device,
+        &[Command::ReadScratchpad as u8],
+        &mut scratchpad[..],
+    )?;
+    Ok(scratchpad)
+}
+
+/// Typed access to a scratchpad without copying it out of the read buffer
+#[derive(Debug, Clone, Copy)]
+pub struct ScratchpadView<'a> {
+    bytes: &'a [u8; 9],
+}
+
+impl<'a> ScratchpadView<'a> {
+    pub fn new(bytes: &'a [u8; 9]) -> Self {
+        ScratchpadView { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8; 9] {
+        self.bytes
+    }
+
+    pub fn raw_temperature(&self) -> u16 {
+        LittleEndian::read_u16(self.bytes)
+    }
+
+    pub fn temperature_millidegrees(&self) -> i32 {
+        raw_to_millidegrees(self.raw_temperature())
+    }
+
+    /// The alarm thresholds, which share the bytes with the user data of the device
+    pub fn alarm_thresholds(&self) -> AlarmThresholds {
+        let [_, _, high, low, ..] = *self.bytes;
+        AlarmThresholds {
+            high: high as i8,
+            low: low as i8,
+        }
+    }
+
+    pub fn config(&self) -> u8 {
+        self.bytes[4]
+    }
+
+    /// The resolution in the configuration register, `None` for devices without one
+    /// (e.g. the DS18S20)
+    pub fn resolution(&self) -> Option<MeasureResolution> {
+        let bits = self.config() & 0x60 | 0x1F;
+        MeasureResolution::ALL
+            .iter()
+            .copied()
+            .find(|resolution| *resolution as u8 == bits)
+            .filter(|_| self.config() & 0x9F == 0x1F)
+    }
+
+    pub fn crc(&self) -> u8 {
+        self.bytes[8]
+    }
+
+    pub fn is_crc_valid(&self) -> bool {
+        super::compute_partial_crc8(0, &self.bytes[..8]) == self.crc()
+    }
+
+    pub fn ensure_correct_crc<E: Debug>(&self) -> Result<(), Error<E>> {
+        super::check_crc8(&self.bytes[..8], self.crc())
+    }
+}
+
+/// The decoded fields of a scratchpad, an owned alternative to [`ScratchpadView`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scratchpad {
+    pub temperature_raw: u16,
+    /// The high alarm threshold in whole degrees celsius
+    pub th: i8,
+    /// The low alarm threshold in whole degrees celsius
+    pub tl: i8,
+    pub config: u8,
+    pub resolution: Option<MeasureResolution>,
+    pub crc: u8,
+}
+
+impl Scratchpad {
+    pub fn temperature_millidegrees(&self) -> i32 {
+        raw_to_millidegrees(self.temperature_raw)
+    }
+
+    pub fn alarm_thresholds(&self) -> AlarmThresholds {
+        AlarmThresholds {
+            high: self.th,
+            low: self.tl,
+        }
+    }
+}
+
+impl From<ScratchpadView<'_>> for Scratchpad {
+    fn from(view: ScratchpadView<'_>) -> Self {
+        let thresholds = view.alarm_thresholds();
+        Scratchpad {
+            temperature_raw: view.raw_temperature(),
+            th: thresholds.high,
+            tl: thresholds.low,
+            config: view.config(),
+            resolution: view.resolution(),
+            crc: view.crc(),
+        }
+    }
+}
+
+/// A temperature read from the scratchpad, which still holds the power-on reset value of
+/// 85 °C if no conversion happened since the sensor was powered up, e.g. because a parasite
+/// powered sensor browned out during the conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemperatureReading {
+    raw: u16,
+    power_on_reset: bool,
+}
+
+impl TemperatureReading {
+    /// The temperature the scratchpad holds after a power-on reset
+    pub const POWER_ON_RESET_RAW: u16 = 0x0550;
+    /// The reserved byte 6 of the scratchpad after a power-on reset, a conversion of exactly
+    /// 85 °C sets it to `0x10` instead
+    const POWER_ON_RESET_RESERVED: u8 = 0x0C;
+
+    pub fn raw(&self) -> u16 {
+        self.raw
+    }
+
+    pub fn millidegrees(&self) -> i32 {
+        raw_to_millidegrees(self.raw)
+    }
+
+    /// Whether the value is the placeholder of the power-on reset instead of a measured
+    /// 85 °C, in which case the temperature is unknown
+    pub fn is_power_on_reset(&self) -> bool {
+        self.power_on_reset
+    }
+}
+
+impl From<ScratchpadView<'_>> for TemperatureReading {
+    fn from(view: ScratchpadView<'_>) -> Self {
+        let raw = view.raw_temperature();
+        TemperatureReading {
+            raw,
+            power_on_reset: raw == Self::POWER_ON_RESET_RAW
+                && view.as_bytes()[6] == Self::POWER_ON_RESET_RESERVED,
+        }
+    }
+}
+
+/// Family codes of thermometers sharing the 9 byte scratchpad layout of the DS18B20
+pub const THERMOMETER_FAMILIES: [u8; 5] = [
+    crate::family::DS18S20,
+    crate::family::DS1822,
+    crate::family::DS18B20,
+    crate::family::DS1825,
+    crate::family::DS28EA00,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScratchpadDump {
+    pub device: Device,
+    pub scratchpad: [u8; 9],
+    pub crc_valid: bool,
+}
+
+/// Reads the raw scratchpad of every thermometer in `devices` (see
+/// [`THERMOMETER_FAMILIES`]) into `out` - other devices are skipped - for remote
+/// diagnosis. Returns the number of entries written, which is limited by the length of
+/// `out`. Scratchpads with an invalid CRC are included and flagged.
+pub fn dump_scratchpads<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    devices: &[Device],
+    out: &mut [Option<ScratchpadDump>],
+) -> Result<usize, Error<W::Error>> {
+    let thermometers = devices
+        .iter()
+        .filter(|device| THERMOMETER_FAMILIES.contains(&device.family_code()));
+    let mut count = 0;
+    for (device, slot) in thermometers.zip(out.iter_mut()) {
+        let scratchpad = read_raw_scratchpad(wire, delay, device)?;
+        let crc_valid = ScratchpadView::new(&scratchpad).is_crc_valid();
+        *slot = Some(ScratchpadDump {
+            device: device.clone(),
+            scratchpad,
+            crc_valid,
+        });
+        count += 1;
+    }
+    Ok(count)
+}
+
 /// Split raw u16 value to two parts: integer and fraction N
 /// Original value may be calculated as: integer + fraction/10000
 pub fn split_temp(temperature: u16) -> (i16, i16) {
+    let abs = (temperature as i16).unsigned_abs();
+    let integer = (abs >> 4) as i16;
+    let fraction = ((abs & 0xF) * 625) as i16;
     if temperature < 0x8000 {
-        (temperature as i16 >> 4, (temperature as i16 & 0xF) * 625)
+        (integer, fraction)
     } else {
-        let abs = -(temperature as i16);
-        (-(abs >> 4), -625 * (abs & 0xF))
+        (-integer, -fraction)
+    }
+}
+
+/// Formats a raw temperature value as degrees celsius with four decimal places
+/// (like `23.0625`) without any floating point math
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayCelsius(pub u16);
+
+impl DisplayCelsius {
+    fn parts(&self) -> (&'static str, u16, u16) {
+        let value = self.0 as i16;
+        let sign = if value < 0 { "-" } else { "" };
+        let abs = value.unsigned_abs();
+        (sign, abs >> 4, (abs & 0xF) * 625)
+    }
+}
+
+impl core::fmt::Display for DisplayCelsius {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let (sign, integer, fraction) = self.parts();
+        write!(f, "{}{}.{:04}", sign, integer, fraction)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for DisplayCelsius {
+    fn format(&self, f: defmt::Formatter) {
+        let (sign, integer, fraction) = self.parts();
+        defmt::write!(
+            f,
+            "{=str}{=u16}.{=u16}{=u16}{=u16}{=u16}",
+            sign,
+            integer,
+            fraction / 1000,
+            fraction / 100 % 10,
+            fraction / 10 % 10,
+            fraction % 10
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::split_temp;
+    use super::Command;
+    use super::{raw_to_millidegrees, split_temp, DisplayCelsius};
+    use super::{AlarmThresholds, MeasureResolution, Scratchpad, ScratchpadView, DS18B20};
+    use crate::test_support::{Delay, Seeded, SimDelay, SimPin, Simulator};
+    use crate::{Device, Error, OneWire, OneWireMaster};
+    use core::convert::Infallible;
+    use core::task::Poll;
+    use hal::blocking::delay::DelayUs;
+
+    /// A sensor answering the function commands after being selected. Conversions and
+    /// EEPROM accesses keep it busy for `busy_slots` read slots.
+    struct FakeSensor {
+        scratchpad: [u8; 9],
+        eeprom: [u8; 3],
+        parasite: bool,
+        busy_slots: usize,
+        busy: usize,
+        byte: u8,
+        bits: usize,
+        command: Option<u8>,
+        read: Option<usize>,
+    }
+
+    impl FakeSensor {
+        fn new(busy_slots: usize) -> Self {
+            FakeSensor {
+                scratchpad: [0x72, 0x01, 0x4b, 0x46, 0x7f, 0xff, 0x0e, 0x10, 0x57],
+                eeprom: [0x4b, 0x46, 0x7f],
+                parasite: false,
+                busy_slots,
+                busy: 0,
+                byte: 0,
+                bits: 0,
+                command: None,
+                read: None,
+            }
+        }
+
+        fn update_crc(&mut self) {
+            self.scratchpad[8] = crate::compute_partial_crc8(0, &self.scratchpad[..8]);
+        }
+
+        /// Handles the `index`th byte after the reset, following the ROM command and address
+        fn receive(&mut self, index: usize, byte: u8) {
+            match (index, self.command) {
+                (0..=8, _) => {}
+                (9, _) => {
+                    self.command = Some(byte);
+                    match byte {
+                        0x44 => self.busy = self.busy_slots,
+                        0xBE => self.read = Some(0),
+                        0x48 => {
+                            self.eeprom.copy_from_slice(&self.scratchpad[2..5]);
+                            self.busy = self.busy_slots;
+                        }
+                        0xB8 => {
+                            self.scratchpad[2..5].copy_from_slice(&self.eeprom);
+                            self.update_crc();
+                            self.busy = self.busy_slots;
+                        }
+                        _ => {}
+                    }
+                }
+                (10..=12, Some(0x4E)) => {
+                    self.scratchpad[index - 8] = byte;
+                    self.update_crc();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    impl OneWireMaster for FakeSensor {
+        type Error = Infallible;
+
+        fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+            self.bits = 0;
+            self.command = None;
+            self.read = None;
+            Ok(true)
+        }
+
+        fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+            if let Some(bit) = self.read.as_mut() {
+                let value = self.scratchpad[*bit / 8] & (1 << (*bit % 8)) != 0;
+                *bit += 1;
+                return Ok(value);
+            }
+            if self.command == Some(0xB4) {
+                return Ok(!self.parasite);
+            }
+            if self.busy > 0 {
+                self.busy -= 1;
+                return Ok(false);
+            }
+            Ok(true)
+        }
+
+        fn write_bit(
+            &mut self,
+            _delay: &mut impl DelayUs<u16>,
+            high: bool,
+        ) -> Result<(), Error<Infallible>> {
+            self.byte = self.byte >> 1 | u8::from(high) << 7;
+            self.bits += 1;
+            if self.bits & 7 == 0 {
+                self.receive(self.bits / 8 - 1, self.byte);
+            }
+            Ok(())
+        }
+    }
+
+    fn sensor() -> DS18B20 {
+        DS18B20::new(Device {
+            address: [0x28, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_poll_temperature() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(2);
+        sensor.measure_temperature(&mut wire, &mut Delay).unwrap();
+        let mut poll = || {
+            sensor
+                .poll_temperature(&mut wire, &mut Delay)
+                .unwrap()
+                .map(|t| t.raw())
+        };
+        assert_eq!(Poll::Pending, poll());
+        assert_eq!(Poll::Pending, poll());
+        assert_eq!(Poll::Ready(0x0172), poll());
+    }
+
+    #[test]
+    fn test_wait_for_conversion() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(5);
+        let timeout = sensor.resolution().time_ms();
+        sensor.measure_temperature(&mut wire, &mut Delay).unwrap();
+        sensor
+            .wait_for_conversion(&mut wire, &mut Delay, timeout)
+            .unwrap();
+        assert_eq!(0, wire.busy);
+
+        sensor.measure_temperature(&mut wire, &mut Delay).unwrap();
+        assert!(matches!(
+            sensor.wait_for_conversion(&mut wire, &mut Delay, 3),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_measure() {
+        let mut wire = FakeSensor::new(0);
+        let measuring = sensor().measure(&mut wire, &mut Delay).ok().unwrap();
+        assert_eq!(MeasureResolution::TC, measuring.resolution());
+        let (sensor, temperature) = measuring.read_temperature(&mut wire, &mut Delay);
+        assert_eq!(0x0172, temperature.unwrap().raw());
+        assert_eq!(0x28, sensor.device().family_code());
+    }
+
+    #[test]
+    fn test_measure_all() {
+        let sensors = [sensor(), sensor()];
+        let mut wire = FakeSensor::new(0);
+        let mut readings = [None; 3];
+        super::measure_all(&mut wire, &mut Delay, &sensors, &mut readings).unwrap();
+        assert_eq!([Some(0x0172), Some(0x0172), None], readings);
+    }
+
+    #[test]
+    fn test_measure_grouped() {
+        let mut wire = FakeSensor::new(0);
+        let mut fast = sensor();
+        fast.set_resolution(&mut wire, &mut Delay, MeasureResolution::TC8)
+            .unwrap();
+        assert_eq!(MeasureResolution::TC8 as u8, wire.scratchpad[4]);
+        assert_eq!([0x4b, 0x46], wire.scratchpad[2..4]);
+
+        let sensors = [sensor(), fast];
+        let mut schedule = super::conversion_schedule(&sensors);
+        assert_eq!(Some(MeasureResolution::TC8), schedule.next());
+        assert_eq!(Some(MeasureResolution::TC), schedule.next());
+        assert_eq!(None, schedule.next());
+        let mut readings = [None; 2];
+        let waited =
+            super::measure_grouped(&mut wire, &mut Delay, &sensors, &mut readings).unwrap();
+        assert_eq!(MeasureResolution::TC.time_ms(), waited);
+        assert_eq!([Some(0x0172), Some(0x0172)], readings);
+
+        let mut restored = sensor();
+        assert_eq!(
+            MeasureResolution::TC8,
+            restored.load_resolution(&mut wire, &mut Delay).unwrap()
+        );
+        let waited =
+            super::measure_grouped(&mut wire, &mut Delay, &[restored], &mut readings).unwrap();
+        assert_eq!(MeasureResolution::TC8.time_ms(), waited);
+    }
+
+    #[test]
+    fn test_power_on_reset() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(0);
+        let reading = sensor.read_temperature(&mut wire, &mut Delay).unwrap();
+        assert!(!reading.is_power_on_reset());
+
+        wire.scratchpad[..2].copy_from_slice(&[0x50, 0x05]);
+        wire.scratchpad[6] = 0x0C;
+        wire.update_crc();
+        let reading = sensor.read_temperature(&mut wire, &mut Delay).unwrap();
+        assert_eq!(85_000, reading.millidegrees());
+        assert!(reading.is_power_on_reset());
+
+        // a conversion of exactly 85 °C
+        wire.scratchpad[6] = 0x10;
+        wire.update_crc();
+        let reading = sensor.read_temperature(&mut wire, &mut Delay).unwrap();
+        assert!(!reading.is_power_on_reset());
+    }
+
+    #[test]
+    fn test_alarm_thresholds() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(0);
+        let thresholds = AlarmThresholds { high: 30, low: -10 };
+        sensor
+            .set_alarm_thresholds(&mut wire, &mut Delay, thresholds)
+            .unwrap();
+        assert_eq!(
+            thresholds,
+            sensor.read_alarm_thresholds(&mut wire, &mut Delay).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_user_bytes() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(0);
+        sensor
+            .write_user_bytes(&mut wire, &mut Delay, [0xCA, 0xFE])
+            .unwrap();
+        assert_eq!(
+            [0xCA, 0xFE],
+            sensor.read_user_bytes(&mut wire, &mut Delay).unwrap()
+        );
+        sensor.save_to_eeprom(&mut wire, &mut Delay).unwrap();
+        assert_eq!([0xCA, 0xFE], wire.eeprom[..2]);
+    }
+
+    #[test]
+    fn test_offset() {
+        let mut sensor = sensor();
+        let mut wire = FakeSensor::new(0);
+        sensor.set_offset(-8);
+        sensor.store_offset(&mut wire, &mut Delay).unwrap();
+        sensor.set_offset(0);
+        assert_eq!(-8, sensor.load_offset(&mut wire, &mut Delay).unwrap());
+
+        let reading = sensor.read_temperature(&mut wire, &mut Delay).unwrap();
+        assert_eq!(0x0172, reading.raw());
+        assert_eq!(0x016A, sensor.calibrated(reading));
+    }
+
+    #[test]
+    fn test_save_to_eeprom() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(0);
+        let thresholds = AlarmThresholds { high: 40, low: 5 };
+        sensor
+            .set_alarm_thresholds(&mut wire, &mut Delay, thresholds)
+            .unwrap();
+        sensor.save_to_eeprom(&mut wire, &mut Delay).unwrap();
+        assert_eq!([40, 5, MeasureResolution::TC as u8], wire.eeprom);
+
+        // the configuration held by the sensor is kept, not the one assumed by the driver
+        wire.scratchpad[4] = MeasureResolution::TC4 as u8;
+        wire.update_crc();
+        sensor
+            .write_user_bytes(&mut wire, &mut Delay, [1, 2])
+            .unwrap();
+        sensor.save_to_eeprom(&mut wire, &mut Delay).unwrap();
+        assert_eq!([1, 2, MeasureResolution::TC4 as u8], wire.eeprom);
+    }
+
+    #[test]
+    fn test_recall_eeprom() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(3);
+        wire.eeprom = [50, 10, MeasureResolution::TC8 as u8];
+        sensor.recall_eeprom(&mut wire, &mut Delay).unwrap();
+        assert_eq!(0, wire.busy);
+        let scratchpad = sensor
+            .read_scratchpad_fields(&mut wire, &mut Delay)
+            .unwrap();
+        assert_eq!((50, 10), (scratchpad.th, scratchpad.tl));
+        assert_eq!(Some(MeasureResolution::TC8), scratchpad.resolution);
+
+        wire.busy_slots = super::RECALL_POLLS + 1;
+        assert!(matches!(
+            sensor.recall_eeprom(&mut wire, &mut Delay),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn test_is_parasite_powered() {
+        let sensor = sensor();
+        let mut wire = FakeSensor::new(0);
+        assert!(!sensor.is_parasite_powered(&mut wire, &mut Delay).unwrap());
+        wire.parasite = true;
+        assert!(sensor.is_parasite_powered(&mut wire, &mut Delay).unwrap());
+    }
+
     #[test]
     fn test_temp_conv() {
         assert_eq!(split_temp(0x07d0), (125, 0));
@@ -156,5 +1296,76 @@ mod tests {
         assert_eq!(split_temp(0xFF5E), (-10, -1250)); // -10.125
         assert_eq!(split_temp(0xFE6F), (-25, -625)); // -25.0625
         assert_eq!(split_temp(0xFC90), (-55, 0)); // -55
+        assert_eq!(split_temp(0x8000), (-2048, 0)); // out of range, but must not panic
+    }
+
+    #[test]
+    fn test_scratchpad_view() {
+        let mut bytes = [0x91, 0x01, 0x4B, 0x46, 0x5F, 0xFF, 0x0F, 0x10, 0x00];
+        bytes[8] = crate::compute_partial_crc8(0, &bytes[..8]);
+        let view = ScratchpadView::new(&bytes);
+        assert_eq!(0x0191, view.raw_temperature());
+        assert_eq!(25_062, view.temperature_millidegrees());
+        assert_eq!(
+            AlarmThresholds { high: 75, low: 70 },
+            view.alarm_thresholds()
+        );
+        assert_eq!(Some(MeasureResolution::TC2), view.resolution());
+        assert!(view.is_crc_valid());
+
+        let scratchpad = Scratchpad::from(view);
+        assert_eq!(0x0191, scratchpad.temperature_raw);
+        assert_eq!((75, 70), (scratchpad.th, scratchpad.tl));
+        assert_eq!(Some(MeasureResolution::TC2), scratchpad.resolution);
+
+        bytes[0] ^= 0x01;
+        assert!(!ScratchpadView::new(&bytes).is_crc_valid());
+    }
+
+    #[test]
+    fn test_raw_to_millidegrees() {
+        assert_eq!(raw_to_millidegrees(0x07d0), 125_000);
+        assert_eq!(raw_to_millidegrees(0x0191), 25_062);
+        assert_eq!(raw_to_millidegrees(0xfff8), -500);
+        assert_eq!(raw_to_millidegrees(0xFC90), -55_000);
+    }
+
+    #[test]
+    fn test_display_celsius() {
+        use core::fmt::Write;
+
+        struct Buffer([u8; 16], usize);
+        impl Write for Buffer {
+            fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                self.0[self.1..self.1 + s.len()].copy_from_slice(s.as_bytes());
+                self.1 += s.len();
+                Ok(())
+            }
+        }
+
+        for (raw, expected) in [
+            (0x0191, "25.0625"),
+            (0x0550, "85.0000"),
+            (0xfff8, "-0.5000"),
+            (0xFC90, "-55.0000"),
+        ] {
+            let mut buffer = Buffer([0u8; 16], 0);
+            write!(buffer, "{}", DisplayCelsius(raw)).unwrap();
+            assert_eq!(&buffer.0[..buffer.1], expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_measure_temperature_all() {
+        let sim = Simulator::<2>::new(&mut Seeded {
+            family: crate::family::DS18B20,
+            state: 5,
+        });
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut delay = SimDelay(&sim);
+        super::measure_temperature_all(&mut wire, &mut delay).unwrap();
+        assert_eq!([Some(Command::Convert as u8); 2], sim.borrow().functions());
+        // both conversions are in progress
+        assert!(!OneWireMaster::read_bit(&mut wire, &mut delay).unwrap());
     }
 }
@@ -0,0 +1,196 @@
+//! The DS2405 addressable switch toggles its PIO on every Match ROM command addressing it,
+//! so selecting it just to read its state - as done for every other device - flips the
+//! switch. This driver reads the state through the Search ROM command instead, which does
+//! not toggle, and only toggles after comparing the state before and after.
+//!
+//! The later DS2406 does not toggle on Match ROM and needs none of this.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::Command;
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::ADDRESS_BITS;
+
+pub const FAMILY_CODE: u8 = crate::family::DS2405;
+
+pub struct DS2405 {
+    device: Device,
+}
+
+impl DS2405 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: false,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2405, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2405 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reads the logic level at the PIO pin without toggling it, `false` if the output
+    /// transistor is on or something else pulls the pin low
+    pub fn read_level<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<bool, Error<O::Error>> {
+        wire.transaction(|wire| {
+            if !wire.reset(delay)? {
+                return Err(Error::NoPresencePulse);
+            }
+            // walk the search path of this device only, all others drop out
+            wire.write_byte_powered(delay, Command::SearchNext as u8, false)?;
+            for bit in 0..ADDRESS_BITS {
+                let bit0 = wire.read_bit(delay)?;
+                let bit1 = wire.read_bit(delay)?;
+                let own = crate::DeviceSearch::is_bit_set(&self.device.address, bit);
+                if (own && bit1) || (!own && bit0) {
+                    // no device with this bit value answered
+                    return Err(Error::NoPresencePulse);
+                }
+                wire.write_bit(delay, own)?;
+            }
+            Ok(wire.read_bit(delay)?)
+        })
+    }
+
+    /// Toggles the PIO, returns whether the output transistor is on afterwards
+    pub fn toggle<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<bool, Error<O::Error>> {
+        wire.transaction(|wire| {
+            if !wire.reset(delay)? {
+                return Err(Error::NoPresencePulse);
+            }
+            wire.select(delay, &self.device)?;
+            // the device answers read slots with 0 while the transistor is on
+            Ok(!wire.read_bit(delay)?)
+        })
+    }
+
+    /// Whether the output transistor is on, judged by the level at the PIO pin and so also
+    /// `true` if something else pulls it low
+    pub fn get<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<bool, Error<O::Error>> {
+        Ok(!self.read_level(wire, delay)?)
+    }
+
+    /// Switches the output transistor on or off. The state is read before, so the PIO is
+    /// only toggled if needed, and after, to verify the switch. Returns
+    /// [`Error::CommandFailed`] with the level read afterwards if the PIO is not as expected.
+    pub fn set<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        on: bool,
+    ) -> Result<(), Error<O::Error>> {
+        let level = self.read_level(wire, delay)?;
+        if on == level {
+            // a high level means the transistor is off, a low one that it is on - or that
+            // something else pulls the pin low, which only toggling can tell
+            let now_on = self.toggle(wire, delay)?;
+            if now_on != on {
+                self.toggle(wire, delay)?;
+            }
+        }
+        let level = self.read_level(wire, delay)?;
+        if on && level {
+            Err(Error::CommandFailed(u8::from(level)))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[deprecated(note = "renamed to `DS2405::set`")]
+    pub fn set_output<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        on: bool,
+    ) -> Result<(), Error<O::Error>> {
+        self.set(wire, delay, on)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Clustered, SimDelay, SimPin, Simulator};
+
+    #[test]
+    fn test_read_level_walks_search_path() {
+        let sim = Simulator::<4>::new(&mut Clustered {
+            base: [FAMILY_CODE, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x00],
+        });
+        let addresses = sim.borrow().addresses();
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut delay = SimDelay(&sim);
+
+        let switch = DS2405::new(Device {
+            address: addresses[2],
+        })
+        .unwrap();
+        assert!(switch.read_level(&mut wire, &mut delay).unwrap());
+        assert!(!switch.get(&mut wire, &mut delay).unwrap());
+
+        let mut address = addresses[2];
+        address[6] ^= 0x80;
+        let missing = DS2405::new(Device { address }).unwrap();
+        assert!(matches!(
+            missing.read_level(&mut wire, &mut delay),
+            Err(Error::NoPresencePulse)
+        ));
+    }
+
+    #[test]
+    fn test_set() {
+        let sim = Simulator::<3>::new(&mut Clustered {
+            base: [FAMILY_CODE, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x00],
+        });
+        let addresses = sim.borrow().addresses();
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut delay = SimDelay(&sim);
+        let switch = DS2405::new(Device {
+            address: addresses[1],
+        })
+        .unwrap();
+        let other = DS2405::new(Device {
+            address: addresses[2],
+        })
+        .unwrap();
+
+        switch.set(&mut wire, &mut delay, true).unwrap();
+        assert!(switch.get(&mut wire, &mut delay).unwrap());
+        // already on, selecting it again would switch it off
+        switch.set(&mut wire, &mut delay, true).unwrap();
+        assert!(switch.get(&mut wire, &mut delay).unwrap());
+        assert!(!other.get(&mut wire, &mut delay).unwrap());
+
+        switch.set(&mut wire, &mut delay, false).unwrap();
+        assert!(!switch.get(&mut wire, &mut delay).unwrap());
+        assert!(switch.toggle(&mut wire, &mut delay).unwrap());
+    }
+}
@@ -0,0 +1,275 @@
+//! The DS2406 (and the DS2407, which adds a hidden mode) is a dual channel addressable
+//! switch with 1 Kb of EPROM. Its status memory holds the write protection of the EPROM
+//! pages in bytes 0 to 6 and, in the SRAM byte 7, the output flip-flops of the channels
+//! together with the condition of the conditional search. Programming the EPROM needs a
+//! 12 V pulse and is not supported.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::bitstream::PioWrite;
+use crate::family::Capabilities;
+use crate::ConditionalSearch;
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OneWireMaster;
+use crate::OpenDrainOutput;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+
+pub const FAMILY_CODE: u8 = crate::family::DS2406;
+
+pub const STATUS_SIZE: usize = 8;
+/// The status byte in SRAM, holding the output flip-flops and the search condition
+const STATUS_SRAM: u16 = 0x07;
+const STATUS_FLIP_FLOP_A: u8 = 0x20;
+const STATUS_FLIP_FLOP_B: u8 = 0x40;
+/// The bits of the search condition in the status byte in SRAM
+const STATUS_CONDITION: u8 = 0x1F;
+
+/// Channel control byte 1 selecting both channels, without toggling or CRC
+const CONTROL_BOTH_CHANNELS: u8 = 0x0C;
+/// Resets the activity latches as part of the channel access
+const CONTROL_RESET_ACTIVITY: u8 = 0x80;
+/// The reserved channel control byte 2
+const CONTROL_2: u8 = 0xFF;
+
+#[repr(u8)]
+pub enum Command {
+    ReadStatus = 0xAA,
+    WriteStatus = 0x55,
+    ChannelAccess = 0xF5,
+}
+
+/// The channel info byte sent at the start of a channel access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelInfo {
+    /// The output flip-flops, a cleared one turns the output transistor on
+    pub flip_flop_a: bool,
+    pub flip_flop_b: bool,
+    /// The level of the pins
+    pub level_a: bool,
+    pub level_b: bool,
+    pub activity_a: bool,
+    pub activity_b: bool,
+    /// Whether the device has the second channel, the TSOC package only has channel A
+    pub has_channel_b: bool,
+    pub vcc_powered: bool,
+}
+
+impl From<u8> for ChannelInfo {
+    fn from(info: u8) -> Self {
+        let bit = |index: u8| info & (1 << index) != 0;
+        ChannelInfo {
+            flip_flop_a: bit(0),
+            flip_flop_b: bit(1),
+            level_a: bit(2),
+            level_b: bit(3),
+            activity_a: bit(4),
+            activity_b: bit(5),
+            has_channel_b: bit(6),
+            vcc_powered: bit(7),
+        }
+    }
+}
+
+/// What the conditional search compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchSource {
+    ActivityLatch = 0b01,
+    FlipFlop = 0b10,
+    Level = 0b11,
+}
+
+/// The channels taking part in the conditional search, with `Both` the device answers if
+/// either of them matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchChannels {
+    None = 0b00,
+    A = 0b01,
+    B = 0b10,
+    Both = 0b11,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchCondition {
+    pub source: SearchSource,
+    pub channels: SearchChannels,
+    /// The value of the source the device answers the conditional search for
+    pub polarity: bool,
+}
+
+impl SearchCondition {
+    /// The condition bits of the status byte in SRAM
+    fn status_bits(&self) -> u8 {
+        u8::from(self.polarity) | (self.source as u8) << 1 | (self.channels as u8) << 3
+    }
+}
+
+pub struct DS2406 {
+    device: Device,
+}
+
+impl DS2406 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: false,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2406, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2406 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reads the whole status memory and checks its CRC16
+    pub fn read_status<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<[u8; STATUS_SIZE], Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            let command = [Command::ReadStatus as u8, 0x00, 0x00];
+            wire.write_bytes(delay, &command)?;
+            let mut status = [0u8; STATUS_SIZE];
+            wire.read_bytes(delay, &mut status)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(compute_partial_crc16(0, &command), &status);
+            ensure_correct_crc16(computed, crc)?;
+            Ok(status)
+        })
+    }
+
+    /// Reads the channel info, resetting the activity latches afterwards if requested
+    pub fn read_channels<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        reset_activity: bool,
+    ) -> Result<ChannelInfo, Error<W::Error>> {
+        let control = if reset_activity {
+            CONTROL_BOTH_CHANNELS | CONTROL_RESET_ACTIVITY
+        } else {
+            CONTROL_BOTH_CHANNELS
+        };
+        let mut info = [0u8; 1];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ChannelAccess as u8, control, CONTROL_2],
+            &mut info,
+        )?;
+        let [info] = info;
+        Ok(ChannelInfo::from(info))
+    }
+
+    /// Sets the output flip-flops, `false` turning the output transistor of the channel on
+    pub fn set_flip_flops<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        a: bool,
+        b: bool,
+    ) -> Result<(), Error<W::Error>> {
+        let [.., sram] = self.read_status(wire, delay)?;
+        let mut status = sram & STATUS_CONDITION;
+        if a {
+            status |= STATUS_FLIP_FLOP_A;
+        }
+        if b {
+            status |= STATUS_FLIP_FLOP_B;
+        }
+        self.write_status_sram(wire, delay, status)
+    }
+
+    fn write_status_sram<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        status: u8,
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            let [low, high] = STATUS_SRAM.to_le_bytes();
+            let command = [Command::WriteStatus as u8, low, high, status];
+            wire.write_bytes(delay, &command)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            ensure_correct_crc16(compute_partial_crc16(0, &command), crc)
+        })
+    }
+}
+
+impl ConditionalSearch for DS2406 {
+    type Condition = SearchCondition;
+
+    /// Keeps the output flip-flops as they are
+    fn configure_conditional_search<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        condition: &SearchCondition,
+    ) -> Result<(), Error<O::Error>> {
+        let [.., sram] = self.read_status(wire, delay)?;
+        let status = sram & (STATUS_FLIP_FLOP_A | STATUS_FLIP_FLOP_B) | condition.status_bits();
+        self.write_status_sram(wire, delay, status)
+    }
+}
+
+/// Bit 0 of the state is the flip-flop of PIO A, bit 1 the one of PIO B. Unlike the other
+/// switches this takes two transactions, as the status is read first to keep the search
+/// condition.
+impl PioWrite for DS2406 {
+    fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        state: u8,
+    ) -> Result<(), Error<W::Error>> {
+        self.set_flip_flops(wire, delay, state & 0x01 != 0, state & 0x02 != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_condition() {
+        let condition = SearchCondition {
+            source: SearchSource::ActivityLatch,
+            channels: SearchChannels::Both,
+            polarity: true,
+        };
+        assert_eq!(0b1_1011, condition.status_bits());
+        let condition = SearchCondition {
+            source: SearchSource::Level,
+            channels: SearchChannels::A,
+            polarity: false,
+        };
+        assert_eq!(0b0_1110, condition.status_bits());
+    }
+
+    #[test]
+    fn test_channel_info() {
+        let info = ChannelInfo::from(0b1100_0101);
+        assert!(info.flip_flop_a && !info.flip_flop_b);
+        assert!(info.level_a && !info.level_b);
+        assert!(info.has_channel_b && info.vcc_powered);
+    }
+}
@@ -0,0 +1,309 @@
+//! The DS2408 is an 8 channel addressable switch with open drain outputs, as found on relay
+//! boards and I/O expanders. Besides the level of its pins it latches activity (edges) on
+//! them, can answer the conditional search depending on either, and has a RSTZ pin which is
+//! either a reset input or a strobe output pulsed on every channel access.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::bitstream::PioWrite;
+use crate::family::Capabilities;
+use crate::ConditionalSearch;
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OneWireMaster;
+use crate::OpenDrainOutput;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+
+pub const FAMILY_CODE: u8 = crate::family::DS2408;
+
+/// The address of the first register, the PIO logic state
+const REGISTERS: u16 = 0x88;
+/// The address of the conditional search channel selection mask
+const SEARCH_REGISTERS: u16 = 0x8B;
+/// The address of the control/status register
+const CONTROL_REGISTER: u16 = 0x8D;
+const CONFIRMATION: u8 = 0xAA;
+
+const CONTROL_ACTIVITY: u8 = 0x01;
+const CONTROL_AND: u8 = 0x02;
+const CONTROL_STROBE: u8 = 0x04;
+const CONTROL_POWER_ON_RESET: u8 = 0x08;
+const CONTROL_VCC_POWERED: u8 = 0x80;
+
+#[repr(u8)]
+pub enum Command {
+    ReadPioRegisters = 0xF0,
+    ChannelAccessRead = 0xF5,
+    ChannelAccessWrite = 0x5A,
+    WriteConditionalSearchRegister = 0xCC,
+    ResetActivityLatches = 0xC3,
+}
+
+/// The registers of the device, one bit per channel unless noted otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    /// The level of the pins
+    pub pio_logic: u8,
+    /// The output latches, a 0 turns the output transistor on
+    pub output_latch: u8,
+    /// Set for pins that changed their level since the latches were reset
+    pub activity_latch: u8,
+    pub search_mask: u8,
+    pub search_polarity: u8,
+    pub control: u8,
+}
+
+impl Registers {
+    /// Whether the device was reset by power loss, which sets all outputs to off
+    pub fn power_on_reset(&self) -> bool {
+        self.control & CONTROL_POWER_ON_RESET != 0
+    }
+
+    pub fn vcc_powered(&self) -> bool {
+        self.control & CONTROL_VCC_POWERED != 0
+    }
+
+    pub fn rstz_strobe(&self) -> bool {
+        self.control & CONTROL_STROBE != 0
+    }
+}
+
+/// What the device answers the conditional search for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchCondition {
+    /// The channels taking part
+    pub mask: u8,
+    /// The level (or latched activity) each selected channel has to have
+    pub polarity: u8,
+    /// Compares the activity latches instead of the levels of the pins
+    pub activity: bool,
+    /// Requires all selected channels to match instead of any
+    pub and: bool,
+}
+
+pub struct DS2408 {
+    device: Device,
+}
+
+impl DS2408 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: true,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2408, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2408 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reads all registers and checks their CRC16
+    pub fn read_registers<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Registers, Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            let [low, high] = REGISTERS.to_le_bytes();
+            let command = [Command::ReadPioRegisters as u8, low, high];
+            wire.write_bytes(delay, &command)?;
+            // up to the end of the register page at 0x8F
+            let mut data = [0u8; 8];
+            wire.read_bytes(delay, &mut data)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(compute_partial_crc16(0, &command), &data);
+            ensure_correct_crc16(computed, crc)?;
+            let [pio_logic, output_latch, activity_latch, search_mask, search_polarity, control, ..] =
+                data;
+            Ok(Registers {
+                pio_logic,
+                output_latch,
+                activity_latch,
+                search_mask,
+                search_polarity,
+                control,
+            })
+        })
+    }
+
+    /// Samples the level of the pins
+    pub fn read_channels<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u8, Error<W::Error>> {
+        let mut state = [0u8; 1];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ChannelAccessRead as u8],
+            &mut state,
+        )?;
+        let [state] = state;
+        Ok(state)
+    }
+
+    /// Sets the output latches, a 0 turns the output transistor of the channel on. Returns
+    /// the level of the pins afterwards.
+    pub fn write_channels<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        latches: u8,
+    ) -> Result<u8, Error<W::Error>> {
+        let mut answer = [0u8; 2];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ChannelAccessWrite as u8, latches, !latches],
+            &mut answer,
+        )?;
+        match answer {
+            [CONFIRMATION, state] => Ok(state),
+            [other, _] => Err(Error::CommandFailed(other)),
+        }
+    }
+
+    pub fn reset_activity_latches<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        let mut confirmation = [0u8; 1];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ResetActivityLatches as u8],
+            &mut confirmation,
+        )?;
+        match confirmation {
+            [CONFIRMATION] => Ok(()),
+            [other] => Err(Error::CommandFailed(other)),
+        }
+    }
+
+    /// Configures the RSTZ pin as strobe output instead of reset input, which also clears
+    /// the power-on reset flag
+    pub fn set_rstz_strobe<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        strobe: bool,
+    ) -> Result<(), Error<W::Error>> {
+        let control = self.read_registers(wire, delay)?.control;
+        let control = control & (CONTROL_ACTIVITY | CONTROL_AND) | strobe_bit(strobe);
+        self.write_search_registers(wire, delay, CONTROL_REGISTER, &[control])
+    }
+
+    fn write_search_registers<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            let [low, high] = address.to_le_bytes();
+            wire.write_bytes(
+                delay,
+                &[Command::WriteConditionalSearchRegister as u8, low, high],
+            )?;
+            wire.write_bytes(delay, data)
+        })
+    }
+}
+
+fn strobe_bit(strobe: bool) -> u8 {
+    if strobe {
+        CONTROL_STROBE
+    } else {
+        0
+    }
+}
+
+impl SearchCondition {
+    /// The control register for the condition, keeping the configuration of the RSTZ pin
+    fn control(&self, strobe: bool) -> u8 {
+        let mut control = strobe_bit(strobe);
+        if self.activity {
+            control |= CONTROL_ACTIVITY;
+        }
+        if self.and {
+            control |= CONTROL_AND;
+        }
+        control
+    }
+}
+
+impl ConditionalSearch for DS2408 {
+    type Condition = SearchCondition;
+
+    fn configure_conditional_search<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        condition: &SearchCondition,
+    ) -> Result<(), Error<O::Error>> {
+        let strobe = self.read_registers(wire, delay)?.rstz_strobe();
+        self.write_search_registers(
+            wire,
+            delay,
+            SEARCH_REGISTERS,
+            &[
+                condition.mask,
+                condition.polarity,
+                condition.control(strobe),
+            ],
+        )
+    }
+}
+
+impl PioWrite for DS2408 {
+    fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        state: u8,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_channels(wire, delay, state).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_condition() {
+        let condition = SearchCondition {
+            mask: 0x0F,
+            polarity: 0x00,
+            activity: true,
+            and: false,
+        };
+        assert_eq!(0x01, condition.control(false));
+        let condition = SearchCondition {
+            and: true,
+            ..condition
+        };
+        assert_eq!(0x07, condition.control(true));
+    }
+}
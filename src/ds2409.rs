@@ -0,0 +1,492 @@
+//! The DS2409 MicroLAN coupler switches two branches, main and auxiliary, onto the bus it
+//! sits on. Devices behind a coupler only show up in a search while their branch is on,
+//! so a flat [`DeviceSearch`] misses them. [`BranchedBus`] walks star and tree topologies
+//! by searching the trunk and then every branch of every coupler found, remembering
+//! behind which branch each device is so it can be connected again later.
+//!
+//! Switching a branch on leaves the segments above it connected, their devices answer the
+//! branch search as well and are told apart by having been found before. Couplers are
+//! expected to start with all lines off, as they do after power up.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::scan::MAX_SEARCH_STEPS;
+use crate::Device;
+use crate::DeviceSearch;
+use crate::Error;
+use crate::OneWireMaster;
+
+pub const FAMILY_CODE: u8 = crate::family::DS2409;
+
+/// Written after the smart-on commands, the coupler then issues a reset on the branch
+const RESET_STIMULUS: u8 = 0xFF;
+
+#[repr(u8)]
+pub enum Command {
+    ReadWriteStatus = 0x5A,
+    AllLinesOff = 0x66,
+    DischargeLines = 0x99,
+    DirectOnMain = 0xA5,
+    SmartOnMain = 0xCC,
+    SmartOnAuxiliary = 0x33,
+}
+
+/// The branches of a coupler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Main,
+    Auxiliary,
+}
+
+pub struct DS2409 {
+    device: Device,
+}
+
+impl DS2409 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: true,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2409, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2409 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Disconnects both branches
+    pub fn all_lines_off<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        self.confirmed(wire, delay, &[Command::AllLinesOff as u8])
+    }
+
+    /// Connects the branch after resetting it, so the devices on it are ready for the
+    /// next command. The other branch is disconnected. Returns whether a device answered
+    /// the reset on the branch with a presence pulse.
+    pub fn smart_on<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        output: Output,
+    ) -> Result<bool, Error<W::Error>> {
+        let command = match output {
+            Output::Main => Command::SmartOnMain,
+            Output::Auxiliary => Command::SmartOnAuxiliary,
+        } as u8;
+        // the coupler answers the reset stimulus with the presence detect byte, which is
+        // all zeros if a presence pulse was seen, followed by the confirmation
+        let mut answer = [0u8; 2];
+        wire.reset_select_write_read(delay, &self.device, &[command, RESET_STIMULUS], &mut answer)?;
+        let [presence, confirmation] = answer;
+        if confirmation != command {
+            return Err(Error::CommandFailed(confirmation));
+        }
+        Ok(presence != 0xFF)
+    }
+
+    /// Connects the main branch without resetting it
+    pub fn direct_on_main<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        self.confirmed(wire, delay, &[Command::DirectOnMain as u8])
+    }
+
+    /// Sends the command, which the coupler confirms by echoing its first byte. Fails with
+    /// [`Error::CommandFailed`] with the byte read otherwise.
+    fn confirmed<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        command: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        let mut confirmation = [0u8];
+        wire.reset_select_write_read(delay, &self.device, command, &mut confirmation)?;
+        match (command.first(), confirmation) {
+            (Some(command), [confirmation]) if *command == confirmation => Ok(()),
+            (_, [confirmation]) => Err(Error::CommandFailed(confirmation)),
+        }
+    }
+}
+
+/// The branch of a coupler a segment of the bus is connected to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Branch {
+    /// The index of the coupler in the entries of the [`BranchedBus`]
+    coupler: usize,
+    output: Output,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    device: Device,
+    /// `None` for devices on the trunk
+    branch: Option<Branch>,
+}
+
+/// The devices of a bus with couplers and the branch each device is behind. Stores up to
+/// `N` devices including the couplers, further devices are counted but dropped - as are
+/// the branches of dropped couplers.
+#[derive(Debug, Clone)]
+pub struct BranchedBus<const N: usize> {
+    entries: [Option<Entry>; N],
+    found: usize,
+}
+
+impl<const N: usize> Default for BranchedBus<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> BranchedBus<N> {
+    const NONE: Option<Entry> = None;
+
+    pub const fn new() -> Self {
+        BranchedBus {
+            entries: [Self::NONE; N],
+            found: 0,
+        }
+    }
+
+    /// Searches the trunk and then, breadth first, both branches of every coupler found,
+    /// replacing the previous results. All couplers are off afterwards.
+    pub fn discover<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        *self = Self::new();
+        self.search(wire, delay, None)?;
+        // the branches searched append their devices, which are visited in turn
+        let mut coupler = 0;
+        while let Some(Some(entry)) = self.entries.get(coupler) {
+            let is_coupler = entry.device.family_code() == FAMILY_CODE;
+            if is_coupler {
+                for output in [Output::Main, Output::Auxiliary] {
+                    self.search(wire, delay, Some(Branch { coupler, output }))?;
+                }
+            }
+            coupler += 1;
+        }
+        self.open(wire, delay, None)
+    }
+
+    /// The stored devices, trunk first and each branch after the one of its coupler
+    pub fn devices(&self) -> impl Iterator<Item = &Device> + '_ {
+        self.entries.iter().flatten().map(|entry| &entry.device)
+    }
+
+    /// The number of devices found, including the ones that did not fit
+    pub fn found(&self) -> usize {
+        self.found
+    }
+
+    /// Whether more devices were found than can be stored
+    pub fn overflowed(&self) -> bool {
+        self.found > N
+    }
+
+    /// The coupler and its branch the device is behind, `None` for devices on the trunk
+    /// and unknown devices
+    pub fn branch_of(&self, device: &Device) -> Option<(&Device, Output)> {
+        let branch = self.entry(device)?.branch?;
+        let coupler = self.entries.get(branch.coupler)?.as_ref()?;
+        Some((&coupler.device, branch.output))
+    }
+
+    /// Switches the couplers so the device is connected and can be addressed as if it
+    /// was on the trunk, with all other branches off. Returns `false` for unknown devices.
+    pub fn connect<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        device: &Device,
+    ) -> Result<bool, Error<W::Error>> {
+        match self.entry(device) {
+            Some(entry) => self.open(wire, delay, entry.branch).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    fn entry(&self, device: &Device) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .flatten()
+            .find(|entry| entry.device == *device)
+    }
+
+    /// Connects the branch and searches it, storing the devices not found before
+    fn search<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        branch: Option<Branch>,
+    ) -> Result<(), Error<W::Error>> {
+        self.open(wire, delay, branch)?;
+        let mut search = DeviceSearch::new();
+        for _ in 0..MAX_SEARCH_STEPS {
+            match wire.search_next(&mut search, delay)? {
+                Some(device) => self.insert(device, branch),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, device: Device, branch: Option<Branch>) {
+        if self.entry(&device).is_some() {
+            return;
+        }
+        if let Some(slot) = self.entries.get_mut(self.found) {
+            *slot = Some(Entry { device, branch });
+        }
+        self.found += 1;
+    }
+
+    /// Connects the branch and the ones above it, switching off the known couplers on it.
+    /// Turns off all couplers on the trunk for `None`.
+    fn open<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        branch: Option<Branch>,
+    ) -> Result<(), Error<W::Error>> {
+        let branch = match branch {
+            Some(branch) => branch,
+            None => {
+                // every coupler on the trunk confirms, which does not disturb the others
+                let mut confirmation = [0u8];
+                return wire.reset_skip_write_read(
+                    delay,
+                    &[Command::AllLinesOff as u8],
+                    &mut confirmation,
+                );
+            }
+        };
+        let coupler = match self.entries.get(branch.coupler) {
+            Some(Some(coupler)) => coupler,
+            _ => return Err(Error::NoPresencePulse),
+        };
+        // the coupler was found after the one of its branch, so this ends at the trunk
+        self.open(wire, delay, coupler.branch)?;
+        let device = DS2409 {
+            device: coupler.device.clone(),
+        };
+        // an empty branch is searched nonetheless, finding nothing
+        device.smart_on(wire, delay, branch.output)?;
+        for entry in self.entries.iter().flatten() {
+            if entry.branch == Some(branch) && entry.device.family_code() == FAMILY_CODE {
+                DS2409 {
+                    device: entry.device.clone(),
+                }
+                .all_lines_off(wire, delay)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, Reply, Script, ScriptedWire};
+    use crate::Command as RomCommand;
+
+    /// Couplers and devices with the branch they are behind
+    struct FakeTree {
+        devices: [([u8; 8], Option<Branch>); 5],
+        /// The branch each coupler has on
+        on: [Option<Output>; 5],
+    }
+
+    impl FakeTree {
+        fn connected(&self, index: usize) -> bool {
+            match self.devices[index].1 {
+                None => true,
+                Some(Branch { coupler, output }) => {
+                    self.on[coupler] == Some(output) && self.connected(coupler)
+                }
+            }
+        }
+
+        fn switch(&mut self, index: usize, command: u8) {
+            self.on[index] = match command {
+                0x66 => None,
+                0xCC => Some(Output::Main),
+                0x33 => Some(Output::Auxiliary),
+                _ => self.on[index],
+            };
+        }
+    }
+
+    impl Script for FakeTree {
+        fn receive(&mut self, written: &[u8], reply: &mut Reply) {
+            let (selected, command) = match written {
+                [0x55, rest @ ..] if rest.len() > 8 => {
+                    let (address, command) = rest.split_at(8);
+                    (Some(address), command)
+                }
+                [0xCC, command @ ..] => (None, command),
+                _ => return,
+            };
+            let (function, smart_on) = match *command {
+                // the smart on commands wait for the reset stimulus
+                [function @ (0xCC | 0x33), RESET_STIMULUS] => (function, true),
+                [function] if !matches!(function, 0xCC | 0x33) => (function, false),
+                _ => return,
+            };
+            let targets: [bool; 5] = core::array::from_fn(|index| {
+                let address = self.devices[index].0;
+                self.connected(index)
+                    && address[0] == FAMILY_CODE
+                    && selected.is_none_or(|selected| selected == address)
+            });
+            if !targets.contains(&true) {
+                return;
+            }
+            for (index, target) in targets.iter().enumerate() {
+                if *target {
+                    self.switch(index, function);
+                }
+            }
+            if smart_on {
+                // the presence detect byte, all zeros if a device is on the branch
+                let present = (0..self.devices.len()).any(|index| {
+                    self.connected(index)
+                        && matches!(self.devices[index].1, Some(branch) if targets[branch.coupler])
+                });
+                reply.bytes(&[if present { 0x00 } else { 0xFF }]);
+            }
+            reply.bytes(&[function]);
+        }
+
+        fn idle_bit(&mut self, written: &[u8], reads: usize) -> bool {
+            let [rom, path @ ..] = written else {
+                return true;
+            };
+            if *rom != RomCommand::SearchNext as u8 {
+                return true;
+            }
+            let bit = reads / 2;
+            let mut bytes = [0u8; 8];
+            for (byte, value) in bytes.iter_mut().zip(path) {
+                *byte = *value;
+            }
+            let path = u64::from_le_bytes(bytes);
+            let mut bits = (0..self.devices.len())
+                .filter(|index| self.connected(*index))
+                .map(|index| u64::from_le_bytes(self.devices[index].0))
+                .filter(|address| (address ^ path) & ((1 << bit) - 1) == 0)
+                .map(|address| address >> bit & 1 != 0);
+            if reads & 1 == 0 {
+                bits.all(|bit| bit)
+            } else {
+                bits.all(|bit| !bit)
+            }
+        }
+    }
+
+    fn address(family: u8, serial: u8) -> [u8; 8] {
+        let mut address = [family, serial, 0, 0, 0, 0, 0, 0];
+        address[7] = crate::crc8_const(&address[..7]);
+        address
+    }
+
+    #[test]
+    fn test_discover_nested_branches() {
+        let trunk_coupler = address(FAMILY_CODE, 1);
+        let nested_coupler = address(FAMILY_CODE, 2);
+        let trunk = address(0x28, 3);
+        let main = address(0x28, 4);
+        let nested = address(0x28, 5);
+        let mut wire = ScriptedWire::new(FakeTree {
+            devices: [
+                (
+                    nested,
+                    Some(Branch {
+                        coupler: 4,
+                        output: Output::Auxiliary,
+                    }),
+                ),
+                (trunk_coupler, None),
+                (
+                    main,
+                    Some(Branch {
+                        coupler: 1,
+                        output: Output::Main,
+                    }),
+                ),
+                (trunk, None),
+                (
+                    nested_coupler,
+                    Some(Branch {
+                        coupler: 1,
+                        output: Output::Main,
+                    }),
+                ),
+            ],
+            on: [None; 5],
+        });
+        let mut bus = BranchedBus::<8>::new();
+        bus.discover(&mut wire, &mut Delay).unwrap();
+        assert_eq!(5, bus.found());
+        assert_eq!([None; 5], wire.script.on);
+
+        let trunk_coupler = Device {
+            address: trunk_coupler,
+        };
+        let nested_coupler = Device {
+            address: nested_coupler,
+        };
+        let nested = Device { address: nested };
+        assert_eq!(None, bus.branch_of(&trunk_coupler));
+        assert_eq!(
+            Some((&trunk_coupler, Output::Main)),
+            bus.branch_of(&Device { address: main })
+        );
+        assert_eq!(
+            Some((&nested_coupler, Output::Auxiliary)),
+            bus.branch_of(&nested)
+        );
+
+        let coupler = DS2409::new(trunk_coupler.clone()).unwrap();
+        assert!(!coupler
+            .smart_on(&mut wire, &mut Delay, Output::Auxiliary)
+            .unwrap());
+        assert!(coupler
+            .smart_on(&mut wire, &mut Delay, Output::Main)
+            .unwrap());
+        coupler.all_lines_off(&mut wire, &mut Delay).unwrap();
+        assert_eq!(Some(&0x66), wire.written().last());
+
+        assert!(bus.connect(&mut wire, &mut Delay, &nested).unwrap());
+        assert_eq!(
+            [
+                None,
+                Some(Output::Main),
+                None,
+                None,
+                Some(Output::Auxiliary)
+            ],
+            wire.script.on
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! The DS2413 is a dual channel addressable switch with open drain outputs. Both PIO
+//! accesses are confirmed by a status byte whose upper nibble is the complement of the
+//! lower one, so a corrupted status is detected without a CRC.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::bitstream::{read_dual_pio, write_dual_pio, PioWrite};
+use crate::family::Capabilities;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+
+pub use crate::bitstream::PioState;
+
+pub const FAMILY_CODE: u8 = crate::family::DS2413;
+
+#[repr(u8)]
+pub enum Command {
+    PioAccessRead = 0xF5,
+    PioAccessWrite = 0x5A,
+}
+
+pub struct DS2413 {
+    device: Device,
+}
+
+impl DS2413 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: true,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2413, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2413 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn read_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<PioState, Error<W::Error>> {
+        read_dual_pio(wire, delay, &self.device, Command::PioAccessRead as u8)
+    }
+
+    /// Sets the output latches, `true` turning the output transistor off. Returns the state
+    /// of the pins afterwards.
+    pub fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        latch_a: bool,
+        latch_b: bool,
+    ) -> Result<PioState, Error<W::Error>> {
+        write_dual_pio(
+            wire,
+            delay,
+            &self.device,
+            Command::PioAccessWrite as u8,
+            latch_a,
+            latch_b,
+        )
+    }
+}
+
+/// Bit 0 of the state is the latch of PIO A, bit 1 the one of PIO B
+impl PioWrite for DS2413 {
+    fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        state: u8,
+    ) -> Result<(), Error<W::Error>> {
+        DS2413::write_pio(self, wire, delay, state & 0x01 != 0, state & 0x02 != 0).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, Reply, Script, ScriptedWire};
+
+    const CONFIRMATION: u8 = 0xAA;
+
+    /// A DS2413 with its pins following the latches, answering after being selected
+    struct FakeSwitch {
+        latches: u8,
+    }
+
+    impl FakeSwitch {
+        fn status(&self) -> u8 {
+            let a = self.latches & 0x01;
+            let b = self.latches >> 1 & 0x01;
+            let status = a | a << 1 | b << 2 | b << 3;
+            status | (!status) << 4
+        }
+    }
+
+    impl Script for FakeSwitch {
+        fn receive(&mut self, written: &[u8], reply: &mut Reply) {
+            match *written {
+                [0x55, _, _, _, _, _, _, _, _, 0xF5] => reply.bytes(&[self.status()]),
+                [0x55, _, _, _, _, _, _, _, _, 0x5A, output, inverted] if output == !inverted => {
+                    self.latches = output & 0x03;
+                    reply.bytes(&[CONFIRMATION, self.status()]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_pio_access() {
+        let switch = DS2413::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap();
+        let mut wire = ScriptedWire::new(FakeSwitch { latches: 0x03 });
+        let state = switch.read_pio(&mut wire, &mut Delay).unwrap();
+        assert!(state.latch_a && state.latch_b);
+
+        let state = switch
+            .write_pio(&mut wire, &mut Delay, false, true)
+            .unwrap();
+        assert!(!state.latch_a && !state.level_a && state.latch_b);
+        assert_eq!(0x02, wire.script.latches);
+        assert_eq!([0x5A, 0xFE, 0x01], wire.written()[9..]);
+    }
+}
@@ -0,0 +1,292 @@
+//! The DS2431 is a 1 Kb EEPROM of four pages of 32 bytes, written in rows of 8 bytes through
+//! a scratchpad: the row is written into the scratchpad, read back together with the
+//! target address and the E/S byte that authorizes the copy, and then copied into the
+//! EEPROM. Each page can be write protected (permanently) through the protection bytes
+//! behind the memory.
+
+use core::convert::Infallible;
+use core::fmt::Debug;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::memory::{check_range, Memory, OneWireEeprom, PendingCopy};
+use crate::scratchpad::ScratchpadEeprom;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+
+pub use crate::scratchpad::Command;
+
+pub const FAMILY_CODE: u8 = crate::family::DS2431;
+
+/// Size of the user memory in bytes
+pub const MEMORY_SIZE: u16 = 128;
+pub const PAGE_SIZE: u16 = 32;
+/// The memory is written in rows of this many bytes
+pub const ROW_SIZE: usize = 8;
+/// How long copying the scratchpad into the EEPROM takes at most
+pub const PROGRAMMING_US: u16 = 12_500;
+
+/// The address of the protection byte of the first page
+const PROTECTION: u16 = 0x80;
+const PROTECTION_WRITE: u8 = 0x55;
+const PROTECTION_EPROM: u8 = 0xAA;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    None,
+    /// The page can not be written anymore
+    WriteProtected,
+    /// Bits of the page can only be changed from 1 to 0
+    Eprom,
+}
+
+pub struct DS2431 {
+    device: Device,
+}
+
+impl DS2431 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: true,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2431, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2431 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn eeprom(&self) -> ScratchpadEeprom<'_, ROW_SIZE> {
+        ScratchpadEeprom {
+            device: &self.device,
+            programming_us: PROGRAMMING_US,
+        }
+    }
+
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().read(wire, delay, address, data)
+    }
+
+    /// Writes a whole row, `address` being the address of its first byte. Fails with
+    /// [`Error::CommandFailed`] if the scratchpad did not receive the row or the copy
+    /// failed, e.g. because the page is write protected.
+    pub fn write_row<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        row: &[u8; ROW_SIZE],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().write_block(wire, delay, address, row)
+    }
+
+    /// Like [`DS2431::write_row`], but returns once the copy into the EEPROM started instead
+    /// of waiting for its programming time
+    pub fn start_write_row<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        row: &[u8; ROW_SIZE],
+    ) -> Result<PendingCopy, Error<W::Error>> {
+        self.eeprom().start_write_block(wire, delay, address, row)
+    }
+
+    /// The protection of the page with the index `0` to `3`
+    pub fn protection<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        page: u8,
+    ) -> Result<Protection, Error<W::Error>> {
+        check_page(page)?;
+        let mut byte = [0u8; 1];
+        self.read(wire, delay, PROTECTION + u16::from(page), &mut byte)?;
+        Ok(match byte {
+            [PROTECTION_WRITE] => Protection::WriteProtected,
+            [PROTECTION_EPROM] => Protection::Eprom,
+            _ => Protection::None,
+        })
+    }
+
+    /// Protects the page with the index `0` to `3`, which can not be undone
+    pub fn protect<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        page: u8,
+        protection: Protection,
+    ) -> Result<(), Error<W::Error>> {
+        check_page(page)?;
+        let byte = match protection {
+            Protection::WriteProtected => PROTECTION_WRITE,
+            Protection::Eprom => PROTECTION_EPROM,
+            Protection::None => return Ok(()),
+        };
+        self.eeprom()
+            .write(wire, delay, PROTECTION + u16::from(page), &[byte])
+    }
+}
+
+fn check_page<E: Debug>(page: u8) -> Result<(), Error<E>> {
+    if u16::from(page) < MEMORY_SIZE / PAGE_SIZE {
+        Ok(())
+    } else {
+        Err(Error::OutOfRange)
+    }
+}
+
+impl Memory for DS2431 {
+    fn size(&self) -> u16 {
+        MEMORY_SIZE
+    }
+
+    fn read_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), MEMORY_SIZE)?;
+        self.read(wire, delay, address, data)
+    }
+
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), MEMORY_SIZE)?;
+        self.eeprom().write(wire, delay, address, data)
+    }
+}
+
+impl OneWireEeprom for DS2431 {
+    fn page_size(&self) -> u16 {
+        ROW_SIZE as u16
+    }
+
+    fn programming_us(&self) -> u16 {
+        PROGRAMMING_US
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, FakeEeprom};
+
+    fn eeprom() -> DS2431 {
+        DS2431::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_write_rows() {
+        let eeprom = eeprom();
+        let mut wire =
+            FakeEeprom::<ROW_SIZE>::new(Some((usize::from(PROTECTION), usize::from(PAGE_SIZE))));
+        eeprom
+            .eeprom()
+            .write(&mut wire, &mut Delay, 5, &[1, 2, 3, 4, 5, 6])
+            .unwrap();
+        let mut data = [0u8; 16];
+        eeprom.read(&mut wire, &mut Delay, 0, &mut data).unwrap();
+        assert_eq!(
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3, 4, 5, 6, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+            data
+        );
+    }
+
+    #[test]
+    fn test_poll_copy() {
+        let eeprom = eeprom();
+        let mut wire = FakeEeprom::<ROW_SIZE>::new(None);
+        let mut copy = eeprom
+            .start_write_row(&mut wire, &mut Delay, 8, &[7; ROW_SIZE])
+            .unwrap();
+        let mut polls = 0;
+        while copy
+            .poll(&mut wire, &mut Delay, 4_000)
+            .unwrap()
+            .is_pending()
+        {
+            polls += 1;
+        }
+        assert_eq!(3, polls);
+        let mut data = [0u8; ROW_SIZE];
+        eeprom.read(&mut wire, &mut Delay, 8, &mut data).unwrap();
+        assert_eq!([7; ROW_SIZE], data);
+    }
+
+    #[test]
+    fn test_protection() {
+        let eeprom = eeprom();
+        let mut wire =
+            FakeEeprom::<ROW_SIZE>::new(Some((usize::from(PROTECTION), usize::from(PAGE_SIZE))));
+        eeprom
+            .protect(&mut wire, &mut Delay, 1, Protection::WriteProtected)
+            .unwrap();
+        assert_eq!(
+            Protection::WriteProtected,
+            eeprom.protection(&mut wire, &mut Delay, 1).unwrap()
+        );
+        assert_eq!(
+            Protection::None,
+            eeprom.protection(&mut wire, &mut Delay, 0).unwrap()
+        );
+        assert!(matches!(
+            eeprom.write_row(&mut wire, &mut Delay, 32, &[0; ROW_SIZE]),
+            Err(Error::CommandFailed(0xFF))
+        ));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let eeprom = eeprom();
+        let mut wire = FakeEeprom::<ROW_SIZE>::new(None);
+        assert!(matches!(
+            eeprom.protect(&mut wire, &mut Delay, 4, Protection::WriteProtected),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            eeprom.protection(&mut wire, &mut Delay, 4),
+            Err(Error::OutOfRange)
+        ));
+        let mut data = [0u8; 16];
+        assert!(matches!(
+            eeprom.read_memory(&mut wire, &mut Delay, 120, &mut data),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            eeprom.write_memory(&mut wire, &mut Delay, u16::MAX, &data),
+            Err(Error::OutOfRange)
+        ));
+        eeprom
+            .read_memory(&mut wire, &mut Delay, 112, &mut data)
+            .unwrap();
+    }
+}
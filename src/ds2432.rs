@@ -0,0 +1,455 @@
+//! The DS2432 (and the DS1961S iButton) is a 1 Kb EEPROM of four pages of 32 bytes secured
+//! by a SHA-1 engine and an 8 byte secret that can be written but never read. Reading a
+//! page authenticated returns a MAC over the page, the secret, the address of the device and
+//! a challenge chosen by the master, so a host knowing the secret can tell a genuine device
+//! from a copy. Writing requires a MAC from the host in turn, proving it knows the secret.
+//!
+//! The MACs are computed on the host with [`compute_read_mac`] and [`compute_write_mac`],
+//! the secret of a device derived from a master secret with [`compute_next_secret`].
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::memory::PendingCopy;
+use crate::scratchpad::ScratchpadEeprom;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+
+pub const FAMILY_CODE: u8 = crate::family::DS2432;
+
+/// Size of the user memory in bytes
+pub const MEMORY_SIZE: u16 = 128;
+pub const PAGE_SIZE: usize = 32;
+/// The memory is written in rows of this many bytes
+pub const ROW_SIZE: usize = 8;
+pub const SECRET_SIZE: usize = 8;
+pub const MAC_SIZE: usize = 20;
+/// How long copying the scratchpad into the EEPROM takes at most
+pub const PROGRAMMING_US: u16 = 10_000;
+/// How long computing a MAC takes at most
+pub const SHA_US: u16 = 1_500;
+
+/// The address the secret is loaded to
+const SECRET: u16 = 0x80;
+/// The bit of the page byte of the message of an authenticated read
+const MESSAGE_READ: u8 = 0x40;
+
+#[repr(u8)]
+pub enum Command {
+    WriteScratchpad = 0x0F,
+    ReadScratchpad = 0xAA,
+    LoadFirstSecret = 0x5A,
+    ComputeNextSecret = 0x33,
+    CopyScratchpad = 0x55,
+    ReadMemory = 0xF0,
+    ReadAuthenticatedPage = 0xA5,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedPage {
+    pub data: [u8; PAGE_SIZE],
+    /// The MAC the device computed over the page
+    pub mac: [u8; MAC_SIZE],
+}
+
+pub struct DS2432 {
+    device: Device,
+}
+
+impl DS2432 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: true,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2432, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2432 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn eeprom(&self) -> ScratchpadEeprom<'_, ROW_SIZE> {
+        ScratchpadEeprom {
+            device: &self.device,
+            programming_us: PROGRAMMING_US,
+        }
+    }
+
+    /// Reads the data starting at `address` without authentication
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().read(wire, delay, address, data)
+    }
+
+    /// Reads the page with the index `0` to `3` together with the MAC the device computed
+    /// over it for the `challenge`, see [`compute_read_mac`]
+    pub fn read_authenticated_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        page: u8,
+        challenge: &[u8; 3],
+    ) -> Result<AuthenticatedPage, Error<W::Error>> {
+        // the challenge is taken from the bytes 4 to 6 of the scratchpad
+        let [a, b, c] = *challenge;
+        self.eeprom()
+            .write_scratchpad(wire, delay, 0, &[0xFF, 0xFF, 0xFF, 0xFF, a, b, c, 0xFF])?;
+
+        let address = u16::from(page) * PAGE_SIZE as u16;
+        let [low, high] = address.to_le_bytes();
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            let command = [Command::ReadAuthenticatedPage as u8, low, high];
+            wire.write_bytes(delay, &command)?;
+            let mut data = [0u8; PAGE_SIZE];
+            wire.read_bytes(delay, &mut data)?;
+            // the page is followed by a 0xFF byte
+            let mut filler = [0u8; 1];
+            wire.read_bytes(delay, &mut filler)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(compute_partial_crc16(0, &command), &data);
+            ensure_correct_crc16(compute_partial_crc16(computed, &filler), crc)?;
+
+            delay.delay_us(SHA_US);
+            let mut mac = [0u8; MAC_SIZE];
+            wire.read_bytes(delay, &mut mac)?;
+            wire.read_bytes(delay, &mut crc)?;
+            ensure_correct_crc16(compute_partial_crc16(0, &mac), crc)?;
+            Ok(AuthenticatedPage { data, mac })
+        })
+    }
+
+    /// Reads the page with the index `0` to `3` authenticated and checks the MAC of the
+    /// device against the one computed with the `secret`. Fails with [`Error::MacMismatch`]
+    /// if the device does not know the secret or the page was tampered with.
+    pub fn authenticate<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        page: u8,
+        secret: &[u8; SECRET_SIZE],
+        challenge: &[u8; 3],
+    ) -> Result<[u8; PAGE_SIZE], Error<W::Error>> {
+        let page_read = self.read_authenticated_page(wire, delay, page, challenge)?;
+        if page_read.mac != compute_read_mac(secret, &page_read.data, page, &self.device, challenge)
+        {
+            return Err(Error::MacMismatch);
+        }
+        Ok(page_read.data)
+    }
+
+    /// Writes a whole row, `address` being the address of its first byte, authorizing the
+    /// copy with a MAC computed with the `secret`. Fails with [`Error::CommandFailed`] if
+    /// the device did not accept the MAC.
+    pub fn write_row<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        row: &[u8; ROW_SIZE],
+        secret: &[u8; SECRET_SIZE],
+    ) -> Result<(), Error<W::Error>> {
+        self.start_write_row(wire, delay, address, row, secret)?
+            .wait(wire, delay)
+    }
+
+    /// Like [`DS2432::write_row`], but returns once the copy into the EEPROM started instead
+    /// of waiting for its programming time
+    pub fn start_write_row<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        row: &[u8; ROW_SIZE],
+        secret: &[u8; SECRET_SIZE],
+    ) -> Result<PendingCopy, Error<W::Error>> {
+        let page = address / PAGE_SIZE as u16;
+        let mut data = [0u8; PAGE_SIZE];
+        self.read(wire, delay, page * PAGE_SIZE as u16, &mut data)?;
+        let es = self.eeprom().prepare_block(wire, delay, address, row)?;
+        let mac = compute_write_mac(secret, &data, row, page as u8, &self.device);
+
+        let [low, high] = address.to_le_bytes();
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            wire.write_bytes(delay, &[Command::CopyScratchpad as u8, low, high, es])?;
+            delay.delay_us(SHA_US);
+            wire.write_bytes(delay, &mac)
+        })?;
+        Ok(PendingCopy::new(PROGRAMMING_US, true))
+    }
+
+    /// Sets the secret of the device, which needs no knowledge of the previous one unless
+    /// the secret was write protected
+    pub fn load_first_secret<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        secret: &[u8; SECRET_SIZE],
+    ) -> Result<(), Error<W::Error>> {
+        let es = self.eeprom().prepare_block(wire, delay, SECRET, secret)?;
+        let [low, high] = SECRET.to_le_bytes();
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            wire.write_bytes(delay, &[Command::LoadFirstSecret as u8, low, high, es])
+        })?;
+        PendingCopy::new(PROGRAMMING_US, true).wait(wire, delay)
+    }
+}
+
+/// The MAC a device with the `secret` answers Read Authenticated Page with
+pub fn compute_read_mac(
+    secret: &[u8; SECRET_SIZE],
+    data: &[u8; PAGE_SIZE],
+    page: u8,
+    device: &Device,
+    challenge: &[u8; 3],
+) -> [u8; MAC_SIZE] {
+    mac(&sha1(
+        &read_message(secret, data, page, device, challenge).0,
+    ))
+}
+
+fn read_message(
+    secret: &[u8; SECRET_SIZE],
+    data: &[u8; PAGE_SIZE],
+    page: u8,
+    device: &Device,
+    challenge: &[u8; 3],
+) -> Message {
+    let mut message = Message::new(secret);
+    message.put(4, data);
+    message.put(36, &[0xFF; 4]);
+    message.put(40, &[MESSAGE_READ | page & 0x03]);
+    message.put_address(device);
+    message.put(52, challenge);
+    message
+}
+
+/// The MAC authorizing a device with the `secret` to copy the `row` into the page with
+/// the index `page` that currently holds `data`. The index is the target address divided by
+/// [`PAGE_SIZE`], `4` for the secret and the registers following the user memory.
+pub fn compute_write_mac(
+    secret: &[u8; SECRET_SIZE],
+    data: &[u8; PAGE_SIZE],
+    row: &[u8; ROW_SIZE],
+    page: u8,
+    device: &Device,
+) -> [u8; MAC_SIZE] {
+    mac(&sha1(&write_message(secret, data, row, page, device).0))
+}
+
+fn write_message(
+    secret: &[u8; SECRET_SIZE],
+    data: &[u8; PAGE_SIZE],
+    row: &[u8; ROW_SIZE],
+    page: u8,
+    device: &Device,
+) -> Message {
+    let mut message = Message::new(secret);
+    message.put(4, data.get(..28).unwrap_or(&[]));
+    message.put(32, row);
+    // the address bits T7 to T5
+    message.put(40, &[page & 0x07]);
+    message.put_address(device);
+    message.put(52, &[0xFF; 3]);
+    message
+}
+
+/// The secret Compute Next Secret derives from the `secret`, the `data` of the page and
+/// the `scratchpad`, e.g. to give every device an individual secret
+pub fn compute_next_secret(
+    secret: &[u8; SECRET_SIZE],
+    data: &[u8; PAGE_SIZE],
+    scratchpad: &[u8; SECRET_SIZE],
+) -> [u8; SECRET_SIZE] {
+    let mut message = Message::new(secret);
+    message.put(4, data);
+    message.put(36, &[0xFF; 4]);
+    message.put(40, scratchpad);
+    message.put(52, &[0xFF; 3]);
+    let [e, d, ..] = sha1(&message.0);
+    let [e0, e1, e2, e3] = e.to_le_bytes();
+    let [d0, d1, d2, d3] = d.to_le_bytes();
+    [e0, e1, e2, e3, d0, d1, d2, d3]
+}
+
+/// The 64 byte block the SHA-1 engine hashes, already padded to the 55 bytes of content
+struct Message([u8; 64]);
+
+impl Message {
+    fn new(secret: &[u8; SECRET_SIZE]) -> Self {
+        let mut message = Message([0u8; 64]);
+        let [s0, s1, s2, s3, s4, s5, s6, s7] = *secret;
+        message.put(0, &[s0, s1, s2, s3]);
+        message.put(48, &[s4, s5, s6, s7]);
+        message.put(55, &[0x80]);
+        // the length of the content in bits
+        message.put(62, &[0x01, 0xB8]);
+        message
+    }
+
+    fn put(&mut self, offset: usize, bytes: &[u8]) {
+        for (byte, value) in self.0.iter_mut().skip(offset).zip(bytes) {
+            *byte = *value;
+        }
+    }
+
+    /// The address of the device without its CRC
+    fn put_address(&mut self, device: &Device) {
+        let [address @ .., _crc] = device.address;
+        self.put(41, &address);
+    }
+}
+
+/// The words E, D, C, B and A of the SHA-1 result, as a single 64 byte block hashes to
+fn sha1(message: &[u8; 64]) -> [u32; 5] {
+    const INITIAL: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+
+    let mut schedule = [0u32; 16];
+    for (word, bytes) in schedule.iter_mut().zip(message.chunks(4)) {
+        *word = bytes
+            .iter()
+            .fold(0, |word, byte| word << 8 | u32::from(*byte));
+    }
+    let [mut a, mut b, mut c, mut d, mut e] = INITIAL;
+    for round in 0..80 {
+        let (f, k) = match round {
+            0..=19 => (b & c | !b & d, 0x5A82_7999),
+            20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+            40..=59 => (b & c | b & d | c & d, 0x8F1B_BCDC),
+            _ => (b ^ c ^ d, 0xCA62_C1D6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(schedule[0]);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+        // the schedule always starts with the word of the current round
+        let next = (schedule[13] ^ schedule[8] ^ schedule[2] ^ schedule[0]).rotate_left(1);
+        schedule.rotate_left(1);
+        schedule[15] = next;
+    }
+    let [ia, ib, ic, id, ie] = INITIAL;
+    [
+        e.wrapping_add(ie),
+        d.wrapping_add(id),
+        c.wrapping_add(ic),
+        b.wrapping_add(ib),
+        a.wrapping_add(ia),
+    ]
+}
+
+/// The MAC as sent by the device, E to A with the least significant byte first
+fn mac(words: &[u32; 5]) -> [u8; MAC_SIZE] {
+    let mut mac = [0u8; MAC_SIZE];
+    for (bytes, word) in mac.chunks_mut(4).zip(words) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha1() {
+        // "abc" from FIPS 180
+        let mut message = [0u8; 64];
+        message[..4].copy_from_slice(&[b'a', b'b', b'c', 0x80]);
+        message[63] = 0x18;
+        assert_eq!(
+            [
+                0x9CD0_D89D,
+                0x7850_C26C,
+                0xBA3E_2571,
+                0x4706_816A,
+                0xA999_3E36
+            ],
+            sha1(&message)
+        );
+    }
+
+    #[test]
+    fn test_read_message() {
+        let device = Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let secret = [1, 2, 3, 4, 5, 6, 7, 8];
+        let Message(message) = read_message(&secret, &[0x55; PAGE_SIZE], 1, &device, &[9, 10, 11]);
+        assert_eq!([1, 2, 3, 4, 0x55], message[..5]);
+        assert_eq!([0x55, 0xFF, 0xFF, 0xFF, 0xFF, 0x41], message[35..41]);
+        assert_eq!([FAMILY_CODE, 1, 2, 3, 4, 5, 6], message[41..48]);
+        assert_eq!([5, 6, 7, 8, 9, 10, 11, 0x80, 0], message[48..57]);
+        assert_eq!([0x01, 0xB8], message[62..]);
+    }
+
+    #[test]
+    fn test_read_mac() {
+        // the message is padded like SHA-1 pads its first 55 bytes, so the expected MAC is the
+        // digest of those bytes by any SHA-1 implementation, its words E to A little endian
+        let device = Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let secret = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(
+            [
+                0xAD, 0x10, 0x1C, 0xCF, 0x98, 0xD6, 0xB2, 0x89, 0xA8, 0x66, 0xB5, 0xF2, 0x9F, 0x6B,
+                0xAC, 0x05, 0xA0, 0x5D, 0x3C, 0xF5
+            ],
+            compute_read_mac(&secret, &[0x55; PAGE_SIZE], 1, &device, &[9, 10, 11])
+        );
+    }
+
+    #[test]
+    fn test_write_message() {
+        let device = Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let secret = [1, 2, 3, 4, 5, 6, 7, 8];
+        let Message(message) =
+            write_message(&secret, &[0x55; PAGE_SIZE], &[0xAA; ROW_SIZE], 3, &device);
+        assert_eq!([0x55, 0xAA, 0xAA, 0xAA, 0xAA], message[31..36]);
+        assert_eq!(0x03, message[40]);
+        // the register page above the user memory
+        let Message(message) =
+            write_message(&secret, &[0xFF; PAGE_SIZE], &[0xAA; ROW_SIZE], 4, &device);
+        assert_eq!(0x04, message[40]);
+    }
+}
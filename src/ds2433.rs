@@ -0,0 +1,178 @@
+//! The DS2433 is a 4 Kb EEPROM of sixteen pages of 32 bytes. It is written page by page
+//! through a scratchpad of one page, with the same protocol as the DS2431, and can be read
+//! in bulk from any address up to the end of the memory.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::memory::{check_range, Memory, OneWireEeprom, PendingCopy};
+use crate::scratchpad::ScratchpadEeprom;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+
+pub use crate::scratchpad::Command;
+
+pub const FAMILY_CODE: u8 = crate::family::DS2433;
+
+/// Size of the memory in bytes
+pub const MEMORY_SIZE: u16 = 512;
+/// The memory is written in pages of this many bytes
+pub const PAGE_SIZE: usize = 32;
+/// How long copying the scratchpad into the EEPROM takes at most
+pub const PROGRAMMING_US: u16 = 5_000;
+
+pub struct DS2433 {
+    device: Device,
+}
+
+impl DS2433 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: true,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2433, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2433 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn eeprom(&self) -> ScratchpadEeprom<'_, PAGE_SIZE> {
+        ScratchpadEeprom {
+            device: &self.device,
+            programming_us: PROGRAMMING_US,
+        }
+    }
+
+    /// Reads the data starting at `address` in one go, across page boundaries
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().read(wire, delay, address, data)
+    }
+
+    /// Writes a whole page, `address` being the address of its first byte. Fails with
+    /// [`Error::CommandFailed`] if the scratchpad did not receive the page or the copy
+    /// failed.
+    pub fn write_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        page: &[u8; PAGE_SIZE],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().write_block(wire, delay, address, page)
+    }
+
+    /// Like [`DS2433::write_page`], but returns once the copy into the EEPROM started instead
+    /// of waiting for its programming time
+    pub fn start_write_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        page: &[u8; PAGE_SIZE],
+    ) -> Result<PendingCopy, Error<W::Error>> {
+        self.eeprom().start_write_block(wire, delay, address, page)
+    }
+}
+
+impl Memory for DS2433 {
+    fn size(&self) -> u16 {
+        MEMORY_SIZE
+    }
+
+    fn read_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), MEMORY_SIZE)?;
+        self.read(wire, delay, address, data)
+    }
+
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), MEMORY_SIZE)?;
+        self.eeprom().write(wire, delay, address, data)
+    }
+}
+
+impl OneWireEeprom for DS2433 {
+    fn page_size(&self) -> u16 {
+        PAGE_SIZE as u16
+    }
+
+    fn programming_us(&self) -> u16 {
+        PROGRAMMING_US
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, FakeEeprom};
+
+    #[test]
+    fn test_write_across_pages() {
+        let eeprom = DS2433::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap();
+        let mut wire = FakeEeprom::<PAGE_SIZE>::new(None);
+        eeprom
+            .eeprom()
+            .write(&mut wire, &mut Delay, 30, &[1, 2, 3, 4])
+            .unwrap();
+        let mut data = [0u8; 8];
+        eeprom.read(&mut wire, &mut Delay, 28, &mut data).unwrap();
+        assert_eq!([0xFF, 0xFF, 1, 2, 3, 4, 0xFF, 0xFF], data);
+        assert_eq!(
+            2 * u32::from(PROGRAMMING_US),
+            eeprom.write_duration_us(30, 4)
+        );
+        assert_eq!(u32::from(PROGRAMMING_US), eeprom.write_duration_us(32, 32));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let eeprom = DS2433::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap();
+        let mut wire = FakeEeprom::<PAGE_SIZE>::new(None);
+        let mut data = [0u8; 4];
+        assert!(matches!(
+            eeprom.read_memory(&mut wire, &mut Delay, MEMORY_SIZE - 3, &mut data),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            eeprom.write_memory(&mut wire, &mut Delay, u16::MAX - 1, &data),
+            Err(Error::OutOfRange)
+        ));
+    }
+}
@@ -0,0 +1,458 @@
+//! The DS2450 is a quad A/D converter. Its memory consists of four pages of 8 bytes: the
+//! conversion results, the control and status of the channels, their alarm thresholds and
+//! the calibration. Reads of the memory are protected by a CRC16 at the end of each page,
+//! writes by a CRC16 over every written byte.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::ConditionalSearch;
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OneWireMaster;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::Unit;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+
+pub const FAMILY_CODE: u8 = crate::family::DS2450;
+
+pub const CHANNELS: usize = 4;
+
+pub const PAGE_SIZE: usize = 8;
+const RESULTS_PAGE: u16 = 0x00;
+const CONTROL_PAGE: u16 = 0x08;
+const ALARM_PAGE: u16 = 0x10;
+/// Writing [`VCC_POWERED`] tells the device that it is not parasite powered
+const VCC_ADDRESS: u16 = 0x1C;
+const VCC_POWERED: u8 = 0x40;
+
+/// How long the conversion of a bit takes, plus a fixed offset per conversion
+const CONVERSION_US_PER_BIT: u16 = 80;
+const CONVERSION_OFFSET_US: u16 = 160;
+
+#[repr(u8)]
+pub enum Command {
+    ReadMemory = 0xAA,
+    WriteMemory = 0x55,
+    Convert = 0x3C,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputRange {
+    /// 0 to 2.56 V
+    Low,
+    /// 0 to 5.12 V
+    High,
+}
+
+impl InputRange {
+    pub fn millivolts(&self) -> u32 {
+        match self {
+            InputRange::Low => 2_560,
+            InputRange::High => 5_120,
+        }
+    }
+}
+
+/// The configuration of a channel as input, its output transistor is turned off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelConfig {
+    /// Bits of a conversion, from 1 to 16
+    pub resolution: u8,
+    pub range: InputRange,
+    /// The threshold of the alarm search on the 8 most significant bits of the result, the
+    /// device answers it if the result is below
+    pub alarm_low: Option<u8>,
+    /// Like `alarm_low`, the device answers the alarm search if the result is above
+    pub alarm_high: Option<u8>,
+}
+
+impl ChannelConfig {
+    /// The two bytes of the channel in the control and status page
+    fn control_bytes(&self) -> [u8; 2] {
+        debug_assert!((1..=16).contains(&self.resolution));
+        // 0 selects 16 bits
+        let resolution = self.resolution & 0x0F;
+        let range = match self.range {
+            InputRange::Low => 0x00,
+            InputRange::High => 0x01,
+        };
+        [
+            resolution,
+            range | alarm_enable(self.alarm_low, self.alarm_high),
+        ]
+    }
+
+    /// The two bytes of the channel in the alarm page
+    fn alarm_bytes(&self) -> [u8; 2] {
+        alarm_bytes(self.alarm_low, self.alarm_high)
+    }
+}
+
+/// The alarm enable bits of the second control byte of a channel
+fn alarm_enable(low: Option<u8>, high: Option<u8>) -> u8 {
+    u8::from(low.is_some()) << 2 | u8::from(high.is_some()) << 3
+}
+
+/// The thresholds of a channel in the alarm page, disabled ones are set to never trigger
+fn alarm_bytes(low: Option<u8>, high: Option<u8>) -> [u8; 2] {
+    [low.unwrap_or(0x00), high.unwrap_or(0xFF)]
+}
+
+/// The alarm thresholds of all channels for the conditional search, the device answers it
+/// if a conversion result is out of the enabled thresholds, see [`ChannelConfig::alarm_low`]
+/// and [`ChannelConfig::alarm_high`]. The resolution and range of the channels are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchCondition {
+    pub alarm_low: [Option<u8>; CHANNELS],
+    pub alarm_high: [Option<u8>; CHANNELS],
+}
+
+impl SearchCondition {
+    /// The control and status page with the alarm enable bits of the condition
+    fn control_page(&self, page: [u8; PAGE_SIZE]) -> [u8; PAGE_SIZE] {
+        let mut page = page;
+        for (channel, bytes) in page.chunks_exact_mut(2).enumerate() {
+            let low = self.alarm_low.get(channel).copied().flatten();
+            let high = self.alarm_high.get(channel).copied().flatten();
+            if let [_, control] = bytes {
+                // keeps the input range, the alarm flags and power on reset are cleared
+                *control = *control & 0x01 | alarm_enable(low, high);
+            }
+        }
+        page
+    }
+
+    fn alarm_page(&self) -> [u8; PAGE_SIZE] {
+        let mut page = [0u8; PAGE_SIZE];
+        for (channel, bytes) in page.chunks_exact_mut(2).enumerate() {
+            let low = self.alarm_low.get(channel).copied().flatten();
+            let high = self.alarm_high.get(channel).copied().flatten();
+            bytes.copy_from_slice(&alarm_bytes(low, high));
+        }
+        page
+    }
+}
+
+/// Converts a conversion result, which is aligned to the most significant bit regardless
+/// of the resolution, into millivolts
+pub fn raw_to_millivolts(raw: u16, range: InputRange) -> u32 {
+    u32::from(raw) * range.millivolts() / 0x1_0000
+}
+
+pub struct DS2450 {
+    device: Device,
+}
+
+impl DS2450 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: false,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2450, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS2450 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Configures the channel with the index `0` to `3`
+    pub fn configure_channel<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        channel: usize,
+        config: ChannelConfig,
+    ) -> Result<(), Error<W::Error>> {
+        debug_assert!(channel < CHANNELS);
+        let offset = 2 * channel as u16;
+        self.write_memory(wire, delay, CONTROL_PAGE + offset, &config.control_bytes())?;
+        self.write_memory(wire, delay, ALARM_PAGE + offset, &config.alarm_bytes())
+    }
+
+    /// Tells the device that it is powered through VCC, so it does not need the wire held
+    /// high during conversions
+    pub fn set_vcc_powered<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_memory(wire, delay, VCC_ADDRESS, &[VCC_POWERED])
+    }
+
+    /// Converts the channels set in the lower four bits of `channels` and waits as long as
+    /// a conversion of all of them with 16 bits takes
+    pub fn convert<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        channels: u8,
+    ) -> Result<(), Error<W::Error>> {
+        let us = start_conversion(wire, delay, &self.device, channels)?;
+        delay.delay_us(us);
+        Ok(())
+    }
+
+    /// The channel with the index `0` to `3` as [`Sensor`], whose values are converted
+    /// with the given range, see [`DS2450::configure_channel`]
+    pub fn channel(&self, channel: usize, range: InputRange) -> Channel {
+        debug_assert!(channel < CHANNELS);
+        Channel {
+            adc: DS2450 {
+                device: self.device.clone(),
+            },
+            channel,
+            range,
+        }
+    }
+
+    /// Reads the results of the last conversions, aligned to the most significant bit
+    pub fn read_results<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<[u16; CHANNELS], Error<W::Error>> {
+        let [a0, a1, b0, b1, c0, c1, d0, d1] = self.read_page(wire, delay, RESULTS_PAGE)?;
+        Ok([
+            u16::from_le_bytes([a0, a1]),
+            u16::from_le_bytes([b0, b1]),
+            u16::from_le_bytes([c0, c1]),
+            u16::from_le_bytes([d0, d1]),
+        ])
+    }
+
+    /// Reads the page starting at `address` and checks its CRC16
+    pub fn read_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+    ) -> Result<[u8; PAGE_SIZE], Error<W::Error>> {
+        debug_assert!(address & (PAGE_SIZE as u16 - 1) == 0);
+        wire.transaction(|wire| {
+            if !wire.reset(delay)? {
+                return Err(Error::NoPresencePulse);
+            }
+            wire.select(delay, &self.device)?;
+            let [low, high] = address.to_le_bytes();
+            let command = [Command::ReadMemory as u8, low, high];
+            wire.write_bytes(delay, &command)?;
+            let mut page = [0u8; PAGE_SIZE];
+            wire.read_bytes(delay, &mut page)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(compute_partial_crc16(0, &command), &page);
+            ensure_correct_crc16(computed, crc)?;
+            Ok(page)
+        })
+    }
+
+    /// Writes the bytes one by one, each followed by a CRC16 and the read back byte
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            if !wire.reset(delay)? {
+                return Err(Error::NoPresencePulse);
+            }
+            wire.select(delay, &self.device)?;
+            let [low, high] = address.to_le_bytes();
+            wire.write_bytes(delay, &[Command::WriteMemory as u8, low, high])?;
+            for (offset, byte) in data.iter().enumerate() {
+                wire.write_bytes(delay, &[*byte])?;
+                let mut crc = [0u8; 2];
+                wire.read_bytes(delay, &mut crc)?;
+                // the CRC16 of the first byte covers the command and the address, the ones
+                // of the following bytes their incremented address
+                let [low, high] = (address + offset as u16).to_le_bytes();
+                let computed = if offset == 0 {
+                    compute_partial_crc16(0, &[Command::WriteMemory as u8, low, high, *byte])
+                } else {
+                    compute_partial_crc16(0, &[low, high, *byte])
+                };
+                ensure_correct_crc16(computed, crc)?;
+                let echo = wire.read_byte(delay)?;
+                if echo != *byte {
+                    return Err(Error::CommandFailed(echo));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ConditionalSearch for DS2450 {
+    type Condition = SearchCondition;
+
+    fn configure_conditional_search<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        condition: &SearchCondition,
+    ) -> Result<(), Error<O::Error>> {
+        let control = self.read_page(wire, delay, CONTROL_PAGE)?;
+        self.write_memory(wire, delay, ALARM_PAGE, &condition.alarm_page())?;
+        self.write_memory(wire, delay, CONTROL_PAGE, &condition.control_page(control))
+    }
+}
+
+/// Starts the conversion of the channels set in the lower four bits of `channels`, returns
+/// the microseconds a conversion of all of them with 16 bits takes
+fn start_conversion<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    device: &Device,
+    channels: u8,
+) -> Result<u16, Error<W::Error>> {
+    let channels = channels & 0x0F;
+    wire.transaction(|wire| {
+        if !wire.reset(delay)? {
+            return Err(Error::NoPresencePulse);
+        }
+        wire.select(delay, device)?;
+        // no preset of the results before the conversion
+        let command = [Command::Convert as u8, channels, 0x00];
+        wire.write_bytes(delay, &command)?;
+        let mut crc = [0u8; 2];
+        wire.read_bytes(delay, &mut crc)?;
+        ensure_correct_crc16(compute_partial_crc16(0, &command), crc)
+    })?;
+    let bits = channels.count_ones() as u16 * 16;
+    Ok(bits * CONVERSION_US_PER_BIT + CONVERSION_OFFSET_US)
+}
+
+/// A single channel of a [`DS2450`], measuring in volts
+pub struct Channel {
+    adc: DS2450,
+    channel: usize,
+    range: InputRange,
+}
+
+impl Channel {
+    pub fn adc(&self) -> &DS2450 {
+        &self.adc
+    }
+
+    pub fn index(&self) -> usize {
+        self.channel
+    }
+}
+
+impl Sensor for Channel {
+    fn family_code() -> u8 {
+        FAMILY_CODE
+    }
+
+    fn device(&self) -> &Device {
+        &self.adc.device
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Volt
+    }
+
+    fn raw_to_milli(&self, raw: u16) -> i32 {
+        raw_to_millivolts(raw, self.range) as i32
+    }
+
+    /// Converts only this channel
+    fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        let us = start_conversion(wire, delay, &self.adc.device, 1 << self.channel)?;
+        Ok(us.div_ceil(1000))
+    }
+
+    #[cfg(not(feature = "no-float"))]
+    fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<f32, Error<O::Error>> {
+        self.read_measurement_milli(wire, delay)
+            .map(|millivolts| millivolts as f32 / 1000_f32)
+    }
+
+    fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        let results = self.adc.read_results(wire, delay)?;
+        results
+            .get(self.channel)
+            .copied()
+            .ok_or(Error::CommandFailed(self.channel as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_config() {
+        let config = ChannelConfig {
+            resolution: 16,
+            range: InputRange::High,
+            alarm_low: None,
+            alarm_high: Some(0xC0),
+        };
+        assert_eq!([0x00, 0x09], config.control_bytes());
+        assert_eq!([0x00, 0xC0], config.alarm_bytes());
+
+        let config = ChannelConfig {
+            resolution: 8,
+            range: InputRange::Low,
+            alarm_low: Some(0x10),
+            alarm_high: None,
+        };
+        assert_eq!([0x08, 0x04], config.control_bytes());
+        assert_eq!([0x10, 0xFF], config.alarm_bytes());
+    }
+
+    #[test]
+    fn test_search_condition() {
+        let condition = SearchCondition {
+            alarm_low: [Some(0x10), None, None, None],
+            alarm_high: [None, None, Some(0xC0), None],
+        };
+        let control = [0x00, 0x81, 0x08, 0x3D, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(
+            [0x00, 0x05, 0x08, 0x01, 0x00, 0x08, 0x00, 0x01],
+            condition.control_page(control)
+        );
+        assert_eq!(
+            [0x10, 0xFF, 0x00, 0xFF, 0x00, 0xC0, 0x00, 0xFF],
+            condition.alarm_page()
+        );
+    }
+
+    #[test]
+    fn test_raw_to_millivolts() {
+        assert_eq!(0, raw_to_millivolts(0x0000, InputRange::Low));
+        assert_eq!(1_280, raw_to_millivolts(0x8000, InputRange::Low));
+        assert_eq!(2_560, raw_to_millivolts(0x8000, InputRange::High));
+        assert_eq!(5_119, raw_to_millivolts(0xFFFF, InputRange::High));
+    }
+}
@@ -0,0 +1,499 @@
+//! The DS2482-100 is an I2C to 1-Wire bridge generating the reset pulse and the time slots
+//! in hardware, so the timing of the bus no longer depends on the interrupt latency of the
+//! microcontroller. It implements [`OneWireMaster`], so device search and all drivers work
+//! with it as with a bit-banged [`crate::OneWire`].
+//!
+//! The DS2482-800 drives eight independent buses, see [`DS2482::channel`].
+
+use core::fmt::Debug;
+
+use hal::blocking::delay::DelayUs;
+use hal::blocking::i2c::{Read, Write, WriteRead};
+
+use crate::Error;
+use crate::OneWireMaster;
+
+/// I2C address with both address pins low
+pub const DEFAULT_ADDRESS: u8 = 0x18;
+
+/// Status polls until a 1-Wire command is considered stuck
+const BUSY_POLLS: u16 = 200;
+const BUSY_POLL_US: u16 = 10;
+
+#[repr(u8)]
+pub enum Command {
+    DeviceReset = 0xF0,
+    SetReadPointer = 0xE1,
+    WriteConfiguration = 0xD2,
+    OneWireReset = 0xB4,
+    OneWireSingleBit = 0x87,
+    OneWireWriteByte = 0xA5,
+    OneWireReadByte = 0x96,
+    OneWireTriplet = 0x78,
+    /// DS2482-800 only
+    ChannelSelect = 0xC3,
+}
+
+#[repr(u8)]
+pub enum Register {
+    Status = 0xF0,
+    ReadData = 0xE1,
+    Configuration = 0xC3,
+    /// DS2482-800 only
+    ChannelSelection = 0xD2,
+}
+
+/// Number of channels of the DS2482-800
+pub const CHANNELS: u8 = 8;
+
+/// The code selecting each channel and the value the channel selection register reads
+/// back after it
+const CHANNEL_CODES: [(u8, u8); CHANNELS as usize] = [
+    (0xF0, 0xB8),
+    (0xE1, 0xB1),
+    (0xD2, 0xAA),
+    (0xC3, 0xA3),
+    (0xB4, 0x9C),
+    (0xA5, 0x95),
+    (0x96, 0x8E),
+    (0x87, 0x87),
+];
+
+pub const STATUS_BUSY: u8 = 0x01;
+pub const STATUS_PRESENCE_PULSE: u8 = 0x02;
+pub const STATUS_SHORT: u8 = 0x04;
+pub const STATUS_LOGIC_LEVEL: u8 = 0x08;
+pub const STATUS_DEVICE_RESET: u8 = 0x10;
+pub const STATUS_SINGLE_BIT: u8 = 0x20;
+pub const STATUS_TRIPLET_SECOND_BIT: u8 = 0x40;
+pub const STATUS_BRANCH_DIRECTION: u8 = 0x80;
+
+#[derive(Debug)]
+pub enum BridgeError<E: Debug> {
+    I2c(E),
+    /// The bridge did not finish the 1-Wire command in time
+    Busy,
+    /// The bridge did not report a reset after [`Command::DeviceReset`]
+    NotFound,
+    /// The channel does not exist or the bridge did not switch to it (e.g. a DS2482-100)
+    InvalidChannel(u8),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Actively pulls the bus high at the end of time slots, for longer buses
+    pub active_pullup: bool,
+    /// Pulls the bus high strongly after the next byte or bit, to power parasite devices
+    /// (e.g. a DS18B20 converting)
+    pub strong_pullup: bool,
+    pub overdrive: bool,
+}
+
+impl Config {
+    /// The register value, whose upper nibble is the complement of the lower one
+    fn to_register(self) -> u8 {
+        let bits = u8::from(self.active_pullup)
+            | u8::from(self.strong_pullup) << 2
+            | u8::from(self.overdrive) << 3;
+        bits | (!bits << 4)
+    }
+}
+
+pub struct DS2482<I2C> {
+    i2c: I2C,
+    address: u8,
+    channel: Option<u8>,
+}
+
+impl<E: Debug, I2C: Write<Error = E> + Read<Error = E> + WriteRead<Error = E>> DS2482<I2C> {
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        DS2482 {
+            i2c,
+            address,
+            channel: None,
+        }
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Resets the bridge and terminates any 1-Wire communication in progress
+    pub fn device_reset(&mut self) -> Result<(), Error<BridgeError<E>>> {
+        self.write(&[Command::DeviceReset as u8])?;
+        self.channel = None;
+        if self.read_register_pointed()? & STATUS_DEVICE_RESET == 0 {
+            return Err(Error::PortError(BridgeError::NotFound));
+        }
+        Ok(())
+    }
+
+    /// Applies the configuration, which returns to the default after every 1-Wire reset
+    /// except for the active pull-up
+    pub fn configure(&mut self, config: Config) -> Result<(), Error<BridgeError<E>>> {
+        self.write(&[Command::WriteConfiguration as u8, config.to_register()])
+    }
+
+    /// Switches the DS2482-800 to the bus of the channel (0 to 7)
+    pub fn select_channel(&mut self, channel: u8) -> Result<(), Error<BridgeError<E>>> {
+        let (code, readback) = *CHANNEL_CODES
+            .get(usize::from(channel))
+            .ok_or(BridgeError::InvalidChannel(channel))?;
+        self.channel = None;
+        self.write(&[Command::ChannelSelect as u8, code])?;
+        // the read pointer is at the channel selection register now
+        if self.read_register_pointed()? != readback {
+            return Err(Error::PortError(BridgeError::InvalidChannel(channel)));
+        }
+        self.channel = Some(channel);
+        Ok(())
+    }
+
+    /// The bus of a channel of the DS2482-800, which is selected before each command
+    pub fn channel(&mut self, channel: u8) -> Result<Channel<'_, I2C>, Error<BridgeError<E>>> {
+        self.select_channel(channel)?;
+        Ok(Channel {
+            bridge: self,
+            channel,
+        })
+    }
+
+    fn ensure_channel(&mut self, channel: u8) -> Result<(), Error<BridgeError<E>>> {
+        if self.channel != Some(channel) {
+            self.select_channel(channel)?;
+        }
+        Ok(())
+    }
+
+    pub fn status(&mut self) -> Result<u8, Error<BridgeError<E>>> {
+        self.read_register(Register::Status)
+    }
+
+    fn read_register(&mut self, register: Register) -> Result<u8, Error<BridgeError<E>>> {
+        let mut value = [0u8];
+        self.i2c
+            .write_read(
+                self.address,
+                &[Command::SetReadPointer as u8, register as u8],
+                &mut value,
+            )
+            .map_err(BridgeError::I2c)?;
+        Ok(value[0])
+    }
+
+    /// Reads the register the read pointer was set to by the last command
+    fn read_register_pointed(&mut self) -> Result<u8, Error<BridgeError<E>>> {
+        let mut value = [0u8];
+        self.i2c
+            .read(self.address, &mut value)
+            .map_err(BridgeError::I2c)?;
+        Ok(value[0])
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error<BridgeError<E>>> {
+        Ok(self
+            .i2c
+            .write(self.address, bytes)
+            .map_err(BridgeError::I2c)?)
+    }
+
+    /// Issues a 1-Wire command and returns the status once the bridge finished it
+    fn execute(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        command: &[u8],
+    ) -> Result<u8, Error<BridgeError<E>>> {
+        self.write(command)?;
+        // commands leave the read pointer at the status register
+        for _ in 0..BUSY_POLLS {
+            let status = self.read_register_pointed()?;
+            if status & STATUS_BUSY == 0 {
+                return Ok(status);
+            }
+            delay.delay_us(BUSY_POLL_US);
+        }
+        Err(Error::PortError(BridgeError::Busy))
+    }
+}
+
+impl<E: Debug, I2C: Write<Error = E> + Read<Error = E> + WriteRead<Error = E>> OneWireMaster
+    for DS2482<I2C>
+{
+    type Error = BridgeError<E>;
+
+    fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>> {
+        let status = self.execute(delay, &[Command::OneWireReset as u8])?;
+        if status & STATUS_SHORT != 0 {
+            return Err(Error::WireNotHigh);
+        }
+        Ok(status & STATUS_PRESENCE_PULSE != 0)
+    }
+
+    fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>> {
+        let status = self.execute(delay, &[Command::OneWireSingleBit as u8, 0x80])?;
+        Ok(status & STATUS_SINGLE_BIT != 0)
+    }
+
+    fn write_bit(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<Self::Error>> {
+        let bit = if high { 0x80 } else { 0x00 };
+        self.execute(delay, &[Command::OneWireSingleBit as u8, bit])?;
+        Ok(())
+    }
+
+    fn triplet(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        direction: bool,
+    ) -> Result<(bool, bool, bool), Error<Self::Error>> {
+        let direction = if direction { 0x80 } else { 0x00 };
+        let status = self.execute(delay, &[Command::OneWireTriplet as u8, direction])?;
+        Ok((
+            status & STATUS_SINGLE_BIT != 0,
+            status & STATUS_TRIPLET_SECOND_BIT != 0,
+            status & STATUS_BRANCH_DIRECTION != 0,
+        ))
+    }
+
+    fn read_byte(&mut self, delay: &mut impl DelayUs<u16>) -> Result<u8, Error<Self::Error>> {
+        self.execute(delay, &[Command::OneWireReadByte as u8])?;
+        self.read_register(Register::ReadData)
+    }
+
+    fn write_byte(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        byte: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        self.execute(delay, &[Command::OneWireWriteByte as u8, byte])?;
+        Ok(())
+    }
+}
+
+/// A bus of the DS2482-800, see [`DS2482::channel`]
+pub struct Channel<'a, I2C> {
+    bridge: &'a mut DS2482<I2C>,
+    channel: u8,
+}
+
+impl<'a, I2C> Channel<'a, I2C> {
+    pub fn number(&self) -> u8 {
+        self.channel
+    }
+}
+
+impl<'a, E: Debug, I2C: Write<Error = E> + Read<Error = E> + WriteRead<Error = E>> OneWireMaster
+    for Channel<'a, I2C>
+{
+    type Error = BridgeError<E>;
+
+    fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>> {
+        self.bridge.ensure_channel(self.channel)?;
+        self.bridge.reset(delay)
+    }
+
+    fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>> {
+        self.bridge.ensure_channel(self.channel)?;
+        self.bridge.read_bit(delay)
+    }
+
+    fn write_bit(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<Self::Error>> {
+        self.bridge.ensure_channel(self.channel)?;
+        self.bridge.write_bit(delay, high)
+    }
+
+    fn triplet(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        direction: bool,
+    ) -> Result<(bool, bool, bool), Error<Self::Error>> {
+        self.bridge.ensure_channel(self.channel)?;
+        self.bridge.triplet(delay, direction)
+    }
+
+    fn read_byte(&mut self, delay: &mut impl DelayUs<u16>) -> Result<u8, Error<Self::Error>> {
+        self.bridge.ensure_channel(self.channel)?;
+        self.bridge.read_byte(delay)
+    }
+
+    fn write_byte(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        byte: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        self.bridge.ensure_channel(self.channel)?;
+        self.bridge.write_byte(delay, byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Seeded, SimDelay, SimPin, Simulator};
+    use crate::{DeviceSearch, OneWire};
+    use core::convert::Infallible;
+
+    /// Executes the commands of the bridge on a simulated bus
+    struct Bridge<'a, const N: usize> {
+        wire: OneWire<SimPin<'a, N>>,
+        delay: SimDelay<'a, N>,
+        status: u8,
+        data: u8,
+        pointer: u8,
+        channel: u8,
+    }
+
+    impl<const N: usize> Write for Bridge<'_, N> {
+        type Error = Infallible;
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Infallible> {
+            assert_eq!(DEFAULT_ADDRESS, address);
+            self.pointer = Register::Status as u8;
+            let bit = |byte: u8| byte & 0x80 != 0;
+            let wire = &mut self.wire;
+            let delay = &mut self.delay;
+            match bytes {
+                [0xF0] => self.status = STATUS_DEVICE_RESET,
+                [0xE1, pointer] => self.pointer = *pointer,
+                [0xD2, _] => {}
+                [0xC3, code] => {
+                    self.pointer = Register::ChannelSelection as u8;
+                    if let Some(channel) = CHANNEL_CODES.iter().position(|(c, _)| c == code) {
+                        self.channel = channel as u8;
+                    }
+                }
+                [0xB4] => {
+                    let presence = OneWireMaster::reset(wire, delay).unwrap();
+                    self.status = if presence { STATUS_PRESENCE_PULSE } else { 0 };
+                }
+                [0x87, byte] => {
+                    let read = if bit(*byte) {
+                        OneWireMaster::read_bit(wire, delay).unwrap()
+                    } else {
+                        OneWireMaster::write_bit(wire, delay, false).unwrap();
+                        false
+                    };
+                    self.status = if read { STATUS_SINGLE_BIT } else { 0 };
+                }
+                [0xA5, byte] => wire.write_byte(delay, *byte).unwrap(),
+                [0x96] => self.data = OneWireMaster::read_byte(wire, delay).unwrap(),
+                [0x78, direction] => {
+                    let (bit0, bit1, written) = wire.triplet(delay, bit(*direction)).unwrap();
+                    self.status =
+                        u8::from(bit0) << 5 | u8::from(bit1) << 6 | u8::from(written) << 7;
+                }
+                bytes => panic!("unexpected {:x?}", bytes),
+            }
+            Ok(())
+        }
+    }
+
+    impl<const N: usize> Read for Bridge<'_, N> {
+        type Error = Infallible;
+
+        fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Infallible> {
+            buffer[0] = match self.pointer {
+                0xF0 => self.status,
+                0xE1 => self.data,
+                0xD2 => CHANNEL_CODES[usize::from(self.channel)].1,
+                pointer => panic!("unexpected pointer {:x}", pointer),
+            };
+            Ok(())
+        }
+    }
+
+    impl<const N: usize> WriteRead for Bridge<'_, N> {
+        type Error = Infallible;
+
+        fn write_read(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Infallible> {
+            self.write(address, bytes)?;
+            self.read(address, buffer)
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let sim = Simulator::<6>::new(&mut Seeded {
+            family: 0x28,
+            state: 9,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut bridge = DS2482::new(
+            Bridge {
+                wire: OneWire::new(SimPin(&sim), false),
+                delay: SimDelay(&sim),
+                status: 0,
+                data: 0,
+                pointer: 0,
+                channel: 0,
+            },
+            DEFAULT_ADDRESS,
+        );
+        bridge.device_reset().unwrap();
+        bridge.configure(Config::default()).unwrap();
+
+        let mut delay = SimDelay(&sim);
+        let mut found = 0;
+        for device in DeviceSearch::new().into_iter(&mut bridge, &mut delay) {
+            assert!(addresses.contains(&device.unwrap().address));
+            found += 1;
+        }
+        assert_eq!(6, found);
+    }
+
+    #[test]
+    fn test_channel() {
+        let sim = Simulator::<3>::new(&mut Seeded {
+            family: 0x28,
+            state: 4,
+        });
+        let mut bridge = DS2482::new(
+            Bridge {
+                wire: OneWire::new(SimPin(&sim), false),
+                delay: SimDelay(&sim),
+                status: 0,
+                data: 0,
+                pointer: 0,
+                channel: 0,
+            },
+            DEFAULT_ADDRESS,
+        );
+        assert!(matches!(
+            bridge.select_channel(CHANNELS),
+            Err(Error::PortError(BridgeError::InvalidChannel(8)))
+        ));
+
+        let mut delay = SimDelay(&sim);
+        let mut channel = bridge.channel(5).unwrap();
+        assert_eq!(
+            3,
+            DeviceSearch::new()
+                .into_iter(&mut channel, &mut delay)
+                .count()
+        );
+        assert_eq!(5, bridge.release().channel);
+    }
+
+    #[test]
+    fn test_config_register() {
+        assert_eq!(0xF0, Config::default().to_register());
+        let config = Config {
+            active_pullup: true,
+            strong_pullup: true,
+            overdrive: false,
+        };
+        assert_eq!(0xA5, config.to_register());
+    }
+}
@@ -0,0 +1,218 @@
+//! The DS2502 (1 Kb) and DS2505 (16 Kb) are add-only memories: their EPROM is programmed
+//! once with a 12 V pulse, which is not supported, and read like any other memory. Every
+//! read starts with the CRC of the command and address, a CRC8 on the DS2502 and a CRC16 on
+//! the DS2505, and the paged reads add the CRC of each page. The DS2502-E48 variant holds an
+//! IEEE EUI-48 MAC address, see [`DS2502::read_eui48`].
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+use crate::{check_crc8, compute_partial_crc16, ensure_correct_crc16, CRC16_RESIDUE};
+
+pub const FAMILY_CODE: u8 = crate::family::DS2502;
+pub const FAMILY_CODE_DS2505: u8 = crate::family::DS2505;
+
+pub const PAGE_SIZE: usize = 32;
+/// Size of the memory of the DS2502 in bytes
+pub const MEMORY_SIZE: u16 = 128;
+/// Size of the memory of the DS2505 in bytes
+pub const MEMORY_SIZE_DS2505: u16 = 2048;
+/// The length of the data of the EUI-48 packet
+const EUI48_SIZE: usize = 6;
+
+#[repr(u8)]
+pub enum Command {
+    ReadMemory = 0xF0,
+    ReadStatus = 0xAA,
+    /// Reads up to the end of the page followed by its CRC8, DS2502 only
+    ReadDataCrc8 = 0xC3,
+    /// Reads the redirection byte and the page followed by its CRC16, DS2505 only
+    ExtendedReadMemory = 0xA5,
+}
+
+pub struct DS2502 {
+    device: Device,
+}
+
+impl DS2502 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: false,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    /// Accepts the DS2502 as well as the DS2505
+    pub fn new(device: Device) -> Result<DS2502, Error<Infallible>> {
+        match device.address[0] {
+            FAMILY_CODE | FAMILY_CODE_DS2505 => Ok(DS2502 { device }),
+            other => Err(Error::FamilyCodeMismatch(FAMILY_CODE, other)),
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn is_ds2505(&self) -> bool {
+        self.device.address[0] == FAMILY_CODE_DS2505
+    }
+
+    /// Size of the memory in bytes
+    pub fn memory_size(&self) -> u16 {
+        if self.is_ds2505() {
+            MEMORY_SIZE_DS2505
+        } else {
+            MEMORY_SIZE
+        }
+    }
+
+    /// Reads the data starting at `address` in one go, checking the CRC of the command
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            self.start(wire, delay, Command::ReadMemory, address)?;
+            wire.read_bytes(delay, data)
+        })
+    }
+
+    /// Reads the status memory starting at `address`, which holds the write protection and
+    /// redirection of the pages, checking the CRC of the command
+    pub fn read_status<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            self.start(wire, delay, Command::ReadStatus, address)?;
+            wire.read_bytes(delay, data)
+        })
+    }
+
+    /// Reads a whole page and checks its CRC
+    pub fn read_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        page: u8,
+    ) -> Result<[u8; PAGE_SIZE], Error<W::Error>> {
+        let address = u16::from(page) * PAGE_SIZE as u16;
+        debug_assert!(address < self.memory_size());
+        wire.transaction(|wire| {
+            let mut data = [0u8; PAGE_SIZE];
+            if self.is_ds2505() {
+                wire.reset(delay)?;
+                wire.select(delay, &self.device)?;
+                let [low, high] = address.to_le_bytes();
+                let command = [Command::ExtendedReadMemory as u8, low, high];
+                wire.write_bytes(delay, &command)?;
+                // the redirection byte, 0xFF unless the page was redirected
+                let mut redirection = [0u8; 1];
+                wire.read_bytes(delay, &mut redirection)?;
+                let computed =
+                    compute_partial_crc16(compute_partial_crc16(0, &command), &redirection);
+                let mut crc = [0u8; 2];
+                wire.read_bytes(delay, &mut crc)?;
+                ensure_correct_crc16(computed, crc)?;
+                wire.read_bytes(delay, &mut data)?;
+                wire.read_bytes(delay, &mut crc)?;
+                ensure_correct_crc16(compute_partial_crc16(0, &data), crc)?;
+            } else {
+                self.start(wire, delay, Command::ReadDataCrc8, address)?;
+                wire.read_bytes(delay, &mut data)?;
+                let crc = wire.read_byte(delay)?;
+                check_crc8(&data, crc)?;
+            }
+            Ok(data)
+        })
+    }
+
+    /// Reads the EUI-48 MAC address of a DS2502-E48, `None` if page 0 does not hold one
+    pub fn read_eui48<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<[u8; 6]>, Error<W::Error>> {
+        Ok(eui48(&self.read_page(wire, delay, 0)?))
+    }
+
+    /// Resets, selects the device and sends the command, checking the CRC the device
+    /// answers with
+    fn start<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        command: Command,
+        address: u16,
+    ) -> Result<(), Error<W::Error>> {
+        wire.reset(delay)?;
+        wire.select(delay, &self.device)?;
+        let [low, high] = address.to_le_bytes();
+        let command = [command as u8, low, high];
+        wire.write_bytes(delay, &command)?;
+        if self.is_ds2505() {
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            ensure_correct_crc16(compute_partial_crc16(0, &command), crc)
+        } else {
+            let crc = wire.read_byte(delay)?;
+            check_crc8(&command, crc)
+        }
+    }
+}
+
+/// Extracts the EUI-48 MAC address from page 0 of a DS2502-E48. It is stored as a 1-Wire
+/// file packet: a length byte, the address with the least significant byte first and the
+/// inverted CRC16 of both.
+pub fn eui48(page: &[u8; PAGE_SIZE]) -> Option<[u8; 6]> {
+    let [length, a, b, c, d, e, f, crc_low, crc_high, ..] = *page;
+    let packet = [length, a, b, c, d, e, f, crc_low, crc_high];
+    if usize::from(length) != EUI48_SIZE || compute_partial_crc16(0, &packet) != CRC16_RESIDUE {
+        return None;
+    }
+    Some([f, e, d, c, b, a])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eui48() {
+        let mut page = [0xFF; PAGE_SIZE];
+        let packet = [6, 0x56, 0x34, 0x12, 0x5E, 0x60, 0x00];
+        page[..7].copy_from_slice(&packet);
+        let crc = !compute_partial_crc16(0, &packet);
+        page[7..9].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(Some([0x00, 0x60, 0x5E, 0x12, 0x34, 0x56]), eui48(&page));
+
+        page[3] = 0;
+        assert_eq!(None, eui48(&page));
+        assert_eq!(None, eui48(&[0xFF; PAGE_SIZE]));
+    }
+
+    #[test]
+    fn test_new() {
+        let device = |family| Device {
+            address: [family, 1, 2, 3, 4, 5, 6, 7],
+        };
+        assert!(DS2502::new(device(FAMILY_CODE)).is_ok_and(|eprom| !eprom.is_ds2505()));
+        assert!(DS2502::new(device(FAMILY_CODE_DS2505))
+            .is_ok_and(|eprom| eprom.memory_size() == MEMORY_SIZE_DS2505));
+        assert!(DS2502::new(device(crate::family::DS2431)).is_err());
+    }
+}
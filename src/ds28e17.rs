@@ -0,0 +1,482 @@
+//! The DS28E17 is a 1-Wire to I2C master bridge. Each I2C transfer is a single CRC16
+//! protected 1-Wire packet, after which the device is polled until the transfer on the
+//! I2C bus finished and then answers with its status (and the data read). [`I2cBridge`]
+//! implements the blocking I2C traits of embedded-hal on top of it (and the `I2c` trait of
+//! embedded-hal 1.0 with the `i2c-hal1` feature), so existing I2C drivers work over the
+//! 1-Wire cable.
+
+use core::convert::Infallible;
+use core::convert::TryFrom;
+
+use hal::blocking::delay::DelayUs;
+use hal::blocking::i2c::{Read, Write, WriteRead};
+
+use crate::compute_partial_crc16;
+use crate::family::Capabilities;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+
+pub const FAMILY_CODE: u8 = crate::family::DS28E17;
+
+/// The most bytes a single packet reads or writes
+pub const MAX_TRANSFER: usize = 255;
+
+/// How many bits are read at most while waiting for the end of a transfer, at about 70 µs
+/// per bit enough for the longest transfer at 100 kHz
+const MAX_BUSY_POLLS: u32 = 10_000;
+
+/// The status bits of the device
+const STATUS_CRC_ERROR: u8 = 0x01;
+const STATUS_ADDRESS_NACK: u8 = 0x02;
+const STATUS_INVALID_START: u8 = 0x08;
+
+#[repr(u8)]
+pub enum Command {
+    WriteDataWithStop = 0x4B,
+    WriteDataNoStop = 0x5A,
+    WriteDataOnly = 0x69,
+    WriteDataOnlyWithStop = 0x78,
+    ReadDataWithStop = 0x87,
+    WriteReadDataWithStop = 0x2D,
+    WriteConfiguration = 0xD2,
+    ReadConfiguration = 0xE1,
+    EnableSleep = 0x1E,
+    ReadDeviceRevision = 0xC3,
+}
+
+/// The speed of the I2C bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Speed {
+    Khz100 = 0x00,
+    Khz400 = 0x01,
+    Khz900 = 0x02,
+}
+
+pub struct DS28E17 {
+    device: Device,
+}
+
+impl DS28E17 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: true,
+            alarm: false,
+            parasite: false,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS28E17, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS28E17 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// The I2C bus of the device, for drivers expecting the I2C traits of embedded-hal
+    pub fn i2c<'a, W: OneWireMaster, D: DelayUs<u16>>(
+        &'a self,
+        wire: &'a mut W,
+        delay: &'a mut D,
+    ) -> I2cBridge<'a, W, D> {
+        I2cBridge {
+            bridge: self,
+            wire,
+            delay,
+        }
+    }
+
+    pub fn set_speed<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        speed: Speed,
+    ) -> Result<(), Error<W::Error>> {
+        wire.reset_select_write_only(
+            delay,
+            &self.device,
+            &[Command::WriteConfiguration as u8, speed as u8],
+        )
+    }
+
+    /// Puts the device into its low power mode until the next 1-Wire activity
+    pub fn enable_sleep<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        wire.reset_select_write_only(delay, &self.device, &[Command::EnableSleep as u8])
+    }
+
+    /// Writes the bytes to the I2C device with the 7 bit `address`, in several packets if
+    /// there are more than [`MAX_TRANSFER`]. Fails with [`Error::CommandFailed`] with the
+    /// status of the device if the I2C device did not acknowledge. Without any bytes this
+    /// only probes whether a device acknowledges the address, by reading a single byte as
+    /// the DS28E17 can not write empty packets.
+    pub fn write<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u8,
+        bytes: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        self.write_parts(wire, delay, address, core::iter::once(bytes))
+    }
+
+    /// Writes the parts as a single write on the I2C bus, without a repeated start between them
+    fn write_parts<'b, W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u8,
+        parts: impl Iterator<Item = &'b [u8]>,
+    ) -> Result<(), Error<W::Error>> {
+        let address = write_address(address)?;
+        let mut chunks = parts.flat_map(|part| part.chunks(MAX_TRANSFER)).peekable();
+        if chunks.peek().is_none() {
+            // the packets carry 1 to 255 bytes, so the address is probed by reading a byte
+            let head = [Command::ReadDataWithStop as u8, address | 1, 1];
+            return self.transfer(wire, delay, &head, &[], &[], false, &mut [0u8]);
+        }
+        let mut first = true;
+        while let Some(chunk) = chunks.next() {
+            let last = chunks.peek().is_none();
+            let command = match (first, last) {
+                (true, true) => Command::WriteDataWithStop,
+                (true, false) => Command::WriteDataNoStop,
+                (false, false) => Command::WriteDataOnly,
+                (false, true) => Command::WriteDataOnlyWithStop,
+            };
+            let len = chunk.len() as u8;
+            if first {
+                let head = [command as u8, address, len];
+                self.transfer(wire, delay, &head, chunk, &[], true, &mut [])?;
+            } else {
+                let head = [command as u8, len];
+                self.transfer(wire, delay, &head, chunk, &[], true, &mut [])?;
+            }
+            first = false;
+        }
+        Ok(())
+    }
+
+    /// Reads 1 to [`MAX_TRANSFER`] bytes from the I2C device with the 7 bit `address`
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u8,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        let address = write_address(address)? | 1;
+        let len = read_len(buffer)?;
+        let head = [Command::ReadDataWithStop as u8, address, len];
+        self.transfer(wire, delay, &head, &[], &[], false, buffer)
+    }
+
+    /// Writes 1 to [`MAX_TRANSFER`] bytes and reads 1 to [`MAX_TRANSFER`] bytes with a
+    /// repeated start in between
+    pub fn write_read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        let address = write_address(address)?;
+        let write_len = match u8::try_from(bytes.len()) {
+            Ok(0) | Err(_) => return Err(Error::CommandFailed(0)),
+            Ok(len) => len,
+        };
+        let len = read_len(buffer)?;
+        let head = [Command::WriteReadDataWithStop as u8, address, write_len];
+        self.transfer(wire, delay, &head, bytes, &[len], true, buffer)
+    }
+
+    /// Sends the packet with its CRC16, waits for the transfer on the I2C bus and checks
+    /// the status, reading the data afterwards
+    #[allow(clippy::too_many_arguments)]
+    fn transfer<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        head: &[u8],
+        data: &[u8],
+        tail: &[u8],
+        write_status: bool,
+        read: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            wire.write_bytes(delay, head)?;
+            wire.write_bytes(delay, data)?;
+            wire.write_bytes(delay, tail)?;
+            let crc = compute_partial_crc16(compute_partial_crc16(0, head), data);
+            let crc = !compute_partial_crc16(crc, tail);
+            wire.write_bytes(delay, &crc.to_le_bytes())?;
+
+            let mut polls = 0;
+            while wire.read_bit(delay)? {
+                polls += 1;
+                if polls > MAX_BUSY_POLLS {
+                    return Err(Error::Timeout);
+                }
+            }
+            let status = wire.read_byte(delay)?;
+            if status & (STATUS_CRC_ERROR | STATUS_ADDRESS_NACK | STATUS_INVALID_START) != 0 {
+                return Err(Error::CommandFailed(status));
+            }
+            // the number of the byte that was not acknowledged, 0 if all were
+            if write_status {
+                match wire.read_byte(delay)? {
+                    0 => {}
+                    other => return Err(Error::CommandFailed(other)),
+                }
+            }
+            wire.read_bytes(delay, read)
+        })
+    }
+}
+
+/// The 7 bit `address` shifted for the write direction, which fails with
+/// [`Error::CommandFailed`] with the address if it does not fit into 7 bits
+fn write_address<E: core::fmt::Debug>(address: u8) -> Result<u8, Error<E>> {
+    if address > 0x7F {
+        Err(Error::CommandFailed(address))
+    } else {
+        Ok(address << 1)
+    }
+}
+
+/// The length of a read, which fails with [`Error::CommandFailed`] for empty and too long
+/// buffers
+fn read_len<E: core::fmt::Debug>(buffer: &[u8]) -> Result<u8, Error<E>> {
+    match u8::try_from(buffer.len()) {
+        Ok(0) | Err(_) => Err(Error::CommandFailed(0)),
+        Ok(len) => Ok(len),
+    }
+}
+
+/// The I2C bus behind a DS28E17, see [`DS28E17::i2c`]
+pub struct I2cBridge<'a, W, D> {
+    bridge: &'a DS28E17,
+    wire: &'a mut W,
+    delay: &'a mut D,
+}
+
+impl<W: OneWireMaster, D: DelayUs<u16>> Write for I2cBridge<'_, W, D> {
+    type Error = Error<W::Error>;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.bridge.write(self.wire, self.delay, address, bytes)
+    }
+}
+
+impl<W: OneWireMaster, D: DelayUs<u16>> Read for I2cBridge<'_, W, D> {
+    type Error = Error<W::Error>;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.bridge.read(self.wire, self.delay, address, buffer)
+    }
+}
+
+impl<W: OneWireMaster, D: DelayUs<u16>> WriteRead for I2cBridge<'_, W, D> {
+    type Error = Error<W::Error>;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.bridge
+            .write_read(self.wire, self.delay, address, bytes, buffer)
+    }
+}
+
+/// The status of the DS28E17 and the numbers of unacknowledged bytes share
+/// [`Error::CommandFailed`], so the kinds are not told apart
+#[cfg(feature = "i2c-hal1")]
+impl<E: core::fmt::Debug> embedded_hal_1::i2c::Error for Error<E> {
+    fn kind(&self) -> embedded_hal_1::i2c::ErrorKind {
+        embedded_hal_1::i2c::ErrorKind::Other
+    }
+}
+
+#[cfg(feature = "i2c-hal1")]
+impl<W: OneWireMaster, D: DelayUs<u16>> embedded_hal_1::i2c::ErrorType for I2cBridge<'_, W, D> {
+    type Error = Error<W::Error>;
+}
+
+/// The DS28E17 has packets for a write, a read and a write followed by a read, so a
+/// transaction is any number of writes (sent as one), a single read or a write and a read.
+/// Other transactions fail with [`Error::CommandFailed`] without touching the bus.
+#[cfg(feature = "i2c-hal1")]
+impl<W: OneWireMaster, D: DelayUs<u16>> embedded_hal_1::i2c::I2c for I2cBridge<'_, W, D> {
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.bridge.read(self.wire, self.delay, address, buffer)
+    }
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.bridge.write(self.wire, self.delay, address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.bridge
+            .write_read(self.wire, self.delay, address, bytes, buffer)
+    }
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal_1::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        use embedded_hal_1::i2c::Operation;
+        match operations {
+            [] => Ok(()),
+            [Operation::Read(buffer)] => self.bridge.read(self.wire, self.delay, address, buffer),
+            [Operation::Write(bytes), Operation::Read(buffer)] => self
+                .bridge
+                .write_read(self.wire, self.delay, address, bytes, buffer),
+            operations => {
+                if operations
+                    .iter()
+                    .any(|operation| matches!(operation, Operation::Read(_)))
+                {
+                    return Err(Error::CommandFailed(0));
+                }
+                let parts = operations.iter().filter_map(|operation| match operation {
+                    Operation::Write(bytes) => Some(*bytes),
+                    Operation::Read(_) => None,
+                });
+                self.bridge
+                    .write_parts(self.wire, self.delay, address, parts)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, Reply, Script, ScriptedWire};
+    use crate::CRC16_RESIDUE;
+
+    /// A DS28E17 answering every packet with the prepared bits, following Match ROM
+    struct FakeBridge {
+        answer: u64,
+    }
+
+    impl Script for FakeBridge {
+        fn receive(&mut self, written: &[u8], reply: &mut Reply) {
+            if written.len() == 1 {
+                reply.bytes(&self.answer.to_le_bytes());
+            }
+        }
+    }
+
+    fn bridge() -> (DS28E17, ScriptedWire<FakeBridge>) {
+        let bridge = DS28E17::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap();
+        (bridge, ScriptedWire::new(FakeBridge { answer: 0 }))
+    }
+
+    #[test]
+    fn test_write_read() {
+        let (bridge, mut wire) = bridge();
+        // busy twice, then the status, the write status and the data
+        wire.script.answer = 0b011 | 0x12 << 19 | 0x34 << 27;
+        let mut data = [0u8; 2];
+        bridge
+            .i2c(&mut wire, &mut Delay)
+            .write_read(0x48, &[0x00], &mut data)
+            .unwrap();
+        assert_eq!([0x12, 0x34], data);
+        let packet = &wire.written()[9..];
+        assert_eq!([0x2D, 0x90, 1, 0x00, 2], packet[..5]);
+        assert_eq!(CRC16_RESIDUE, compute_partial_crc16(0, packet));
+
+        // the address is not acknowledged
+        wire.script.answer = u64::from(STATUS_ADDRESS_NACK) << 1;
+        assert!(matches!(
+            bridge.read(&mut wire, &mut Delay, 0x48, &mut data),
+            Err(Error::CommandFailed(STATUS_ADDRESS_NACK))
+        ));
+    }
+
+    #[test]
+    fn test_probe() {
+        let (bridge, mut wire) = bridge();
+        // not busy, the status and the byte read
+        wire.script.answer = 0x5A << 9;
+        bridge.write(&mut wire, &mut Delay, 0x48, &[]).unwrap();
+        let packet = &wire.written()[9..];
+        assert_eq!([0x87, 0x91, 1], packet[..3]);
+
+        wire.script.answer = u64::from(STATUS_ADDRESS_NACK) << 1;
+        assert!(matches!(
+            bridge.write(&mut wire, &mut Delay, 0x48, &[]),
+            Err(Error::CommandFailed(STATUS_ADDRESS_NACK))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let (bridge, mut wire) = bridge();
+        assert!(matches!(
+            bridge.write(&mut wire, &mut Delay, 0x80, &[0x00]),
+            Err(Error::CommandFailed(0x80))
+        ));
+        assert!(matches!(
+            bridge.read(&mut wire, &mut Delay, 0xFF, &mut [0u8]),
+            Err(Error::CommandFailed(0xFF))
+        ));
+        assert!(matches!(
+            bridge.write_read(&mut wire, &mut Delay, 0x90, &[0x00], &mut [0u8]),
+            Err(Error::CommandFailed(0x90))
+        ));
+        assert!(wire.written().is_empty());
+    }
+
+    #[cfg(feature = "i2c-hal1")]
+    #[test]
+    fn test_transaction() {
+        use embedded_hal_1::i2c::{I2c, Operation};
+
+        let (bridge, mut wire) = bridge();
+        // adjacent writes go out as one write
+        bridge
+            .i2c(&mut wire, &mut Delay)
+            .transaction(
+                0x48,
+                &mut [Operation::Write(&[0x01]), Operation::Write(&[0x02])],
+            )
+            .unwrap();
+        let packet = &wire.written()[9..];
+        assert_eq!([0x78, 1, 0x02], packet[..3]);
+
+        // a read can not be followed by anything
+        assert!(matches!(
+            bridge.i2c(&mut wire, &mut Delay).transaction(
+                0x48,
+                &mut [Operation::Read(&mut [0u8]), Operation::Write(&[0x01])]
+            ),
+            Err(Error::CommandFailed(0))
+        ));
+    }
+}
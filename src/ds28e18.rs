@@ -0,0 +1,367 @@
+//! The DS28E18 is a 1-Wire to I2C/SPI bridge. Commands for the I2C/SPI bus are collected
+//! in a [`Sequence`], loaded into the sequencer memory of the device and then executed
+//! at once. Data read from the I2C/SPI bus is stored in the sequencer memory in place
+//! of the read command and can be read back afterwards.
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::Error;
+use crate::OneWire;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+use crate::{Device, OpenDrainOutput};
+use core::convert::Infallible;
+use core::convert::TryFrom;
+
+pub const FAMILY_CODE: u8 = crate::family::DS28E18;
+
+/// Size of the sequencer memory in bytes
+pub const SEQUENCER_SIZE: u16 = 512;
+
+const COMMAND_START: u8 = 0x66;
+const RELEASE: u8 = 0xAA;
+const RESULT_SUCCESS: u8 = 0xAA;
+const COMMAND_DURATION_US: u32 = 1_000;
+const MAX_CHUNK: usize = 128;
+
+#[repr(u8)]
+pub enum Command {
+    WriteSequencer = 0x11,
+    ReadSequencer = 0x22,
+    RunSequencer = 0x33,
+    WriteConfiguration = 0x55,
+    ReadConfiguration = 0x6A,
+    DeviceStatus = 0x7A,
+}
+
+#[repr(u8)]
+pub enum SequencerCommand {
+    I2cStart = 0x02,
+    I2cStop = 0x03,
+    I2cWriteData = 0xE3,
+    I2cReadData = 0xD4,
+    I2cReadDataNackEnd = 0xD3,
+    SpiWriteReadByte = 0xC0,
+    SpiSlaveSelectHigh = 0x01,
+    SpiSlaveSelectLow = 0x80,
+    Delay = 0xDD,
+    SensorVddOn = 0xCC,
+    SensorVddOff = 0xBB,
+}
+
+/// Builds a command sequence in a caller provided buffer. Each method returns the offset
+/// of its data in the sequence - which is where the device stores read data - or `None`
+/// if the buffer is full.
+pub struct Sequence<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Sequence<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        let len = buffer.len().min(usize::from(SEQUENCER_SIZE));
+        Sequence {
+            buffer: buffer.get_mut(..len).unwrap_or(&mut []),
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, header: &[u8], data: &[u8], placeholder: usize) -> Option<u16> {
+        let start = self.len;
+        let data_start = start + header.len();
+        let end = data_start + data.len() + placeholder;
+        let target = self.buffer.get_mut(start..end)?;
+        let (target_header, target_data) = target.split_at_mut(header.len());
+        target_header.copy_from_slice(header);
+        for (i, byte) in target_data.iter_mut().enumerate() {
+            *byte = data.get(i).copied().unwrap_or(0xFF);
+        }
+        self.len = end;
+        Some(data_start as u16)
+    }
+
+    pub fn i2c_start(&mut self) -> Option<u16> {
+        self.push(&[SequencerCommand::I2cStart as u8], &[], 0)
+    }
+
+    pub fn i2c_stop(&mut self) -> Option<u16> {
+        self.push(&[SequencerCommand::I2cStop as u8], &[], 0)
+    }
+
+    /// The address byte (with the read/write bit) has to be written like any other data
+    pub fn i2c_write(&mut self, data: &[u8]) -> Option<u16> {
+        let len = u8::try_from(data.len()).ok()?;
+        self.push(&[SequencerCommand::I2cWriteData as u8, len], data, 0)
+    }
+
+    /// Reads `len` bytes, acknowledging all but - if `nack_end` is set - the last one
+    pub fn i2c_read(&mut self, len: u8, nack_end: bool) -> Option<u16> {
+        let command = if nack_end {
+            SequencerCommand::I2cReadDataNackEnd
+        } else {
+            SequencerCommand::I2cReadData
+        };
+        self.push(&[command as u8, len], &[], usize::from(len))
+    }
+
+    /// Writes `data` and then reads `read_len` bytes, returns the offset of the read bytes
+    pub fn spi_write_read(&mut self, data: &[u8], read_len: u8) -> Option<u16> {
+        let len = u8::try_from(data.len()).ok()?;
+        let header = [SequencerCommand::SpiWriteReadByte as u8, len, read_len];
+        self.push(&header, data, usize::from(read_len))
+            .map(|offset| offset + u16::from(len))
+    }
+
+    pub fn spi_slave_select(&mut self, active: bool) -> Option<u16> {
+        let command = if active {
+            SequencerCommand::SpiSlaveSelectLow
+        } else {
+            SequencerCommand::SpiSlaveSelectHigh
+        };
+        self.push(&[command as u8], &[], 0)
+    }
+
+    /// Waits 2^`exponent` milliseconds
+    pub fn delay(&mut self, exponent: u8) -> Option<u16> {
+        self.push(&[SequencerCommand::Delay as u8, exponent], &[], 0)
+    }
+
+    pub fn sensor_vdd(&mut self, on: bool) -> Option<u16> {
+        let command = if on {
+            SequencerCommand::SensorVddOn
+        } else {
+            SequencerCommand::SensorVddOff
+        };
+        self.push(&[command as u8], &[], 0)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buffer.get(..self.len).unwrap_or(&[])
+    }
+}
+
+pub struct DS28E18 {
+    device: Device,
+}
+
+impl DS28E18 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: true,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS28E18, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS28E18 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Writes the sequence into the sequencer memory at the given address
+    pub fn write_sequencer<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<O::Error>> {
+        for (i, chunk) in data.chunks(MAX_CHUNK).enumerate() {
+            let address = address + (i * MAX_CHUNK) as u16;
+            let mut command = [0u8; 3 + MAX_CHUNK];
+            command[0] = Command::WriteSequencer as u8;
+            command[1] = address as u8;
+            command[2] = (address >> 8) as u8 & 0x01;
+            for (dst, src) in command.iter_mut().skip(3).zip(chunk.iter()) {
+                *dst = *src;
+            }
+            let command = command.get(..3 + chunk.len()).unwrap_or(&[]);
+            self.run_command(wire, delay, command, &mut [], COMMAND_DURATION_US)?;
+        }
+        Ok(())
+    }
+
+    /// Reads from the sequencer memory at the given address, e.g. the data read by
+    /// [`Sequence::i2c_read`]
+    pub fn read_sequencer<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<O::Error>> {
+        for (i, chunk) in data.chunks_mut(MAX_CHUNK).enumerate() {
+            let address = address + (i * MAX_CHUNK) as u16;
+            // a length of 0 reads 128 bytes
+            let len = (chunk.len() % MAX_CHUNK) as u8;
+            let command = [
+                Command::ReadSequencer as u8,
+                address as u8,
+                (len << 1) | ((address >> 8) as u8 & 0x01),
+            ];
+            self.run_command(wire, delay, &command, chunk, COMMAND_DURATION_US)?;
+        }
+        Ok(())
+    }
+
+    /// Executes `len` bytes of the sequencer memory starting at the given address, waiting
+    /// `duration_us` for the execution to finish (which depends on the I2C/SPI bus speed
+    /// and the delays in the sequence)
+    pub fn run_sequencer<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        len: u16,
+        duration_us: u32,
+    ) -> Result<(), Error<O::Error>> {
+        let command = [
+            Command::RunSequencer as u8,
+            address as u8,
+            ((len as u8) << 1) | ((address >> 8) as u8 & 0x01),
+            (len >> 7) as u8,
+        ];
+        self.run_command(wire, delay, &command, &mut [], duration_us)?;
+        Ok(())
+    }
+
+    /// Loads the sequence at the start of the sequencer memory and executes it
+    pub fn execute<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        sequence: &Sequence,
+        duration_us: u32,
+    ) -> Result<(), Error<O::Error>> {
+        let bytes = sequence.as_bytes();
+        self.write_sequencer(wire, delay, 0, bytes)?;
+        self.run_sequencer(wire, delay, 0, bytes.len() as u16, duration_us)
+    }
+
+    /// Configures the protocol (bits 7:6, 0 = I2C, 1 = SPI), SPI mode and bus speed
+    pub fn write_configuration<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        configuration: u8,
+    ) -> Result<(), Error<O::Error>> {
+        let command = [Command::WriteConfiguration as u8, configuration];
+        self.run_command(wire, delay, &command, &mut [], COMMAND_DURATION_US)?;
+        Ok(())
+    }
+
+    pub fn read_configuration<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u8, Error<O::Error>> {
+        let mut configuration = [0u8; 1];
+        let command = [Command::ReadConfiguration as u8];
+        self.run_command(
+            wire,
+            delay,
+            &command,
+            &mut configuration,
+            COMMAND_DURATION_US,
+        )?;
+        Ok(configuration[0])
+    }
+
+    /// Reads the status bytes, e.g. to find out why a sequence failed
+    pub fn device_status<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        status: &mut [u8],
+    ) -> Result<usize, Error<O::Error>> {
+        let command = [Command::DeviceStatus as u8];
+        self.run_command(wire, delay, &command, status, COMMAND_DURATION_US)
+    }
+
+    /// Sends the command with a CRC16 protected Command Start frame, waits for its
+    /// execution and reads the result into `response`. Returns the number of response
+    /// bytes sent by the device, which are only stored as far as `response` is long.
+    fn run_command<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        command: &[u8],
+        response: &mut [u8],
+        duration_us: u32,
+    ) -> Result<usize, Error<O::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+
+            let header = [COMMAND_START, command.len() as u8];
+            wire.write_bytes(delay, &header)?;
+            wire.write_bytes(delay, command)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(compute_partial_crc16(0, &header), command);
+            ensure_correct_crc16(computed, crc)?;
+
+            wire.write_bytes(delay, &[RELEASE])?;
+            wait_us(delay, duration_us);
+
+            let mut head = [0u8; 3]; // dummy byte, length, result byte
+            wire.read_bytes(delay, &mut head)?;
+            let [_, len, result] = head;
+            let mut computed = compute_partial_crc16(0, &[len, result]);
+            let data_len = usize::from(len.saturating_sub(1));
+            for i in 0..data_len {
+                let mut byte = [0u8; 1];
+                wire.read_bytes(delay, &mut byte)?;
+                computed = compute_partial_crc16(computed, &byte);
+                if let Some(target) = response.get_mut(i) {
+                    *target = byte[0];
+                }
+            }
+            wire.read_bytes(delay, &mut crc)?;
+            ensure_correct_crc16(computed, crc)?;
+
+            if result != RESULT_SUCCESS {
+                Err(Error::CommandFailed(result))
+            } else {
+                Ok(data_len)
+            }
+        })
+    }
+}
+
+fn wait_us(delay: &mut impl DelayUs<u16>, duration_us: u32) {
+    let mut remaining = duration_us;
+    while remaining > 0 {
+        let step = remaining.min(u32::from(u16::MAX));
+        delay.delay_us(step as u16);
+        remaining -= step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sequence;
+
+    #[test]
+    fn test_sequence() {
+        let mut buffer = [0u8; 16];
+        let mut sequence = Sequence::new(&mut buffer);
+        assert_eq!(sequence.i2c_start(), Some(1));
+        assert_eq!(sequence.i2c_write(&[0x90, 0x00]), Some(3));
+        assert_eq!(sequence.i2c_read(2, true), Some(7));
+        assert_eq!(sequence.i2c_stop(), Some(10));
+        assert_eq!(sequence.i2c_read(8, true), None);
+        assert_eq!(
+            sequence.as_bytes(),
+            &[0x02, 0xE3, 0x02, 0x90, 0x00, 0xD3, 0x02, 0xFF, 0xFF, 0x03]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! The DS28EA00 is a thermometer with two PIO pins and a sequence detect function: its EN
+//! input and POUT output daisy-chain the devices along the cable, so the order in which
+//! they are wired can be discovered with [`discover_chain`]. In chain mode only the first
+//! device that is enabled and not yet done answers a Conditional Read ROM, and marking it
+//! done enables the next one.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::bitstream::{read_dual_pio, write_dual_pio, PioWrite};
+use crate::check_crc8;
+use crate::family::Capabilities;
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OneWireMaster;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::Unit;
+
+pub use crate::bitstream::PioState;
+
+pub const FAMILY_CODE: u8 = crate::family::DS28EA00;
+
+/// The longest conversion, at 12 bit resolution
+pub const CONVERSION_MS: u16 = 750;
+
+const CONFIRMATION: u8 = 0xAA;
+
+#[repr(u8)]
+pub enum Command {
+    Convert = 0x44,
+    ReadScratchpad = 0xBE,
+    PioAccessRead = 0xF5,
+    PioAccessWrite = 0xA5,
+    Chain = 0x99,
+    /// A ROM function command, only answered in chain mode
+    ConditionalReadRom = 0x0F,
+}
+
+/// The control byte following [`Command::Chain`]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    Off = 0x3C,
+    On = 0x5A,
+    Done = 0x96,
+}
+
+pub struct DS28EA00 {
+    device: Device,
+}
+
+impl DS28EA00 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: true,
+            alarm: true,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS28EA00, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS28EA00 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Starts a conversion, returns the milliseconds to wait until it finished
+    pub fn measure_temperature<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<W::Error>> {
+        wire.reset_select_write_only(delay, &self.device, &[Command::Convert as u8])?;
+        Ok(CONVERSION_MS)
+    }
+
+    /// Reads the raw temperature in sixteenths of a degree celsius from the scratchpad,
+    /// which has the layout of the one of the DS18B20
+    pub fn read_temperature<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<W::Error>> {
+        let mut bytes = [0u8; 9];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadScratchpad as u8],
+            &mut bytes,
+        )?;
+        let [low, high, .., crc] = bytes;
+        check_crc8(&bytes[..8], crc)?;
+        Ok(u16::from_le_bytes([low, high]))
+    }
+
+    pub fn read_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<PioState, Error<W::Error>> {
+        read_dual_pio(wire, delay, &self.device, Command::PioAccessRead as u8)
+    }
+
+    /// Sets the output latches, `true` turning the output transistor off. Returns the state
+    /// of the pins afterwards.
+    pub fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        latch_a: bool,
+        latch_b: bool,
+    ) -> Result<PioState, Error<W::Error>> {
+        write_dual_pio(
+            wire,
+            delay,
+            &self.device,
+            Command::PioAccessWrite as u8,
+            latch_a,
+            latch_b,
+        )
+    }
+}
+
+impl Sensor for DS28EA00 {
+    fn family_code() -> u8 {
+        FAMILY_CODE
+    }
+
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Celsius
+    }
+
+    fn raw_to_milli(&self, raw: u16) -> i32 {
+        i32::from(raw as i16) * 625 / 10
+    }
+
+    fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        self.measure_temperature(wire, delay)
+    }
+
+    #[cfg(not(feature = "no-float"))]
+    fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<f32, Error<O::Error>> {
+        self.read_temperature(wire, delay)
+            .map(|raw| raw as i16 as f32 / 16_f32)
+    }
+
+    fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        self.read_temperature(wire, delay)
+    }
+}
+
+/// Bit 0 of the state is the latch of PIO A, bit 1 the one of PIO B
+impl PioWrite for DS28EA00 {
+    fn write_pio<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        state: u8,
+    ) -> Result<(), Error<W::Error>> {
+        DS28EA00::write_pio(self, wire, delay, state & 0x01 != 0, state & 0x02 != 0).map(|_| ())
+    }
+}
+
+/// Sends the chain control to the given device or all devices
+fn set_chain<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    device: Option<&Device>,
+    control: Chain,
+) -> Result<(), Error<W::Error>> {
+    wire.transaction(|wire| {
+        if !wire.reset(delay)? {
+            return Err(Error::NoPresencePulse);
+        }
+        match device {
+            Some(device) => wire.select(delay, device)?,
+            None => wire.skip(delay)?,
+        }
+        let control = control as u8;
+        wire.write_bytes(delay, &[Command::Chain as u8, control, !control])?;
+        match wire.read_byte(delay)? {
+            CONFIRMATION => Ok(()),
+            other => Err(Error::CommandFailed(other)),
+        }
+    })
+}
+
+/// The address of the next device in the chain, `None` after the last one
+fn conditional_read_rom<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+) -> Result<Option<Device>, Error<W::Error>> {
+    wire.transaction(|wire| {
+        if !wire.reset(delay)? {
+            return Err(Error::NoPresencePulse);
+        }
+        wire.write_bytes(delay, &[Command::ConditionalReadRom as u8])?;
+        let mut address = [0u8; 8];
+        wire.read_bytes(delay, &mut address)?;
+        if address.iter().all(|byte| *byte == 0xFF) {
+            Ok(None)
+        } else {
+            Device::checked(address).map(Some)
+        }
+    })
+}
+
+fn walk_chain<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    devices: &mut [Device],
+) -> Result<usize, Error<W::Error>> {
+    let mut found = 0;
+    for slot in devices.iter_mut() {
+        let device = match conditional_read_rom(wire, delay)? {
+            Some(device) => device,
+            None => break,
+        };
+        set_chain(wire, delay, Some(&device), Chain::Done)?;
+        *slot = device;
+        found += 1;
+    }
+    Ok(found)
+}
+
+/// Stores the DS28EA00s of the chain into `devices` in the order they are wired, starting
+/// with the one whose EN input is tied to ground, and returns how many were found. Stops early
+/// if `devices` is full. Devices of other families on the bus must not react to
+/// [`Command::Chain`] or [`Command::ConditionalReadRom`].
+pub fn discover_chain<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    devices: &mut [Device],
+) -> Result<usize, Error<W::Error>> {
+    set_chain(wire, delay, None, Chain::On)?;
+    let found = walk_chain(wire, delay, devices);
+    set_chain(wire, delay, None, Chain::Off)?;
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, Reply, Script, ScriptedWire};
+
+    /// Devices wired in the order of `addresses`, answering the chain commands
+    struct FakeChain {
+        addresses: [[u8; 8]; 3],
+        chain: bool,
+        done: usize,
+    }
+
+    impl Script for FakeChain {
+        fn receive(&mut self, written: &[u8], reply: &mut Reply) {
+            match *written {
+                [0x0F] => {
+                    let enabled = self.addresses.get(self.done).filter(|_| self.chain);
+                    reply.bytes(enabled.unwrap_or(&[0xFF; 8]));
+                }
+                [0xCC, 0x99, control, _] => {
+                    self.chain = control == Chain::On as u8;
+                    self.done = 0;
+                    reply.bytes(&[CONFIRMATION]);
+                }
+                [0x55, ref selected @ .., 0x99, control, _] if selected.len() == 8 => {
+                    if control == Chain::Done as u8
+                        && self.addresses.get(self.done).map(|a| &a[..]) == Some(selected)
+                    {
+                        self.done += 1;
+                    }
+                    reply.bytes(&[CONFIRMATION]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn address(serial: u8) -> [u8; 8] {
+        let mut address = [FAMILY_CODE, serial, 0, 0, 0, 0, 0, 0];
+        address[7] = crate::compute_partial_crc8(0, &address[..7]);
+        address
+    }
+
+    #[test]
+    fn test_discover_chain() {
+        let mut wire = ScriptedWire::new(FakeChain {
+            addresses: [address(3), address(1), address(2)],
+            chain: false,
+            done: 0,
+        });
+        let mut devices = [
+            Device { address: [0; 8] },
+            Device { address: [0; 8] },
+            Device { address: [0; 8] },
+            Device { address: [0; 8] },
+        ];
+        assert_eq!(
+            3,
+            discover_chain(&mut wire, &mut Delay, &mut devices).unwrap()
+        );
+        assert_eq!(address(3), devices[0].address);
+        assert_eq!(address(1), devices[1].address);
+        assert_eq!(address(2), devices[2].address);
+        assert!(!wire.script.chain);
+        assert_eq!(
+            [0xCC, 0x99, Chain::Off as u8, !(Chain::Off as u8)],
+            wire.written()
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! The DS28EC20 is a 20 Kb EEPROM of 80 pages of 32 bytes, with an address space extending
+//! beyond the 8 bit addresses of the smaller EEPROMs. It is written page by page through a
+//! scratchpad of one page, with the same protocol as the DS2431. The memory is divided into
+//! ten blocks of eight pages, each of which can be write protected or put into EPROM mode
+//! (permanently) through the registers behind the memory.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::memory::{check_range, Memory, OneWireEeprom, PendingCopy};
+use crate::scratchpad::ScratchpadEeprom;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+
+pub const FAMILY_CODE: u8 = crate::family::DS28EC20;
+
+/// Size of the memory in bytes
+pub const MEMORY_SIZE: u16 = 2560;
+/// The memory is written in pages of this many bytes
+pub const PAGE_SIZE: usize = 32;
+/// The number of protection blocks of eight pages each
+pub const BLOCKS: u8 = 10;
+pub const BLOCK_SIZE: u16 = 256;
+/// How long copying the scratchpad into the EEPROM takes at most
+pub const PROGRAMMING_US: u16 = 10_000;
+
+/// The address of the write protection byte of the first block
+const WRITE_PROTECTION: u16 = 0x0A00;
+/// The address of the EPROM mode byte of the first block
+const EPROM_MODE: u16 = 0x0A0A;
+/// Either value activates the protection
+const PROTECTION_ACTIVE: [u8; 2] = [0x55, 0xAA];
+
+#[repr(u8)]
+pub enum Command {
+    WriteScratchpad = 0x0F,
+    ReadScratchpad = 0xAA,
+    CopyScratchpad = 0x55,
+    ReadMemory = 0xF0,
+    ExtendedReadMemory = 0xA5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    None,
+    /// The block can not be written anymore
+    WriteProtected,
+    /// Bits of the block can only be changed from 1 to 0
+    Eprom,
+}
+
+pub struct DS28EC20 {
+    device: Device,
+}
+
+impl DS28EC20 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: true,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS28EC20, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(DS28EC20 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn eeprom(&self) -> ScratchpadEeprom<'_, PAGE_SIZE> {
+        ScratchpadEeprom {
+            device: &self.device,
+            programming_us: PROGRAMMING_US,
+        }
+    }
+
+    /// Reads the data starting at `address` in one go, across page boundaries
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().read(wire, delay, address, data)
+    }
+
+    /// Reads the data with Extended Read Memory, checking the CRC16 the device sends at the
+    /// end of every page. The last page is read up to its end even if `data` ends before.
+    pub fn read_checked<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        let [low, high] = address.to_le_bytes();
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            let command = [Command::ExtendedReadMemory as u8, low, high];
+            wire.write_bytes(delay, &command)?;
+            // the CRC16 of the first page covers the command as well
+            let mut crc = compute_partial_crc16(0, &command);
+            let mut offset = usize::from(address) & (PAGE_SIZE - 1);
+            let mut data = data;
+            while !data.is_empty() {
+                let mut buffer = [0u8; PAGE_SIZE];
+                let page = buffer.get_mut(offset..).unwrap_or(&mut []);
+                wire.read_bytes(delay, page)?;
+                let mut received = [0u8; 2];
+                wire.read_bytes(delay, &mut received)?;
+                ensure_correct_crc16(compute_partial_crc16(crc, page), received)?;
+                let mut count = 0;
+                for (byte, value) in data.iter_mut().zip(page.iter()) {
+                    *byte = *value;
+                    count += 1;
+                }
+                data = core::mem::take(&mut data)
+                    .get_mut(count..)
+                    .unwrap_or(&mut []);
+                crc = 0;
+                offset = 0;
+            }
+            Ok(())
+        })
+    }
+
+    /// Writes a whole page, `address` being the address of its first byte. Fails with
+    /// [`Error::CommandFailed`] if the scratchpad did not receive the page or the copy
+    /// failed, e.g. because the block is write protected.
+    pub fn write_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        page: &[u8; PAGE_SIZE],
+    ) -> Result<(), Error<W::Error>> {
+        self.eeprom().write_block(wire, delay, address, page)
+    }
+
+    /// Like [`DS28EC20::write_page`], but returns once the copy into the EEPROM started instead
+    /// of waiting for its programming time
+    pub fn start_write_page<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        page: &[u8; PAGE_SIZE],
+    ) -> Result<PendingCopy, Error<W::Error>> {
+        self.eeprom().start_write_block(wire, delay, address, page)
+    }
+
+    /// The protection of the block with the index `0` to `9`
+    pub fn protection<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        block: u8,
+    ) -> Result<Protection, Error<W::Error>> {
+        if block >= BLOCKS {
+            return Err(Error::OutOfRange);
+        }
+        let mut registers = [0u8; 2 * BLOCKS as usize];
+        self.read(wire, delay, WRITE_PROTECTION, &mut registers)?;
+        let active = |register: u16| {
+            registers
+                .get(usize::from(register - WRITE_PROTECTION + u16::from(block)))
+                .is_some_and(|byte| PROTECTION_ACTIVE.contains(byte))
+        };
+        Ok(if active(WRITE_PROTECTION) {
+            Protection::WriteProtected
+        } else if active(EPROM_MODE) {
+            Protection::Eprom
+        } else {
+            Protection::None
+        })
+    }
+
+    /// Protects the block with the index `0` to `9`, which can not be undone
+    pub fn protect<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        block: u8,
+        protection: Protection,
+    ) -> Result<(), Error<W::Error>> {
+        if block >= BLOCKS {
+            return Err(Error::OutOfRange);
+        }
+        let [active, _] = PROTECTION_ACTIVE;
+        let register = match protection {
+            Protection::WriteProtected => WRITE_PROTECTION,
+            Protection::Eprom => EPROM_MODE,
+            Protection::None => return Ok(()),
+        };
+        self.eeprom()
+            .write(wire, delay, register + u16::from(block), &[active])
+    }
+}
+
+impl Memory for DS28EC20 {
+    fn size(&self) -> u16 {
+        MEMORY_SIZE
+    }
+
+    fn read_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), MEMORY_SIZE)?;
+        self.read(wire, delay, address, data)
+    }
+
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), MEMORY_SIZE)?;
+        self.eeprom().write(wire, delay, address, data)
+    }
+}
+
+impl OneWireEeprom for DS28EC20 {
+    fn page_size(&self) -> u16 {
+        PAGE_SIZE as u16
+    }
+
+    fn programming_us(&self) -> u16 {
+        PROGRAMMING_US
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, FakeEeprom};
+
+    fn eeprom() -> DS28EC20 {
+        DS28EC20::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read_checked() {
+        let eeprom = eeprom();
+        let mut wire = FakeEeprom::<PAGE_SIZE>::new(None);
+        for (byte, value) in wire.memory.iter_mut().zip((0..=255u8).cycle()) {
+            *byte = value;
+        }
+        let mut data = [0u8; 6];
+        eeprom
+            .read_checked(&mut wire, &mut Delay, 0x91E, &mut data)
+            .unwrap();
+        assert_eq!([0x1E, 0x1F, 0x20, 0x21, 0x22, 0x23], data);
+    }
+
+    #[test]
+    fn test_protection() {
+        let eeprom = eeprom();
+        let mut wire = FakeEeprom::<PAGE_SIZE>::new(Some((
+            usize::from(WRITE_PROTECTION),
+            usize::from(BLOCK_SIZE),
+        )));
+        eeprom
+            .protect(&mut wire, &mut Delay, 9, Protection::WriteProtected)
+            .unwrap();
+        assert_eq!(
+            Protection::WriteProtected,
+            eeprom.protection(&mut wire, &mut Delay, 9).unwrap()
+        );
+        assert_eq!(
+            Protection::None,
+            eeprom.protection(&mut wire, &mut Delay, 8).unwrap()
+        );
+        assert!(matches!(
+            eeprom.write_page(&mut wire, &mut Delay, 0x900, &[0; PAGE_SIZE]),
+            Err(Error::CommandFailed(0xFF))
+        ));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let eeprom = eeprom();
+        let mut wire = FakeEeprom::<PAGE_SIZE>::new(None);
+        assert!(matches!(
+            eeprom.protect(&mut wire, &mut Delay, BLOCKS, Protection::Eprom),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            eeprom.protection(&mut wire, &mut Delay, BLOCKS),
+            Err(Error::OutOfRange)
+        ));
+        let mut data = [0u8; 8];
+        assert!(matches!(
+            eeprom.read_memory(&mut wire, &mut Delay, MEMORY_SIZE - 4, &mut data),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            eeprom.write_memory(&mut wire, &mut Delay, u16::MAX - 4, &data),
+            Err(Error::OutOfRange)
+        ));
+    }
+}
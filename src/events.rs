@@ -0,0 +1,165 @@
+//! Bus events pushed into a lock-free single-producer/single-consumer queue, so a task
+//! polling the bus and a task reacting to the events (e.g. next to an ISR) are decoupled
+//!
+//! ```ignore
+//! static mut QUEUE: EventQueue<8> = EventQueue::new();
+//! let (mut producer, mut consumer) = unsafe { QUEUE.split() };
+//! ```
+
+use hal::blocking::delay::DelayUs;
+use heapless::spsc::{Consumer, Producer, Queue};
+
+use crate::Device;
+use crate::DeviceSearch;
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The device answered the alarm search
+    Alarm(Device),
+    /// The device appeared on the bus
+    Arrived(Device),
+    /// The device is no longer found on the bus
+    Departed(Device),
+    /// The wire did not go high, it is shorted or the pull-up resistor is missing
+    BusFault,
+}
+
+/// Holds up to `N - 1` events
+pub type EventQueue<const N: usize> = Queue<Event, N>;
+pub type EventProducer<'a, const N: usize> = Producer<'a, Event, N>;
+pub type EventConsumer<'a, const N: usize> = Consumer<'a, Event, N>;
+
+/// Pushes an [`Event::Alarm`] for every device answering the alarm search, returns how
+/// many events were dropped because the queue was full
+pub fn push_alarms<O: OpenDrainOutput, const N: usize>(
+    wire: &mut OneWire<O>,
+    delay: &mut impl DelayUs<u16>,
+    producer: &mut EventProducer<'_, N>,
+) -> Result<usize, Error<O::Error>> {
+    let mut search = DeviceSearch::new();
+    let mut dropped = 0;
+    while let Some(device) =
+        search_or_fault(wire.search_next_alarmed(&mut search, delay), producer)?
+    {
+        if producer.enqueue(Event::Alarm(device)).is_err() {
+            dropped += 1;
+        }
+    }
+    Ok(dropped)
+}
+
+fn search_or_fault<E: core::fmt::Debug, const N: usize>(
+    result: Result<Option<Device>, Error<E>>,
+    producer: &mut EventProducer<'_, N>,
+) -> Result<Option<Device>, Error<E>> {
+    if let Err(Error::WireNotHigh) = result {
+        let _ = producer.enqueue(Event::BusFault);
+    }
+    result
+}
+
+/// Tracks up to `M` devices and reports devices appearing on or disappearing from the bus
+pub struct HotplugWatcher<const M: usize> {
+    known: [Option<Device>; M],
+}
+
+impl<const M: usize> HotplugWatcher<M> {
+    const NONE: Option<Device> = None;
+
+    pub fn new() -> Self {
+        HotplugWatcher {
+            known: [Self::NONE; M],
+        }
+    }
+
+    /// The devices found by the last poll, further devices are ignored
+    pub fn known(&self) -> impl Iterator<Item = &Device> + '_ {
+        self.known.iter().flatten()
+    }
+
+    /// Searches the bus and pushes an event for every change since the last poll, returns
+    /// how many events were dropped because the queue was full
+    pub fn poll<O: OpenDrainOutput, const N: usize>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        producer: &mut EventProducer<'_, N>,
+    ) -> Result<usize, Error<O::Error>> {
+        let mut present = [Self::NONE; M];
+        let mut slots = present.iter_mut();
+        let mut search = DeviceSearch::new();
+        while let Some(device) = search_or_fault(wire.search_next(&mut search, delay), producer)? {
+            match slots.next() {
+                Some(slot) => *slot = Some(device),
+                None => break,
+            }
+        }
+
+        let mut dropped = 0;
+        let mut push = |event| {
+            if producer.enqueue(event).is_err() {
+                dropped += 1;
+            }
+        };
+        for device in present.iter().flatten() {
+            if !self.known().any(|known| known == device) {
+                push(Event::Arrived(device.clone()));
+            }
+        }
+        for device in self.known() {
+            if !present.iter().flatten().any(|present| present == device) {
+                push(Event::Departed(device.clone()));
+            }
+        }
+        self.known = present;
+        Ok(dropped)
+    }
+}
+
+impl<const M: usize> Default for HotplugWatcher<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Seeded, SimDelay, SimPin, Simulator};
+
+    #[test]
+    fn test_hotplug() {
+        let mut queue = EventQueue::<8>::new();
+        let (mut producer, mut consumer) = queue.split();
+        let sim = Simulator::<2>::new(&mut Seeded {
+            family: 0x28,
+            state: 3,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut watcher = HotplugWatcher::<4>::new();
+        watcher
+            .poll(&mut wire, &mut SimDelay(&sim), &mut producer)
+            .unwrap();
+
+        let mut arrived = 0;
+        while let Some(event) = consumer.dequeue() {
+            match event {
+                Event::Arrived(device) => {
+                    assert!(addresses.contains(&device.address));
+                    arrived += 1;
+                }
+                event => panic!("unexpected {:?}", event),
+            }
+        }
+        assert_eq!(2, arrived);
+
+        watcher
+            .poll(&mut wire, &mut SimDelay(&sim), &mut producer)
+            .unwrap();
+        assert_eq!(None, consumer.dequeue());
+    }
+}
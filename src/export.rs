@@ -0,0 +1,164 @@
+//! JSON and CSV lines of readings and scan results, e.g. for gateways piping sensor data
+//! into existing logging stacks
+
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use crate::family;
+use crate::reading::{Quality, Reading, Unit};
+use crate::scan::ScanReport;
+
+/// Header line matching [`write_csv`]
+pub const CSV_HEADER: &str = "device,raw,milli,unit,quality";
+
+/// Header line matching [`write_scan_csv`]
+pub const SCAN_CSV_HEADER: &str = "device,family,name";
+
+fn unit_name(unit: Unit) -> &'static str {
+    match unit {
+        Unit::Celsius => "celsius",
+        Unit::Volt => "volt",
+        Unit::RelativeHumidity => "relative_humidity",
+        Unit::Count => "count",
+    }
+}
+
+fn quality_name(quality: Quality) -> &'static str {
+    match quality {
+        Quality::Fresh => "fresh",
+        Quality::CrcRetried => "crc_retried",
+        Quality::Quarantined => "quarantined",
+    }
+}
+
+/// Writes the reading as single line JSON object without a trailing newline
+pub fn write_json(out: &mut impl Write, reading: &Reading) -> fmt::Result {
+    write!(
+        out,
+        "{{\"device\":\"{}\",\"raw\":{},\"milli\":{},\"unit\":\"{}\",\"quality\":\"{}\"}}",
+        reading.device,
+        reading.raw,
+        reading.milli,
+        unit_name(reading.unit),
+        quality_name(reading.quality),
+    )
+}
+
+/// Writes the reading as CSV line without a trailing newline, see [`CSV_HEADER`]
+pub fn write_csv(out: &mut impl Write, reading: &Reading) -> fmt::Result {
+    write!(
+        out,
+        "{},{},{},{},{}",
+        reading.device,
+        reading.raw,
+        reading.milli,
+        unit_name(reading.unit),
+        quality_name(reading.quality),
+    )
+}
+
+pub fn to_json(reading: &Reading) -> String {
+    let mut line = String::new();
+    // writing into a String does not fail
+    let _ = write_json(&mut line, reading);
+    line
+}
+
+pub fn to_csv(reading: &Reading) -> String {
+    let mut line = String::new();
+    let _ = write_csv(&mut line, reading);
+    line
+}
+
+/// Writes the scan report as single line JSON object without a trailing newline
+pub fn write_scan_json<const N: usize>(
+    out: &mut impl Write,
+    report: &ScanReport<N>,
+) -> fmt::Result {
+    write!(
+        out,
+        "{{\"found\":{},\"crc_failures\":{},\"search_steps\":{},\"duration_estimate_us\":{},\
+         \"healthy\":{},\"wire_not_high\":{},\"no_presence\":{},\"aborted\":{},\
+         \"overflowed\":{},\"devices\":[",
+        report.found(),
+        report.crc_failures(),
+        report.search_steps(),
+        report.duration_estimate_us(),
+        report.is_healthy(),
+        report.wire_not_high(),
+        report.no_presence(),
+        report.aborted(),
+        report.overflowed(),
+    )?;
+    for (index, device) in report.devices().enumerate() {
+        if index > 0 {
+            out.write_char(',')?;
+        }
+        write!(out, "\"{}\"", device)?;
+    }
+    out.write_str("]}")
+}
+
+/// Writes one CSV line per device of the scan report, each with a trailing newline, see
+/// [`SCAN_CSV_HEADER`]
+pub fn write_scan_csv<const N: usize>(out: &mut impl Write, report: &ScanReport<N>) -> fmt::Result {
+    for device in report.devices() {
+        writeln!(
+            out,
+            "{},{:02x},{}",
+            device,
+            device.family_code(),
+            family::name(device.family_code()).unwrap_or("")
+        )?;
+    }
+    Ok(())
+}
+
+pub fn scan_to_json<const N: usize>(report: &ScanReport<N>) -> String {
+    let mut line = String::new();
+    let _ = write_scan_json(&mut line, report);
+    line
+}
+
+/// Writes each reading as JSON line, e.g. to stdout
+#[cfg(feature = "std")]
+pub fn write_json_lines(
+    out: &mut impl std::io::Write,
+    readings: &[Reading],
+) -> std::io::Result<()> {
+    for reading in readings {
+        writeln!(out, "{}", to_json(reading))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Device;
+
+    fn reading() -> Reading {
+        Reading {
+            device: Device {
+                address: [0x28, 0xff, 0x64, 0x1e, 0x0f, 0x21, 0xc4, 0x5a],
+            },
+            raw: 0x0158,
+            milli: 21500,
+            unit: Unit::Celsius,
+            quality: Quality::CrcRetried,
+        }
+    }
+
+    #[test]
+    fn test_reading() {
+        assert_eq!(
+            to_json(&reading()),
+            "{\"device\":\"28:ff:64:1e:0f:21:c4:5a\",\"raw\":344,\"milli\":21500,\
+             \"unit\":\"celsius\",\"quality\":\"crc_retried\"}"
+        );
+        assert_eq!(
+            to_csv(&reading()),
+            "28:ff:64:1e:0f:21:c4:5a,344,21500,celsius,crc_retried"
+        );
+    }
+}
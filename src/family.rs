@@ -0,0 +1,244 @@
+//! Family codes (the first byte of the address) of known devices, including devices
+//! without a driver in this crate
+
+use crate::Speed;
+
+/// DS1990A iButton, DS2401 silicon serial number
+pub const DS1990A: u8 = 0x01;
+/// DS2401 silicon serial number
+pub const DS2401: u8 = 0x01;
+/// DS2404 EconoRAM time chip
+pub const DS2404: u8 = 0x04;
+/// DS2405 addressable switch
+pub const DS2405: u8 = 0x05;
+/// DS2502 1 Kb add-only memory
+pub const DS2502: u8 = 0x09;
+/// DS2505 16 Kb add-only memory
+pub const DS2505: u8 = 0x0B;
+/// DS18S20 high-precision digital thermometer (and DS1820)
+pub const DS18S20: u8 = 0x10;
+/// DS2406 / DS2407 dual addressable switch
+pub const DS2406: u8 = 0x12;
+/// DS2430A 256 bit EEPROM
+pub const DS2430A: u8 = 0x14;
+/// DS28E17 1-Wire-to-I2C master bridge
+pub const DS28E17: u8 = 0x19;
+/// DS2423 4 Kb RAM with counter
+pub const DS2423: u8 = 0x1D;
+/// DS2409 MicroLAN coupler
+pub const DS2409: u8 = 0x1F;
+/// DS2450 quad A/D converter
+pub const DS2450: u8 = 0x20;
+/// DS1921 Thermochron iButton
+pub const DS1921: u8 = 0x21;
+/// DS1822 econo digital thermometer
+pub const DS1822: u8 = 0x22;
+/// DS2433 4 Kb EEPROM
+pub const DS2433: u8 = 0x23;
+/// DS2415 real-time clock
+pub const DS2415: u8 = 0x24;
+/// DS2438 smart battery monitor
+pub const DS2438: u8 = 0x26;
+/// DS2417 real-time clock with interrupt
+pub const DS2417: u8 = 0x27;
+/// DS18B20 programmable resolution digital thermometer
+pub const DS18B20: u8 = 0x28;
+/// DS2408 8-channel addressable switch
+pub const DS2408: u8 = 0x29;
+/// DS2890 single channel digital potentiometer
+pub const DS2890: u8 = 0x2C;
+/// DS2431 1 Kb EEPROM
+pub const DS2431: u8 = 0x2D;
+/// DS1961S / DS2432 1 Kb protected EEPROM with SHA-1 engine
+pub const DS2432: u8 = 0x33;
+/// DS1977 password-protected 32 KB EEPROM
+pub const DS1977: u8 = 0x37;
+/// DS2413 dual channel addressable switch
+pub const DS2413: u8 = 0x3A;
+/// DS1825 programmable resolution digital thermometer with ID
+pub const DS1825: u8 = 0x3B;
+/// MAX31850 / MAX31851 thermocouple-to-digital converter
+pub const MAX31850: u8 = 0x3B;
+/// MAX31826 digital temperature sensor with 1 Kb EEPROM
+pub const MAX31826: u8 = 0x3B;
+/// DS28EA00 digital thermometer with sequence detect and PIO
+pub const DS28EA00: u8 = 0x42;
+/// DS28EC20 20 Kb EEPROM
+pub const DS28EC20: u8 = 0x43;
+/// DS28E18 1-Wire-to-I2C/SPI bridge with command sequencer
+pub const DS28E18: u8 = 0x56;
+
+/// Returns the name of the device(s) with the given family code, if known
+pub fn name(family_code: u8) -> Option<&'static str> {
+    Some(match family_code {
+        DS1990A => "DS1990A/DS2401",
+        DS2404 => "DS2404",
+        DS2405 => "DS2405",
+        DS2502 => "DS2502",
+        DS2505 => "DS2505",
+        DS18S20 => "DS18S20",
+        DS2406 => "DS2406/DS2407",
+        DS2430A => "DS2430A",
+        DS28E17 => "DS28E17",
+        DS2423 => "DS2423",
+        DS2409 => "DS2409",
+        DS2450 => "DS2450",
+        DS1921 => "DS1921",
+        DS1822 => "DS1822",
+        DS2433 => "DS2433",
+        DS2415 => "DS2415",
+        DS2438 => "DS2438",
+        DS2417 => "DS2417",
+        DS18B20 => "DS18B20",
+        DS2408 => "DS2408",
+        DS2890 => "DS2890",
+        DS2431 => "DS2431",
+        DS2432 => "DS1961S/DS2432",
+        DS1977 => "DS1977",
+        DS2413 => "DS2413",
+        DS1825 => "DS1825/MAX31826/MAX31850",
+        DS28EA00 => "DS28EA00",
+        DS28EC20 => "DS28EC20",
+        DS28E18 => "DS28E18",
+        _ => return None,
+    })
+}
+
+/// What a device supports, declared by its driver so generic tooling does not need to know
+/// about families
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Has non-volatile memory, e.g. for alarm thresholds or user data
+    pub eeprom: bool,
+    /// Supports the overdrive speed
+    pub overdrive: bool,
+    /// Answers the alarm search ([`crate::Command::SearchNextAlarmed`]) under some condition
+    pub alarm: bool,
+    /// Can be powered by the data line alone
+    pub parasite: bool,
+}
+
+impl Capabilities {
+    pub const fn supports(&self, speed: Speed) -> bool {
+        match speed {
+            Speed::Standard => true,
+            Speed::Overdrive => self.overdrive,
+        }
+    }
+
+    /// Returns the speed for [`crate::OneWire::transaction_with_speed`] if the device
+    /// supports it and panics otherwise, which turns requesting overdrive from a device
+    /// without it into a build error when used in a constant
+    ///
+    /// ```
+    /// use onewire::family::{Capabilities, SupportedSpeed};
+    /// use onewire::Speed;
+    /// const EEPROM: Capabilities = Capabilities {
+    ///     eeprom: true,
+    ///     overdrive: true,
+    ///     alarm: false,
+    ///     parasite: true,
+    /// };
+    /// const SPEED: SupportedSpeed = EEPROM.validated_speed(Speed::Overdrive);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use onewire::family::{Capabilities, SupportedSpeed};
+    /// use onewire::Speed;
+    /// const SWITCH: Capabilities = Capabilities {
+    ///     eeprom: false,
+    ///     overdrive: false,
+    ///     alarm: true,
+    ///     parasite: true,
+    /// };
+    /// const SPEED: SupportedSpeed = SWITCH.validated_speed(Speed::Overdrive);
+    /// ```
+    pub const fn validated_speed(&self, speed: Speed) -> SupportedSpeed {
+        assert!(
+            self.supports(speed),
+            "the device does not support the speed"
+        );
+        SupportedSpeed(speed)
+    }
+}
+
+/// A speed the addressed devices support, see [`Capabilities::validated_speed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupportedSpeed(Speed);
+
+impl SupportedSpeed {
+    /// Standard speed is supported by every device
+    pub const STANDARD: SupportedSpeed = SupportedSpeed(Speed::Standard);
+
+    pub const fn speed(&self) -> Speed {
+        self.0
+    }
+}
+
+/// The capabilities of the device with the given family code, if its driver is enabled
+pub fn capabilities(family_code: u8) -> Option<Capabilities> {
+    match family_code {
+        #[cfg(feature = "ds18b20")]
+        DS18B20 => Some(crate::ds18b20::DS18B20::capabilities()),
+        #[cfg(feature = "ds1825")]
+        DS1825 => Some(crate::ds1825::DS1825::capabilities()),
+        #[cfg(feature = "ds2405")]
+        DS2405 => Some(crate::ds2405::DS2405::capabilities()),
+        #[cfg(feature = "ds2406")]
+        DS2406 => Some(crate::ds2406::DS2406::capabilities()),
+        #[cfg(feature = "ds2408")]
+        DS2408 => Some(crate::ds2408::DS2408::capabilities()),
+        #[cfg(feature = "ds2409")]
+        DS2409 => Some(crate::ds2409::DS2409::capabilities()),
+        #[cfg(feature = "ds2413")]
+        DS2413 => Some(crate::ds2413::DS2413::capabilities()),
+        #[cfg(feature = "ds2431")]
+        DS2431 => Some(crate::ds2431::DS2431::capabilities()),
+        #[cfg(feature = "ds2432")]
+        DS2432 => Some(crate::ds2432::DS2432::capabilities()),
+        #[cfg(feature = "ds2433")]
+        DS2433 => Some(crate::ds2433::DS2433::capabilities()),
+        #[cfg(feature = "ds2450")]
+        DS2450 => Some(crate::ds2450::DS2450::capabilities()),
+        #[cfg(feature = "ds2502")]
+        DS2502 | DS2505 => Some(crate::ds2502::DS2502::capabilities()),
+        #[cfg(feature = "ds28ea00")]
+        DS28EA00 => Some(crate::ds28ea00::DS28EA00::capabilities()),
+        #[cfg(feature = "ds28ec20")]
+        DS28EC20 => Some(crate::ds28ec20::DS28EC20::capabilities()),
+        #[cfg(feature = "ds28e17")]
+        DS28E17 => Some(crate::ds28e17::DS28E17::capabilities()),
+        #[cfg(feature = "ds28e18")]
+        DS28E18 => Some(crate::ds28e18::DS28E18::capabilities()),
+        #[cfg(feature = "rtc")]
+        DS2404 => Some(crate::rtc::DS2404::capabilities()),
+        #[cfg(feature = "rtc")]
+        DS2417 => Some(crate::rtc::DS2417::capabilities()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(feature = "ds18b20")]
+    fn test_capabilities() {
+        use super::*;
+
+        assert!(capabilities(DS18B20).is_some_and(|capabilities| capabilities.alarm));
+        assert_eq!(None, capabilities(DS1977));
+    }
+
+    #[test]
+    fn test_supports() {
+        use super::Capabilities;
+        use crate::Speed;
+
+        let capabilities = Capabilities {
+            overdrive: false,
+            ..Capabilities::default()
+        };
+        assert!(capabilities.supports(Speed::Standard));
+        assert!(!capabilities.supports(Speed::Overdrive));
+    }
+}
@@ -0,0 +1,173 @@
+//! Filters for noisy readings that work without floating point math. The raw value of
+//! a DS18B20 should be converted with `raw as i16` (or to millidegrees) first, so that
+//! negative temperatures are ordered correctly.
+
+/// Median of the last `N` values
+#[derive(Debug, Clone)]
+pub struct Median<T, const N: usize> {
+    values: [T; N],
+    len: usize,
+    next: usize,
+}
+
+impl<T: Copy + Ord + Default, const N: usize> Median<T, N> {
+    pub fn new() -> Self {
+        Median {
+            values: [T::default(); N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Adds the value - replacing the oldest one if `N` values are already present -
+    /// and returns the new median
+    pub fn push(&mut self, value: T) -> Option<T> {
+        let slot = self.values.get_mut(self.next)?;
+        *slot = value;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+        self.median()
+    }
+
+    /// For an even number of values, the lower one of the two middle values is returned
+    pub fn median(&self) -> Option<T> {
+        let mut sorted = self.values;
+        let sorted = sorted.get_mut(..self.len)?;
+        sorted.sort_unstable();
+        sorted.get(self.len.checked_sub(1)? / 2).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+impl<T: Copy + Ord + Default, const N: usize> Default for Median<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Exponential moving average with a smoothing factor of `1 / 2^shift`
+#[derive(Debug, Clone)]
+pub struct MovingAverage {
+    value: Option<i32>,
+    shift: u8,
+}
+
+impl MovingAverage {
+    pub fn new(shift: u8) -> Self {
+        MovingAverage {
+            value: None,
+            shift: shift.min(31),
+        }
+    }
+
+    /// Adds the value and returns the new average, the first value is taken as is
+    pub fn push(&mut self, value: i32) -> i32 {
+        let average = match self.value {
+            None => value,
+            Some(average) => {
+                let delta = (i64::from(value) - i64::from(average)) >> self.shift;
+                (i64::from(average) + delta) as i32
+            }
+        };
+        self.value = Some(average);
+        average
+    }
+
+    pub fn value(&self) -> Option<i32> {
+        self.value
+    }
+
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+}
+
+/// Two-point switch for thermostat like logic: becomes active once the value rises
+/// above the upper threshold and only becomes inactive again after the value has
+/// fallen below the lower threshold
+#[derive(Debug, Clone)]
+pub struct Hysteresis {
+    enter_above: i32,
+    exit_below: i32,
+    active: bool,
+}
+
+impl Hysteresis {
+    /// Thresholds are usually given in millidegrees, `exit_below` should not be greater
+    /// than `enter_above`
+    pub fn new(enter_above: i32, exit_below: i32) -> Self {
+        Hysteresis {
+            enter_above,
+            exit_below,
+            active: false,
+        }
+    }
+
+    /// Evaluates the value and returns whether the state is active afterwards
+    pub fn update(&mut self, value: i32) -> bool {
+        if value > self.enter_above {
+            self.active = true;
+        } else if value < self.exit_below {
+            self.active = false;
+        }
+        self.active
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn enter_above(&self) -> i32 {
+        self.enter_above
+    }
+
+    pub fn exit_below(&self) -> i32 {
+        self.exit_below
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Hysteresis, Median, MovingAverage};
+
+    #[test]
+    fn test_median() {
+        let mut median = Median::<i16, 3>::new();
+        assert_eq!(median.median(), None);
+        assert_eq!(median.push(0x0191), Some(0x0191));
+        assert_eq!(median.push(0xFE6F_u16 as i16), Some(0xFE6F_u16 as i16));
+        assert_eq!(median.push(0x0550), Some(0x0191));
+        assert_eq!(median.push(0x0550), Some(0x0550));
+        assert_eq!(median.len(), 3);
+    }
+
+    #[test]
+    fn test_moving_average() {
+        let mut average = MovingAverage::new(1);
+        assert_eq!(average.push(1000), 1000);
+        assert_eq!(average.push(2000), 1500);
+        assert_eq!(average.push(-500), 500);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut hysteresis = Hysteresis::new(25_000, 23_000);
+        assert!(!hysteresis.update(24_000));
+        assert!(hysteresis.update(25_001));
+        assert!(hysteresis.update(23_500));
+        assert!(!hysteresis.update(22_999));
+        assert!(!hysteresis.update(24_000));
+    }
+}
@@ -0,0 +1,192 @@
+//! Reading iButtons (e.g. the DS1990A) touched to a reader probe, as in 1-Wire access
+//! control. The probe is polled with a plain reset, which is fast as long as nothing is
+//! touched, and only when a presence pulse answers the serial number is read with Read ROM.
+//! Touching and removing a button bounces, so a change is only reported after it was seen
+//! by a number of consecutive polls.
+
+use hal::blocking::delay::DelayUs;
+
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The button was touched to the probe
+    Arrived(Device),
+    /// The button was removed from the probe
+    Departed(Device),
+}
+
+pub struct IButtonReader {
+    current: Option<Device>,
+    candidate: Option<Device>,
+    /// How many consecutive polls saw the candidate, `0` if there is none
+    seen: u8,
+    debounce: u8,
+}
+
+impl IButtonReader {
+    /// Reports a change after `debounce` consecutive polls saw it, `1` reports it at once
+    pub const fn new(debounce: u8) -> Self {
+        IButtonReader {
+            current: None,
+            candidate: None,
+            seen: 0,
+            debounce,
+        }
+    }
+
+    /// The button on the probe, as reported by the last event
+    pub fn current(&self) -> Option<&Device> {
+        self.current.as_ref()
+    }
+
+    /// Samples the probe once, returning the event if the debounced state changed. Swapping
+    /// one button for another reports the departure first and the arrival on the next poll.
+    pub fn poll<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<Event>, Error<W::Error>> {
+        let presence = match wire.reset(delay) {
+            Ok(presence) => presence,
+            // the contact shorts the wire while it is being made
+            Err(Error::WireNotHigh) => return Ok(self.bounced()),
+            Err(e) => return Err(e),
+        };
+        let sample = if presence {
+            match wire.read_rom(delay) {
+                // a contact held low reads as all zeros, which passes the CRC
+                Ok(device) if device.family_code() == 0x00 => return Ok(self.bounced()),
+                Ok(device) => Some(device),
+                // the contact bounced while reading, the sample does not count
+                Err(Error::NoPresencePulse) | Err(Error::CrcMismatch(..)) => {
+                    return Ok(self.bounced())
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
+        Ok(self.debounced(sample))
+    }
+
+    /// Discards a sample taken while the contact bounced
+    fn bounced(&mut self) -> Option<Event> {
+        self.seen = 0;
+        None
+    }
+
+    fn debounced(&mut self, sample: Option<Device>) -> Option<Event> {
+        if sample == self.current {
+            self.candidate = None;
+            self.seen = 0;
+            return None;
+        }
+        if self.seen > 0 && sample == self.candidate {
+            self.seen = self.seen.saturating_add(1);
+        } else {
+            self.candidate = sample;
+            self.seen = 1;
+        }
+        if self.seen < self.debounce {
+            return None;
+        }
+        match self.current.take() {
+            // the candidate stays, so a swapped button arrives on the next poll
+            Some(departed) => Some(Event::Departed(departed)),
+            None => {
+                self.current = self.candidate.take();
+                self.seen = 0;
+                self.current.clone().map(Event::Arrived)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, LowPin, Seeded, SimDelay, SimPin, Simulator};
+    use crate::OneWire;
+    use core::convert::Infallible;
+
+    /// A contact held low after the presence pulse, reading all zeros
+    struct Shorted;
+
+    impl OneWireMaster for Shorted {
+        type Error = Infallible;
+
+        fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+            Ok(true)
+        }
+
+        fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+            Ok(false)
+        }
+
+        fn write_bit(
+            &mut self,
+            _delay: &mut impl DelayUs<u16>,
+            _high: bool,
+        ) -> Result<(), Error<Infallible>> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_bounce() {
+        let mut reader = IButtonReader::new(1);
+        assert_eq!(None, reader.poll(&mut Shorted, &mut Delay).unwrap());
+        assert_eq!(
+            None,
+            reader
+                .poll(&mut OneWire::new(LowPin, false), &mut Delay)
+                .unwrap()
+        );
+        assert_eq!(None, reader.current());
+    }
+
+    #[test]
+    fn test_arrival_and_departure() {
+        let touched = Simulator::<1>::new(&mut Seeded {
+            family: crate::family::DS1990A,
+            state: 5,
+        });
+        let removed = Simulator::<0>::new(&mut Seeded {
+            family: crate::family::DS1990A,
+            state: 5,
+        });
+        let [address] = touched.borrow().addresses();
+        let mut reader = IButtonReader::new(2);
+        let touch = |reader: &mut IButtonReader| {
+            reader.poll(
+                &mut OneWire::new(SimPin(&touched), false),
+                &mut SimDelay(&touched),
+            )
+        };
+        assert_eq!(None, touch(&mut reader).unwrap());
+        assert_eq!(
+            Some(Event::Arrived(Device { address })),
+            touch(&mut reader).unwrap()
+        );
+        assert_eq!(None, touch(&mut reader).unwrap());
+
+        let remove = |reader: &mut IButtonReader| {
+            reader.poll(
+                &mut OneWire::new(SimPin(&removed), false),
+                &mut SimDelay(&removed),
+            )
+        };
+        assert_eq!(None, remove(&mut reader).unwrap());
+        // bouncing contact
+        assert_eq!(None, touch(&mut reader).unwrap());
+        assert_eq!(None, remove(&mut reader).unwrap());
+        assert_eq!(
+            Some(Event::Departed(Device { address })),
+            remove(&mut reader).unwrap()
+        );
+        assert_eq!(None, reader.current());
+    }
+}
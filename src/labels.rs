@@ -0,0 +1,136 @@
+//! Names for devices, so diagnostics print `boiler_out (28:ff:...)` instead of bare addresses
+//! of which nobody remembers which sensor they belong to
+
+use core::fmt::{Display, Formatter};
+
+use crate::Device;
+
+/// Maps up to `N` devices to their names
+#[derive(Debug, Clone)]
+pub struct Labels<const N: usize> {
+    entries: [Option<(Device, &'static str)>; N],
+}
+
+impl<const N: usize> Labels<N> {
+    const EMPTY: Option<(Device, &'static str)> = None;
+
+    pub const fn new() -> Self {
+        Labels {
+            entries: [Self::EMPTY; N],
+        }
+    }
+
+    /// Names the device, replacing a previous name. Returns `false` if all `N` entries
+    /// are taken by other devices.
+    pub fn register(&mut self, device: Device, name: &'static str) -> bool {
+        let mut free = None;
+        for entry in self.entries.iter_mut() {
+            match entry {
+                Some((known, known_name)) if *known == device => {
+                    *known_name = name;
+                    return true;
+                }
+                Some(_) => {}
+                None => {
+                    free = free.or(Some(entry));
+                }
+            }
+        }
+        match free {
+            Some(entry) => {
+                *entry = Some((device, name));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes the name of the device, returns it if there was one
+    pub fn unregister(&mut self, device: &Device) -> Option<&'static str> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| matches!(entry, Some((known, _)) if known == device))?;
+        entry.take().map(|(_, name)| name)
+    }
+
+    pub fn name(&self, device: &Device) -> Option<&'static str> {
+        self.entries
+            .iter()
+            .flatten()
+            .find(|(known, _)| known == device)
+            .map(|(_, name)| *name)
+    }
+
+    /// The device together with its name for printing
+    pub fn label<'a>(&self, device: &'a Device) -> Labeled<'a> {
+        Labeled {
+            device,
+            name: self.name(device),
+        }
+    }
+}
+
+impl<const N: usize> Default for Labels<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Prints `name (address)` or only the address of devices without a name
+#[derive(Debug, Clone, Copy)]
+pub struct Labeled<'a> {
+    device: &'a Device,
+    name: Option<&'static str>,
+}
+
+impl Display for Labeled<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{} ({})", name, self.device),
+            None => write!(f, "{}", self.device),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Labeled<'_> {
+    fn format(&self, f: defmt::Formatter) {
+        match self.name {
+            Some(name) => defmt::write!(f, "{=str} ({})", name, self.device),
+            None => defmt::write!(f, "{}", self.device),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn test_labels() {
+        let boiler = Device {
+            address: [0x28, 0xFF, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+        };
+        let outside = Device {
+            address: [0x28, 0xFF, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16],
+        };
+        let mut labels = Labels::<1>::new();
+        assert!(labels.register(boiler.clone(), "boiler"));
+        assert!(labels.register(boiler.clone(), "boiler_out"));
+        assert!(!labels.register(outside.clone(), "outside"));
+
+        assert_eq!(
+            "boiler_out (28:ff:01:02:03:04:05:06)",
+            labels.label(&boiler).to_string()
+        );
+        assert_eq!(
+            "28:ff:11:12:13:14:15:16",
+            labels.label(&outside).to_string()
+        );
+        assert_eq!(Some("boiler_out"), labels.unregister(&boiler));
+        assert_eq!(None, labels.name(&boiler));
+    }
+}
@@ -1,15 +1,113 @@
 #![no_std]
 #![crate_name = "onewire"]
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 extern crate byteorder;
 extern crate embedded_hal as hal;
 
+pub mod address_book;
+pub mod alias;
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod bitstream;
+pub mod calibration;
+#[cfg(feature = "ds1825")]
+pub mod ds1825;
+#[cfg(feature = "ds18b20")]
 pub mod ds18b20;
-
+#[cfg(feature = "ds2405")]
+pub mod ds2405;
+#[cfg(feature = "ds2406")]
+pub mod ds2406;
+#[cfg(feature = "ds2408")]
+pub mod ds2408;
+#[cfg(feature = "ds2409")]
+pub mod ds2409;
+#[cfg(feature = "ds2413")]
+pub mod ds2413;
+#[cfg(feature = "ds2431")]
+pub mod ds2431;
+#[cfg(feature = "ds2432")]
+pub mod ds2432;
+#[cfg(feature = "ds2433")]
+pub mod ds2433;
+#[cfg(feature = "ds2450")]
+pub mod ds2450;
+#[cfg(feature = "ds2482")]
+pub mod ds2482;
+#[cfg(feature = "ds2502")]
+pub mod ds2502;
+#[cfg(feature = "ds28e17")]
+pub mod ds28e17;
+#[cfg(feature = "ds28e18")]
+pub mod ds28e18;
+#[cfg(feature = "ds28ea00")]
+pub mod ds28ea00;
+#[cfg(feature = "ds28ec20")]
+pub mod ds28ec20;
+#[cfg(feature = "events")]
+pub mod events;
+#[cfg(feature = "alloc")]
+pub mod export;
+pub mod family;
+pub mod filter;
+pub mod ibutton;
+pub mod labels;
+#[cfg(all(feature = "linux", feature = "ds18b20"))]
+pub mod linux;
+pub mod manager;
+pub mod master;
+#[cfg(feature = "max31826")]
+pub mod max31826;
+pub mod memory;
+pub mod observer;
+pub mod pin;
+#[cfg(feature = "rp2040-pio")]
+pub mod pio;
+pub mod provisioning;
+pub mod reading;
+#[cfg(feature = "esp32-rmt")]
+pub mod rmt;
+#[cfg(feature = "rtc")]
+pub mod rtc;
+pub mod scan;
+#[cfg(any(
+    feature = "ds2431",
+    feature = "ds2432",
+    feature = "ds2433",
+    feature = "ds28ec20"
+))]
+mod scratchpad;
+#[cfg(test)]
+mod test_support;
+pub mod timing;
+#[cfg(feature = "uart")]
+pub mod uart;
+
+#[cfg(feature = "ds18b20")]
 pub use crate::ds18b20::DS18B20;
+pub use crate::master::OneWireMaster;
+pub use crate::reading::{Quality, Reading, Unit};
+pub use crate::timing::{Operation, Speed, Timing, TimingError};
 
+use crate::family::SupportedSpeed;
 use core::fmt::Formatter;
 use core::fmt::{Debug, Display};
+use core::task::Poll;
 use hal::blocking::delay::DelayUs;
 use hal::digital::v2::InputPin;
 use hal::digital::v2::OutputPin;
@@ -18,17 +116,49 @@ pub const ADDRESS_BYTES: u8 = 8;
 pub const ADDRESS_BITS: u8 = ADDRESS_BYTES * 8;
 
 #[repr(u8)]
+#[derive(Debug, Clone, Copy)]
 pub enum Command {
     SelectRom = 0x55,
     SearchNext = 0xF0,
     SearchNextAlarmed = 0xEC,
+    SkipRom = 0xCC,
+    /// Makes the only device on the bus send its address
+    ReadRom = 0x33,
+    /// Like [`Command::SelectRom`], but puts the selected device into overdrive speed
+    OverdriveSelectRom = 0x69,
+    /// Puts all overdrive capable devices into overdrive speed
+    OverdriveSkipRom = 0x3C,
 }
 
 #[derive(Debug)]
 pub enum Error<E: Sized + Debug> {
     WireNotHigh,
+    /// No device answered the reset pulse
+    NoPresencePulse,
     CrcMismatch(u8, u8),
+    /// The CRC16 computed over the received data (first) and the received one (second)
+    /// do not match
+    Crc16Mismatch(u16, u16),
+    /// The device reported a failure of the command with the given result byte
+    CommandFailed(u8),
     FamilyCodeMismatch(u8, u8),
+    /// The configured parasite mode (first) contradicts whether parasite powered devices
+    /// were detected on the bus (second)
+    ParasiteModeMismatch(bool, bool),
+    /// The wire was already low right before it was to be driven, presumably by another
+    /// master, see [`OneWire::set_collision_detection`]
+    BusContention,
+    /// A read slot was sampled the given microseconds after it started, too late for the
+    /// bit of the device to be valid. An interrupt presumably exceeded
+    /// [`Timing::interrupt_latency_budget_us`], see [`OneWire::set_latency_check`].
+    SlotOverrun(u32),
+    /// The device did not signal the completion of an operation within the expected time
+    Timeout,
+    /// The MAC of the device does not match the one computed on the host, the device does
+    /// not know the secret
+    MacMismatch,
+    /// The address, page or block lies beyond the memory of the device
+    OutOfRange,
     Debug(Option<u8>),
     PortError(E),
 }
@@ -39,6 +169,14 @@ impl<E: Sized + Debug> From<E> for Error<E> {
     }
 }
 
+/// The CRC of an address computed over its first seven bytes and the last byte, which
+/// do not match, see [`Device::try_new`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidCrc {
+    pub computed: u8,
+    pub received: u8,
+}
+
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub struct Device {
     pub address: [u8; ADDRESS_BYTES as usize],
@@ -48,48 +186,120 @@ impl Device {
     pub fn family_code(&self) -> u8 {
         self.address[0]
     }
+
+    /// Whether the last byte of the address is the CRC of the others, which a corrupted
+    /// search result or mistyped address most likely fails
+    pub const fn is_valid(&self) -> bool {
+        crc8_const(&self.address) == 0
+    }
+
+    /// Fails if the last byte of the address is not the CRC of the others
+    pub fn try_new(address: [u8; ADDRESS_BYTES as usize]) -> Result<Device, InvalidCrc> {
+        let [data @ .., received] = address;
+        let computed = crc8_const(&data);
+        if computed != received {
+            Err(InvalidCrc { computed, received })
+        } else {
+            Ok(Device { address })
+        }
+    }
+
+    /// Fails with [`Error::CrcMismatch`] if the last byte of the address is not its CRC
+    pub fn checked<E: Debug>(address: [u8; ADDRESS_BYTES as usize]) -> Result<Device, Error<E>> {
+        Device::try_new(address)
+            .map_err(|InvalidCrc { computed, received }| Error::CrcMismatch(computed, received))
+    }
+
+    /// Panics if the last byte of the address is not its CRC, which turns a mistyped
+    /// address into a build error when used in a constant, see [`device!`]
+    pub const fn validated(address: [u8; ADDRESS_BYTES as usize]) -> Device {
+        assert!(
+            crc8_const(&address) == 0,
+            "the CRC of the address does not match"
+        );
+        Device { address }
+    }
+}
+
+/// A [`Device`] whose address is checked at compile time
+///
+/// ```
+/// use onewire::{device, Device};
+/// const BOILER_OUT: Device = device!(0x28, 0xFF, 0x64, 0x1E, 0x0F, 0x2D, 0x04, 0x1D);
+/// ```
+///
+/// ```compile_fail
+/// use onewire::{device, Device};
+/// const BOILER_OUT: Device = device!(0x28, 0xFF, 0x64, 0x1E, 0x0F, 0x2D, 0x40, 0x1D);
+/// ```
+#[macro_export]
+macro_rules! device {
+    ($($byte:expr),+ $(,)?) => {{
+        const DEVICE: $crate::Device = $crate::Device::validated([$($byte),+]);
+        DEVICE
+    }};
 }
 
 impl core::str::FromStr for Device {
     type Err = core::num::ParseIntError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() < 23 {
-            let _ = u8::from_str_radix("", 16)?; // this causes a ParseIntError::Empty
-        }
-        Ok(Device {
-            address: [
-                u8::from_str_radix(&s[0..2], 16)?,
-                u8::from_str_radix(&s[3..5], 16)?,
-                u8::from_str_radix(&s[6..8], 16)?,
-                u8::from_str_radix(&s[9..11], 16)?,
-                u8::from_str_radix(&s[12..14], 16)?,
-                u8::from_str_radix(&s[15..17], 16)?,
-                u8::from_str_radix(&s[18..20], 16)?,
-                u8::from_str_radix(&s[21..23], 16)?,
-            ],
-        })
+        let mut address = [0u8; ADDRESS_BYTES as usize];
+        for (i, byte) in address.iter_mut().enumerate() {
+            // `get` fails instead of panicking if the string is too short or the
+            // range is not on a char boundary, the empty str then causes a ParseIntError::Empty
+            let hex = s.get(i * 3..i * 3 + 2).unwrap_or("");
+            *byte = u8::from_str_radix(hex, 16)?;
+        }
+        Ok(Device { address })
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 enum SearchState {
+    #[default]
     Initialized,
     DeviceFound,
     End,
 }
 
-impl Default for SearchState {
-    fn default() -> Self {
-        SearchState::Initialized
+/// Bit positions of an address at which devices with both bit values responded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Discrepancies([u8; ADDRESS_BYTES as usize]);
+
+impl Discrepancies {
+    pub fn is_set(&self, bit: u8) -> bool {
+        DeviceSearch::is_bit_set(&self.0, bit)
+    }
+
+    /// Iterates the bit positions in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..ADDRESS_BITS).filter(move |bit| self.is_set(*bit))
+    }
+
+    pub fn last(&self) -> Option<u8> {
+        self.iter().last()
     }
+
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_BYTES as usize] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SearchProgress {
+    next_bit: u8,
+    last_discrepancy: Option<u8>,
+    discrepancy_found: bool,
 }
 
 #[derive(Clone, Default)]
 pub struct DeviceSearch {
     address: [u8; 8],
     discrepancies: [u8; 8],
+    forks: Discrepancies,
     state: SearchState,
+    progress: Option<SearchProgress>,
 }
 
 impl DeviceSearch {
@@ -145,30 +355,33 @@ impl DeviceSearch {
     }
 
     fn is_bit_set(array: &[u8], bit: u8) -> bool {
-        if bit / 8 >= array.len() as u8 {
-            return false;
-        }
         let index = bit / 8;
         let offset = bit % 8;
-        array[index as usize] & (0x01 << offset) != 0x00
+        array
+            .get(index as usize)
+            .is_some_and(|byte| byte & (0x01 << offset) != 0x00)
     }
 
     fn set_bit(array: &mut [u8], bit: u8) {
-        if bit / 8 >= array.len() as u8 {
-            return;
-        }
         let index = bit / 8;
         let offset = bit % 8;
-        array[index as usize] |= 0x01 << offset
+        if let Some(byte) = array.get_mut(index as usize) {
+            *byte |= 0x01 << offset
+        }
     }
 
     fn reset_bit(array: &mut [u8], bit: u8) {
-        if bit / 8 >= array.len() as u8 {
-            return;
-        }
         let index = bit / 8;
         let offset = bit % 8;
-        array[index as usize] &= !(0x01 << offset)
+        if let Some(byte) = array.get_mut(index as usize) {
+            *byte &= !(0x01 << offset)
+        }
+    }
+
+    /// All discrepancies encountered while finding the most recent device - including
+    /// the ones already explored by previous search steps
+    pub fn last_step_discrepancies(&self) -> Discrepancies {
+        self.forks
     }
 
     pub fn last_discrepancy(&self) -> Option<u8> {
@@ -181,11 +394,105 @@ impl DeviceSearch {
         result
     }
 
-    pub fn into_iter<'a, ODO: OpenDrainOutput>(
+    /// Returns the last discrepancy of the previous search step or `None` if the search
+    /// is over
+    fn search_prepare(&mut self) -> Option<Option<u8>> {
+        if SearchState::End == self.state {
+            return None;
+        }
+        self.forks = Discrepancies::default();
+        Some(self.last_discrepancy())
+    }
+
+    /// Called after the search command was sent, see [`DeviceSearch::search_prepare`]
+    fn search_continues(&mut self, last_discrepancy: Option<u8>) -> Option<Option<u8>> {
+        // no discrepancy and device found, meaning the one found is the only one
+        if last_discrepancy.is_none() && self.state == SearchState::DeviceFound {
+            self.state = SearchState::End;
+            return None;
+        }
+        Some(last_discrepancy)
+    }
+
+    /// Decides which path to follow at bit `i` of the address given the bit and the
+    /// complementary bit read from the devices, `None` if no device responded
+    fn search_direction(
+        &mut self,
+        i: u8,
+        last_discrepancy: Option<u8>,
+        discrepancy_found: &mut bool,
+        bit0: bool,
+        bit1: bool,
+    ) -> Option<bool> {
+        match last_discrepancy {
+            Some(last_discrepancy) if i < last_discrepancy => {
+                // walk previous path
+                if bit0 && bit1 {
+                    // no device responded
+                    return None;
+                }
+                if !bit0 && !bit1 {
+                    DeviceSearch::set_bit(&mut self.forks.0, i);
+                }
+                Some(self.is_bit_set_in_address(i))
+            }
+            Some(last_discrepancy) if i == last_discrepancy => {
+                // be sure to go different path from before (go second path, thus writing 1)
+                DeviceSearch::set_bit(&mut self.forks.0, i);
+                self.reset_bit_in_discrepancy(i);
+                self.set_bit_in_address(i);
+                Some(true)
+            }
+            _ => {
+                if bit0 && bit1 {
+                    // no response received
+                    return None;
+                }
+
+                if !bit0 && !bit1 {
+                    // addresses with 0 and 1
+                    // found new path, go first path by default (thus writing 0)
+                    *discrepancy_found |= true;
+                    DeviceSearch::set_bit(&mut self.forks.0, i);
+                    self.set_bit_in_discrepancy(i);
+                    self.reset_bit_in_address(i);
+                    Some(false)
+                } else {
+                    // addresses only with bit0
+                    self.write_bit_in_address(i, bit0);
+                    Some(bit0)
+                }
+            }
+        }
+    }
+
+    /// The path [`DeviceSearch::search_direction`] follows at bit `i` if devices with both
+    /// bit values respond, for masters deciding on their own like with
+    /// [`OneWireMaster::triplet`]
+    fn search_preferred_direction(&self, i: u8, last_discrepancy: Option<u8>) -> bool {
+        match last_discrepancy {
+            Some(last_discrepancy) if i < last_discrepancy => self.is_bit_set_in_address(i),
+            Some(last_discrepancy) => i == last_discrepancy,
+            None => false,
+        }
+    }
+
+    fn search_finish(&mut self, discrepancy_found: bool) -> Device {
+        if !discrepancy_found && self.last_discrepancy().is_none() {
+            self.state = SearchState::End;
+        } else {
+            self.state = SearchState::DeviceFound;
+        }
+        Device {
+            address: self.address,
+        }
+    }
+
+    pub fn into_iter<'a, W: OneWireMaster>(
         self,
-        wire: &'a mut OneWire<ODO>,
+        wire: &'a mut W,
         delay: &'a mut impl DelayUs<u16>,
-    ) -> DeviceSearchIter<'a, ODO, impl DelayUs<u16>> {
+    ) -> DeviceSearchIter<'a, W, impl DelayUs<u16>> {
         DeviceSearchIter {
             search: Some(self),
             wire,
@@ -194,14 +501,14 @@ impl DeviceSearch {
     }
 }
 
-pub struct DeviceSearchIter<'a, ODO: OpenDrainOutput, Delay: DelayUs<u16>> {
+pub struct DeviceSearchIter<'a, W: OneWireMaster, Delay: DelayUs<u16>> {
     search: Option<DeviceSearch>,
-    wire: &'a mut OneWire<ODO>,
+    wire: &'a mut W,
     delay: &'a mut Delay,
 }
 
-impl<'a, ODO: OpenDrainOutput, Delay: DelayUs<u16>> Iterator for DeviceSearchIter<'a, ODO, Delay> {
-    type Item = Result<Device, Error<ODO::Error>>;
+impl<'a, W: OneWireMaster, Delay: DelayUs<u16>> Iterator for DeviceSearchIter<'a, W, Delay> {
+    type Item = Result<Device, Error<W::Error>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut search = self.search.take()?;
@@ -217,6 +524,10 @@ impl<'a, ODO: OpenDrainOutput, Delay: DelayUs<u16>> Iterator for DeviceSearchIte
 pub trait OpenDrainOutput {
     type Error: Sized + Debug;
 
+    /// Whether all devices on the bus are known to be powered through VDD. If so,
+    /// [`OneWire`] compiles out every parasite mode branch. See [`Powered`].
+    const ALWAYS_POWERED: bool = false;
+
     /// Is the input pin high?
     fn is_high(&self) -> Result<bool, Self::Error>;
 
@@ -234,6 +545,39 @@ pub trait OpenDrainOutput {
     /// *NOTE* the actual electrical state of the pin may not actually be high, e.g. due to external
     /// electrical sources
     fn set_high(&mut self) -> Result<(), Self::Error>;
+
+    /// Invoked before each bus transaction, e.g. to save the configuration of a pin
+    /// that is shared with another function and to switch it into open drain mode
+    fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Invoked after each bus transaction - even a failed one - e.g. to restore the
+    /// configuration saved in [`OpenDrainOutput::begin_transaction`]
+    fn end_transaction(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Enables the internal pull-up of the MCU while the bus is released for reading and
+    /// disables it while the bus is driven low. Only meant for short wires without an
+    /// external pull-up resistor (usually 4.7k), which should be preferred wherever possible.
+    fn set_internal_pull_up(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Connects the wire to VDD through a low impedance (e.g. a MOSFET), while parasite
+    /// powered devices need more current than the pull-up resistor supplies. Only invoked
+    /// in parasite mode, enabled while the wire is released after a command and disabled
+    /// before the wire is driven again. See [`pin::StrongPullUpPin`].
+    fn set_strong_pull_up(&mut self, _enabled: bool) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// A free running microsecond counter, e.g. the cycle counter divided by the clock in
+    /// MHz, that is allowed to wrap. Only needed for [`OneWire::set_latency_check`].
+    fn timestamp_us(&self) -> Option<u32> {
+        None
+    }
 }
 impl<E: Debug, P: OutputPin<Error = E> + InputPin<Error = E>> OpenDrainOutput for P {
     type Error = E;
@@ -255,19 +599,217 @@ impl<E: Debug, P: OutputPin<Error = E> + InputPin<Error = E>> OpenDrainOutput fo
     }
 }
 
+/// Marks the wrapped pin as driving a bus on which every device is powered through VDD,
+/// so that no strong pull-up or parasite mode handling is ever needed
+pub struct Powered<ODO>(pub ODO);
+
+impl<ODO: OpenDrainOutput> OpenDrainOutput for Powered<ODO> {
+    type Error = ODO::Error;
+
+    const ALWAYS_POWERED: bool = true;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.0.is_high()
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.0.is_low()
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.0.begin_transaction()
+    }
+
+    fn end_transaction(&mut self) -> Result<(), Self::Error> {
+        self.0.end_transaction()
+    }
+
+    fn set_internal_pull_up(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.0.set_internal_pull_up(enabled)
+    }
+
+    fn timestamp_us(&self) -> Option<u32> {
+        self.0.timestamp_us()
+    }
+}
+
+/// A bus on which every device is powered through VDD
+pub type PoweredBus<ODO> = OneWire<Powered<ODO>>;
+
 pub struct OneWire<ODO: OpenDrainOutput> {
     output: ODO,
     parasite_mode: bool,
+    timing: Timing,
+    overdrive_timing: Timing,
+    speed: Speed,
+    overdrive_active: bool,
+    collision_detection: bool,
+    contention: bool,
+    /// Whether the master itself holds the wire low, which leaves nothing to sample
+    driving_low: bool,
+    latency_check: bool,
+    worst_sample_us: Option<u32>,
+    overrun: Option<u32>,
+    strong_pull_up: bool,
+}
+
+impl<ODO: OpenDrainOutput> PoweredBus<ODO> {
+    pub fn new_powered(output: ODO) -> Self {
+        OneWire {
+            output: Powered(output),
+            parasite_mode: false,
+            timing: Timing::STANDARD,
+            overdrive_timing: Timing::OVERDRIVE,
+            speed: Speed::Standard,
+            overdrive_active: false,
+            collision_detection: false,
+            contention: false,
+            driving_low: false,
+            latency_check: false,
+            worst_sample_us: None,
+            overrun: None,
+            strong_pull_up: false,
+        }
+    }
 }
 
 impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
     pub fn new(output: ODO, parasite_mode: bool) -> Self {
         OneWire {
             output,
-            parasite_mode,
+            parasite_mode: parasite_mode && !ODO::ALWAYS_POWERED,
+            timing: Timing::STANDARD,
+            overdrive_timing: Timing::OVERDRIVE,
+            speed: Speed::Standard,
+            overdrive_active: false,
+            collision_detection: false,
+            contention: false,
+            driving_low: false,
+            latency_check: false,
+            worst_sample_us: None,
+            overrun: None,
+            strong_pull_up: false,
+        }
+    }
+
+    /// Like [`OneWire::new`] but verifies that the wire is pulled high at rest and that at
+    /// least one device answers a reset with a presence pulse. Returns
+    /// [`Error::WireNotHigh`] if the wire is shorted or the pull-up resistor is missing and
+    /// [`Error::NoPresencePulse`] if no device is connected.
+    pub fn try_new(
+        output: ODO,
+        parasite_mode: bool,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Self, Error<E>> {
+        let mut wire = OneWire::new(output, parasite_mode);
+        let present = wire.transaction(|wire| wire.reset(delay))?;
+        if present {
+            Ok(wire)
+        } else {
+            Err(Error::NoPresencePulse)
+        }
+    }
+
+    /// Releases the wire and returns the pin, e.g. to reconfigure it for another purpose.
+    /// Failed transactions release the wire on their own, but a wire left low by the lower
+    /// level functions is only released here.
+    pub fn release(mut self) -> ODO {
+        self.release_line();
+        self.output
+    }
+
+    pub fn inner(&self) -> &ODO {
+        &self.output
+    }
+
+    /// Changing the state of the pin in the middle of a transaction corrupts it
+    pub fn inner_mut(&mut self) -> &mut ODO {
+        &mut self.output
+    }
+
+    pub fn parasite_mode(&self) -> bool {
+        !ODO::ALWAYS_POWERED && self.parasite_mode
+    }
+
+    pub fn timing(&self) -> &Timing {
+        &self.timing
+    }
+
+    /// Replaces the timing of the bus, e.g. to accommodate slow clones. Keeps the previous
+    /// timing if the new one violates the specification, see [`Timing::check`].
+    pub fn set_timing(&mut self, timing: Timing) -> Result<(), TimingError> {
+        timing.check()?;
+        self.timing = timing;
+        Ok(())
+    }
+
+    /// Replaces the timing used while in overdrive speed, see [`Timing::OVERDRIVE`] and
+    /// [`OneWire::set_timing`]
+    pub fn set_overdrive_timing(&mut self, timing: Timing) -> Result<(), TimingError> {
+        timing.check_for(Speed::Overdrive)?;
+        self.overdrive_timing = timing;
+        Ok(())
+    }
+
+    pub fn overdrive_timing(&self) -> &Timing {
+        &self.overdrive_timing
+    }
+
+    /// The speed the bus currently runs at: overdrive once the devices were switched by
+    /// [`OneWire::skip`] or [`OneWire::select`] within [`OneWire::transaction_with_speed`]
+    pub fn speed(&self) -> Speed {
+        if self.overdrive_active {
+            Speed::Overdrive
+        } else {
+            Speed::Standard
         }
     }
 
+    fn active_timing(&self) -> &Timing {
+        if self.overdrive_active {
+            &self.overdrive_timing
+        } else {
+            &self.timing
+        }
+    }
+
+    /// Enables sampling the wire right before driving it for a reset or a write slot. If it
+    /// is already low, presumably because another master shares the wire, the write slots
+    /// are skipped and [`Error::BusContention`] is returned by the transaction or the next
+    /// reset. This is best effort only: a collision starting after the sample goes unnoticed.
+    pub fn set_collision_detection(&mut self, enabled: bool) {
+        self.collision_detection = enabled;
+        self.contention = false;
+    }
+
+    /// Enables measuring with [`OpenDrainOutput::timestamp_us`] when each read slot is
+    /// sampled. A read slot sampled too late fails the transaction or the next reset with
+    /// [`Error::SlotOverrun`].
+    pub fn set_latency_check(&mut self, enabled: bool) {
+        self.latency_check = enabled;
+        self.worst_sample_us = None;
+        self.overrun = None;
+    }
+
+    /// The longest time in microseconds from the start of a read slot until it was sampled
+    /// since the latency check was enabled
+    pub fn worst_sample_us(&self) -> Option<u32> {
+        self.worst_sample_us
+    }
+
+    /// Estimates the bus time in microseconds an operation takes with the active timing
+    pub fn estimate_us(&self, operation: Operation) -> u32 {
+        self.active_timing().estimate_us(operation)
+    }
+
     pub fn reset_select_write_read(
         &mut self,
         delay: &mut impl DelayUs<u16>,
@@ -275,11 +817,13 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         write: &[u8],
         read: &mut [u8],
     ) -> Result<(), Error<E>> {
-        self.reset(delay)?;
-        self.select(delay, device)?;
-        self.write_bytes(delay, write)?;
-        self.read_bytes(delay, read)?;
-        Ok(())
+        self.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, device)?;
+            wire.write_bytes(delay, write)?;
+            wire.read_bytes(delay, read)?;
+            Ok(())
+        })
     }
 
     pub fn reset_select_read_only(
@@ -288,11 +832,12 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         device: &Device,
         read: &mut [u8],
     ) -> Result<(), Error<E>> {
-        self.reset(delay)?;
-        self.select(delay, device)?;
-        self.select(delay, device)?;
-        self.read_bytes(delay, read)?;
-        Ok(())
+        self.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, device)?;
+            wire.read_bytes(delay, read)?;
+            Ok(())
+        })
     }
 
     pub fn reset_select_write_only(
@@ -301,10 +846,96 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         device: &Device,
         write: &[u8],
     ) -> Result<(), Error<E>> {
-        self.reset(delay)?;
-        self.select(delay, device)?;
-        self.select(delay, device)?;
-        self.write_bytes(delay, write)?;
+        self.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, device)?;
+            wire.write_bytes(delay, write)?;
+            Ok(())
+        })
+    }
+
+    /// Like [`OneWire::reset_select_write_read`], but addresses all devices at once, e.g. to
+    /// start a conversion on all of them
+    pub fn reset_skip_write_read(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        self.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.skip(delay)?;
+            wire.write_bytes(delay, write)?;
+            wire.read_bytes(delay, read)?;
+            Ok(())
+        })
+    }
+
+    /// Reading from several devices at once only makes sense for a single device on the
+    /// bus or for commands all devices answer the same way
+    pub fn reset_skip_read_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        self.reset_skip_write_read(delay, &[], read)
+    }
+
+    pub fn reset_skip_write_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        write: &[u8],
+    ) -> Result<(), Error<E>> {
+        self.reset_skip_write_read(delay, write, &mut [])
+    }
+
+    /// Wraps the given bus activity in the [`OpenDrainOutput::begin_transaction`] and
+    /// [`OpenDrainOutput::end_transaction`] hooks of the pin
+    pub fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        self.output.begin_transaction()?;
+        let result = f(self);
+        if result.is_err() {
+            // the activity may have been abandoned in the middle of a slot
+            self.release_line();
+        }
+        self.output.end_transaction()?;
+        if let Some(e) = self.take_slot_error() {
+            return Err(e);
+        }
+        result
+    }
+
+    /// Runs the bus activity at the given speed. With [`Speed::Overdrive`], the devices
+    /// selected with [`OneWire::select`] are switched into overdrive speed and everything
+    /// following, including further resets, happens at overdrive speed. Afterwards, the next
+    /// reset is a standard speed reset, which returns all devices to standard speed.
+    ///
+    /// The speed is checked against the capabilities of the devices at compile time, e.g.
+    /// `const { DS2431::capabilities().validated_speed(Speed::Overdrive) }`.
+    pub fn transaction_with_speed<T>(
+        &mut self,
+        speed: SupportedSpeed,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        self.speed = speed.speed();
+        let result = self.transaction(f);
+        self.speed = Speed::Standard;
+        self.overdrive_active = false;
+        result
+    }
+
+    /// Addresses all devices, at the speed of [`OneWire::transaction_with_speed`]
+    pub fn skip(&mut self, delay: &mut impl DelayUs<u16>) -> Result<(), Error<E>> {
+        let parasite_mode = self.parasite_mode();
+        if self.speed == Speed::Overdrive && !self.overdrive_active {
+            self.write_command(delay, Command::OverdriveSkipRom, parasite_mode)?;
+            self.overdrive_active = true;
+        } else {
+            self.write_command(delay, Command::SkipRom, parasite_mode)?;
+        }
         Ok(())
     }
 
@@ -313,21 +944,45 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         delay: &mut impl DelayUs<u16>,
         device: &Device,
     ) -> Result<(), Error<E>> {
-        let parasite_mode = self.parasite_mode;
+        let parasite_mode = self.parasite_mode();
+        if self.speed == Speed::Overdrive && !self.overdrive_active {
+            // the command itself is still sent at standard speed
+            self.write_command(delay, Command::OverdriveSelectRom, false)?;
+            self.overdrive_active = true;
+            for (i, byte) in device.address.iter().enumerate() {
+                let last = i == device.address.len() - 1;
+                self.write_byte_powered(delay, *byte, parasite_mode && last)?;
+            }
+            return Ok(());
+        }
         self.write_command(delay, Command::SelectRom, parasite_mode)?; // select
-        for i in 0..device.address.len() {
+        for (i, byte) in device.address.iter().enumerate() {
             let last = i == device.address.len() - 1;
-            self.write_byte(delay, device.address[i], parasite_mode && last)?;
+            self.write_byte_powered(delay, *byte, parasite_mode && last)?;
         }
         Ok(())
     }
 
+    /// Reads the address of the only device on the bus without searching. With more than
+    /// one device, their answers collide and the CRC check fails.
+    pub fn read_rom(&mut self, delay: &mut impl DelayUs<u16>) -> Result<Device, Error<E>> {
+        self.transaction(|wire| {
+            if !wire.reset(delay)? {
+                return Err(Error::NoPresencePulse);
+            }
+            wire.write_command(delay, Command::ReadRom, false)?;
+            let mut address = [0u8; ADDRESS_BYTES as usize];
+            wire.read_bytes(delay, &mut address)?;
+            Device::checked(address)
+        })
+    }
+
     pub fn search_next(
         &mut self,
         search: &mut DeviceSearch,
         delay: &mut impl DelayUs<u16>,
     ) -> Result<Option<Device>, Error<E>> {
-        self.search(search, delay, Command::SearchNext)
+        self.transaction(|wire| wire.search(search, delay, Command::SearchNext))
     }
 
     pub fn search_next_alarmed(
@@ -335,7 +990,44 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         search: &mut DeviceSearch,
         delay: &mut impl DelayUs<u16>,
     ) -> Result<Option<Device>, Error<E>> {
-        self.search(search, delay, Command::SearchNextAlarmed)
+        self.transaction(|wire| wire.search(search, delay, Command::SearchNextAlarmed))
+    }
+
+    /// Like [`OneWire::search_next`] but also returns the discrepancies encountered while
+    /// finding the device, which hint at the bus topology
+    pub fn search_next_with_discrepancies(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<(Device, Discrepancies)>, Error<E>> {
+        Ok(self
+            .search_next(search, delay)?
+            .map(|device| (device, search.last_step_discrepancies())))
+    }
+
+    /// Like [`OneWire::search_next`] but only processes up to `max_bits` of the address
+    /// per call, returning [`Poll::Pending`] until the search step is complete. This keeps
+    /// each call short enough to service a watchdog in between.
+    pub fn search_next_chunked(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+        max_bits: u8,
+    ) -> Result<Poll<Option<Device>>, Error<E>> {
+        self.transaction(|wire| wire.search_chunked(search, delay, Command::SearchNext, max_bits))
+    }
+
+    /// Like [`OneWire::search_next_alarmed`] but split into chunks, see
+    /// [`OneWire::search_next_chunked`]
+    pub fn search_next_alarmed_chunked(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+        max_bits: u8,
+    ) -> Result<Poll<Option<Device>>, Error<E>> {
+        self.transaction(|wire| {
+            wire.search_chunked(search, delay, Command::SearchNextAlarmed, max_bits)
+        })
     }
 
     /// Heavily inspired by https://github.com/ntruchsess/arduino-OneWire/blob/85d1aae63ea4919c64151e03f7e24c2efbc40198/OneWire.cpp#L362
@@ -345,81 +1037,110 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         delay: &mut impl DelayUs<u16>,
         cmd: Command,
     ) -> Result<Option<Device>, Error<E>> {
-        if SearchState::End == rom.state {
-            return Ok(None);
-        }
+        rom.progress = None;
+        let last_discrepancy = match self.search_begin(rom, delay, cmd)? {
+            Some(last_discrepancy) => last_discrepancy,
+            None => return Ok(None),
+        };
 
         let mut discrepancy_found = false;
-        let last_discrepancy = rom.last_discrepancy();
+        for i in 0..ADDRESS_BITS {
+            if !self.search_bit(rom, delay, i, last_discrepancy, &mut discrepancy_found)? {
+                return Ok(None);
+            }
+        }
+        Ok(Some(rom.search_finish(discrepancy_found)))
+    }
 
-        if !self.reset(delay)? {
-            return Ok(None);
+    fn search_chunked(
+        &mut self,
+        rom: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+        cmd: Command,
+        max_bits: u8,
+    ) -> Result<Poll<Option<Device>>, Error<E>> {
+        // an error drops the progress, so the next call starts the search step anew
+        let mut progress = match rom.progress.take() {
+            Some(progress) => progress,
+            None => match self.search_begin(rom, delay, cmd)? {
+                Some(last_discrepancy) => SearchProgress {
+                    next_bit: 0,
+                    last_discrepancy,
+                    discrepancy_found: false,
+                },
+                None => return Ok(Poll::Ready(None)),
+            },
+        };
+
+        let end = progress
+            .next_bit
+            .saturating_add(max_bits.max(1))
+            .min(ADDRESS_BITS);
+        for i in progress.next_bit..end {
+            let last_discrepancy = progress.last_discrepancy;
+            if !self.search_bit(
+                rom,
+                delay,
+                i,
+                last_discrepancy,
+                &mut progress.discrepancy_found,
+            )? {
+                return Ok(Poll::Ready(None));
+            }
         }
 
-        self.write_byte(delay, cmd as u8, false)?;
+        if end < ADDRESS_BITS {
+            progress.next_bit = end;
+            rom.progress = Some(progress);
+            Ok(Poll::Pending)
+        } else {
+            Ok(Poll::Ready(Some(
+                rom.search_finish(progress.discrepancy_found),
+            )))
+        }
+    }
 
-        if let Some(last_discrepancy) = last_discrepancy {
-            // walk previous path
-            for i in 0..last_discrepancy {
-                let bit0 = self.read_bit(delay)?;
-                let bit1 = self.read_bit(delay)?;
+    /// Issues the search command, returns the last discrepancy of the previous search step
+    /// or `None` if the search is over
+    fn search_begin(
+        &mut self,
+        rom: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+        cmd: Command,
+    ) -> Result<Option<Option<u8>>, Error<E>> {
+        let last_discrepancy = match rom.search_prepare() {
+            Some(last_discrepancy) => last_discrepancy,
+            None => return Ok(None),
+        };
 
-                if bit0 && bit1 {
-                    // no device responded
-                    return Ok(None);
-                } else {
-                    let bit = rom.is_bit_set_in_address(i);
-                    // rom.write_bit_in_address(i, bit0);
-                    // rom.write_bit_in_discrepancy(i, bit);
-                    self.write_bit(delay, bit)?;
-                }
-            }
-        } else {
-            // no discrepancy and device found, meaning the one found is the only one
-            if rom.state == SearchState::DeviceFound {
-                rom.state = SearchState::End;
-                return Ok(None);
-            }
+        if !self.reset(delay)? {
+            return Ok(None);
         }
 
-        for i in last_discrepancy.unwrap_or(0)..ADDRESS_BITS {
-            let bit0 = self.read_bit(delay)?; // normal bit
-            let bit1 = self.read_bit(delay)?; // complementar bit
+        self.write_byte_powered(delay, cmd as u8, false)?;
 
-            if last_discrepancy.eq(&Some(i)) {
-                // be sure to go different path from before (go second path, thus writing 1)
-                rom.reset_bit_in_discrepancy(i);
-                rom.set_bit_in_address(i);
-                self.write_bit(delay, true)?;
-            } else {
-                if bit0 && bit1 {
-                    // no response received
-                    return Ok(None);
-                }
+        Ok(rom.search_continues(last_discrepancy))
+    }
 
-                if !bit0 && !bit1 {
-                    // addresses with 0 and 1
-                    // found new path, go first path by default (thus writing 0)
-                    discrepancy_found |= true;
-                    rom.set_bit_in_discrepancy(i);
-                    rom.reset_bit_in_address(i);
-                    self.write_bit(delay, false)?;
-                } else {
-                    // addresses only with bit0
-                    rom.write_bit_in_address(i, bit0);
-                    self.write_bit(delay, bit0)?;
-                }
+    /// Processes the bit at index `i` of the address, returns false if no device responded
+    fn search_bit(
+        &mut self,
+        rom: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+        i: u8,
+        last_discrepancy: Option<u8>,
+        discrepancy_found: &mut bool,
+    ) -> Result<bool, Error<E>> {
+        let bit0 = self.read_bit(delay)?; // normal bit
+        let bit1 = self.read_bit(delay)?; // complementar bit
+
+        match rom.search_direction(i, last_discrepancy, discrepancy_found, bit0, bit1) {
+            Some(bit) => {
+                self.write_bit(delay, bit)?;
+                Ok(true)
             }
+            None => Ok(false),
         }
-
-        if !discrepancy_found && rom.last_discrepancy().is_none() {
-            rom.state = SearchState::End;
-        } else {
-            rom.state = SearchState::DeviceFound;
-        }
-        Ok(Some(Device {
-            address: rom.address,
-        }))
     }
 
     /// Performs a reset and listens for a presence pulse
@@ -427,30 +1148,47 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
     /// Ok(true) if presence pulse has been received and Ok(false)
     /// if no other device was detected but the wire seems to be ok
     pub fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<E>> {
+        if let Some(e) = self.take_slot_error() {
+            return Err(e);
+        }
+
         // let mut cli = DisableInterrupts::new();
         self.set_input()?;
         // drop(cli);
 
         self.ensure_wire_high(delay)?;
+        if self.collision_detection && !self.read()? {
+            return Err(Error::BusContention);
+        }
         // cli = DisableInterrupts::new();
         self.write_low()?;
         self.set_output()?;
 
         // drop(cli);
-        delay.delay_us(480);
+        delay.delay_us(self.active_timing().reset_low);
         // cli = DisableInterrupts::new();
         self.set_input()?;
 
         let mut val = false;
-        for _ in 0..7 {
-            delay.delay_us(10);
+        for _ in 0..self.active_timing().presence_polls {
+            delay.delay_us(self.active_timing().presence_poll);
             val |= !self.read()?;
         }
         // drop(cli);
-        delay.delay_us(410);
+        delay.delay_us(self.active_timing().reset_recovery);
         Ok(val)
     }
 
+    /// Takes the error of a time slot recorded by the collision detection or the latency
+    /// check, as the slots themselves only return errors of the pin
+    fn take_slot_error(&mut self) -> Option<Error<E>> {
+        if core::mem::take(&mut self.contention) {
+            Some(Error::BusContention)
+        } else {
+            self.overrun.take().map(Error::SlotOverrun)
+        }
+    }
+
     fn ensure_wire_high(&mut self, delay: &mut impl DelayUs<u16>) -> Result<(), Error<E>> {
         for _ in 0..125 {
             if self.read()? {
@@ -468,7 +1206,8 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         Ok(())
     }
 
-    fn read_byte(&mut self, delay: &mut impl DelayUs<u16>) -> Result<u8, E> {
+    /// Reads a byte, least significant bit first
+    pub fn read_byte(&mut self, delay: &mut impl DelayUs<u16>) -> Result<u8, E> {
         let mut byte = 0_u8;
         for _ in 0..8 {
             byte >>= 1;
@@ -479,25 +1218,55 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         Ok(byte)
     }
 
-    fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, E> {
+    /// Generates a read slot and samples the answer of the devices
+    pub fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, E> {
+        let start = self.slot_timestamp();
         // let cli = DisableInterrupts::new();
         self.set_output()?;
         self.write_low()?;
-        delay.delay_us(3);
+        delay.delay_us(self.active_timing().read_low);
         self.set_input()?;
-        delay.delay_us(2); // was 10
+        delay.delay_us(self.active_timing().read_sample);
         let val = self.read();
         // drop(cli);
-        delay.delay_us(61); // was 53
+        self.check_sample_latency(start);
+        delay.delay_us(self.active_timing().read_recovery);
         val
     }
 
+    fn slot_timestamp(&self) -> Option<u32> {
+        if self.latency_check {
+            self.output.timestamp_us()
+        } else {
+            None
+        }
+    }
+
+    /// Records how long after `start` a read slot was sampled, see
+    /// [`OneWire::set_latency_check`]
+    fn check_sample_latency(&mut self, start: Option<u32>) {
+        if let (Some(start), Some(end)) = (start, self.output.timestamp_us()) {
+            let elapsed = end.wrapping_sub(start);
+            self.worst_sample_us = Some(self.worst_sample_us.unwrap_or(0).max(elapsed));
+            let limit = if self.overdrive_active {
+                timing::OVERDRIVE_READ_SAMPLE_MAX
+            } else {
+                timing::READ_SAMPLE_MAX
+            };
+            if elapsed > u32::from(limit) {
+                self.overrun = Some(elapsed);
+            }
+        }
+    }
+
     pub fn write_bytes(&mut self, delay: &mut impl DelayUs<u16>, bytes: &[u8]) -> Result<(), E> {
         for b in bytes {
-            self.write_byte(delay, *b, false)?;
+            self.write_byte_powered(delay, *b, false)?;
         }
-        if !self.parasite_mode {
+        if !self.parasite_mode() {
             self.disable_parasite_mode()?;
+        } else if !bytes.is_empty() {
+            self.enable_strong_pull_up()?;
         }
         Ok(())
     }
@@ -508,10 +1277,13 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
         cmd: Command,
         parasite_mode: bool,
     ) -> Result<(), E> {
-        self.write_byte(delay, cmd as u8, parasite_mode)
+        self.write_byte_powered(delay, cmd as u8, parasite_mode)
     }
 
-    fn write_byte(
+    /// Writes a byte, least significant bit first, like [`OneWireMaster::write_byte`]. With
+    /// `parasite_mode`, the wire is kept powered afterwards for the devices executing the
+    /// command, e.g. a conversion.
+    pub fn write_byte_powered(
         &mut self,
         delay: &mut impl DelayUs<u16>,
         mut byte: u8,
@@ -521,31 +1293,89 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
             self.write_bit(delay, (byte & 0x01) == 0x01)?;
             byte >>= 1;
         }
-        if !parasite_mode {
+        if !ODO::ALWAYS_POWERED && !parasite_mode {
             self.disable_parasite_mode()?;
+        } else if parasite_mode {
+            self.enable_strong_pull_up()?;
         }
         Ok(())
     }
 
-    fn write_bit(&mut self, delay: &mut impl DelayUs<u16>, high: bool) -> Result<(), E> {
+    /// Generates a write slot for the given bit
+    pub fn write_bit(&mut self, delay: &mut impl DelayUs<u16>, high: bool) -> Result<(), E> {
+        if self.collides()? {
+            return Ok(());
+        }
+        let (low, recovery) = self.write_slot(high);
         // let cli = DisableInterrupts::new();
         self.write_low()?;
         self.set_output()?;
-        delay.delay_us(if high { 10 } else { 65 });
+        delay.delay_us(low);
         self.write_high()?;
         // drop(cli);
-        delay.delay_us(if high { 55 } else { 5 });
+        delay.delay_us(recovery);
+        Ok(())
+    }
+
+    /// Whether the write slot has to be skipped, see [`OneWire::set_collision_detection`]
+    fn collides(&mut self) -> Result<bool, E> {
+        if self.collision_detection && (self.contention || (!self.driving_low && !self.read()?)) {
+            // do not corrupt the slots of the other master any further
+            self.contention = true;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// The low and the recovery time of a write slot
+    fn write_slot(&self, high: bool) -> (u16, u16) {
+        let timing = self.active_timing();
+        if high {
+            (timing.write_one_low, timing.write_one_recovery)
+        } else {
+            (timing.write_zero_low, timing.write_zero_recovery)
+        }
+    }
+
+    /// Supplies the parasite powered devices until the wire is driven next
+    fn enable_strong_pull_up(&mut self) -> Result<(), E> {
+        if ODO::ALWAYS_POWERED {
+            return Ok(());
+        }
+        self.write_high()?;
+        self.output.set_strong_pull_up(true)?;
+        self.strong_pull_up = true;
+        Ok(())
+    }
+
+    fn disable_strong_pull_up(&mut self) -> Result<(), E> {
+        if self.strong_pull_up {
+            self.strong_pull_up = false;
+            self.output.set_strong_pull_up(false)?;
+        }
         Ok(())
     }
 
     fn disable_parasite_mode(&mut self) -> Result<(), E> {
+        if ODO::ALWAYS_POWERED {
+            return Ok(());
+        }
         // let cli = DisableInterrupts::new();
         self.set_input()?;
         self.write_low()
     }
 
+    /// Releases the wire so it is pulled high, ignoring errors as there is nothing left to
+    /// do about them when giving up the bus
+    fn release_line(&mut self) {
+        let _ = self.disable_strong_pull_up();
+        let _ = self.set_input();
+    }
+
     fn set_input(&mut self) -> Result<(), E> {
-        self.output.set_high()
+        self.driving_low = false;
+        self.output.set_high()?;
+        self.output.set_internal_pull_up(true)
     }
 
     fn set_output(&mut self) -> Result<(), E> {
@@ -554,11 +1384,17 @@ impl<E: core::fmt::Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
     }
 
     fn write_low(&mut self) -> Result<(), E> {
+        self.disable_strong_pull_up()?;
+        self.output.set_internal_pull_up(false)?;
+        self.driving_low = true;
         self.output.set_low()
     }
 
     fn write_high(&mut self) -> Result<(), E> {
-        self.output.set_high()
+        self.driving_low = false;
+        self.output.set_high()?;
+        // without an external resistor the released wire floats low otherwise
+        self.output.set_internal_pull_up(true)
     }
 
     fn read(&self) -> Result<bool, E> {
@@ -571,35 +1407,130 @@ pub fn ensure_correct_rcr8<E: Debug>(
     data: &[u8],
     crc8: u8,
 ) -> Result<(), Error<E>> {
-    let computed = compute_crc8(device, data);
-    if computed != crc8 {
-        Err(Error::CrcMismatch(computed, crc8))
+    ensure_correct_crc8(compute_crc8(device, data), crc8)
+}
+
+/// Fails with [`Error::CrcMismatch`] if the CRC8 received after the data does not match
+pub fn check_crc8<E: Debug>(data: &[u8], received: u8) -> Result<(), Error<E>> {
+    ensure_correct_crc8(compute_partial_crc8(0, data), received)
+}
+
+/// Like [`check_crc8`] for a CRC computed with [`compute_partial_crc8`] over data received
+/// in several parts
+pub fn ensure_correct_crc8<E: Debug>(computed: u8, received: u8) -> Result<(), Error<E>> {
+    if computed != received {
+        Err(Error::CrcMismatch(computed, received))
     } else {
         Ok(())
     }
 }
 
-pub fn compute_crc8(device: &Device, data: &[u8]) -> u8 {
-    let crc = compute_partial_crc8(0u8, &device.address[..]);
+pub const fn compute_crc8(device: &Device, data: &[u8]) -> u8 {
+    let crc = compute_partial_crc8(0u8, &device.address);
     compute_partial_crc8(crc, data)
 }
 
-pub fn compute_partial_crc8(crc: u8, data: &[u8]) -> u8 {
+/// Const so addresses and tables can be checked at compile time, see [`device!`]. Bit by
+/// bit unless the `crc8-table` feature trades 256 bytes of flash for speed.
+pub const fn compute_partial_crc8(crc: u8, data: &[u8]) -> u8 {
+    let mut crc = crc;
+    let mut data = data;
+    while let [byte, rest @ ..] = data {
+        crc = crc8_step(crc, *byte);
+        data = rest;
+    }
+    crc
+}
+
+#[cfg(not(feature = "crc8-table"))]
+const fn crc8_step(crc: u8, byte: u8) -> u8 {
+    crc8_bitwise(crc, byte)
+}
+
+#[cfg(feature = "crc8-table")]
+#[allow(clippy::indexing_slicing)] // a u8 is always in bounds
+const fn crc8_step(crc: u8, byte: u8) -> u8 {
+    CRC8_TABLE[(crc ^ byte) as usize]
+}
+
+#[cfg(feature = "crc8-table")]
+#[allow(clippy::indexing_slicing)] // evaluated at compile time
+static CRC8_TABLE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut index = 0;
+    while index < table.len() {
+        table[index] = crc8_bitwise(0, index as u8);
+        index += 1;
+    }
+    table
+};
+
+const fn crc8_bitwise(crc: u8, byte: u8) -> u8 {
+    let mut crc = crc;
+    let mut byte = byte;
+    let mut bit = 0;
+    while bit < 8 {
+        let mix = (crc ^ byte) & 0x01;
+        crc >>= 1;
+        if mix != 0x00 {
+            crc ^= 0x8C;
+        }
+        byte >>= 1;
+        bit += 1;
+    }
+    crc
+}
+
+/// CRC8 over the whole data, an address including its CRC byte results in 0. Unlike
+/// [`compute_crc8`] it takes any data, not only the one following an address.
+pub const fn crc8_const(data: &[u8]) -> u8 {
+    compute_partial_crc8(0, data)
+}
+
+/// CRC16 as used by 1-Wire devices (polynomial 0xA001), which transmit the inverted
+/// value with the least significant byte first. Computing it over the data followed by
+/// such a transmitted CRC results in [`CRC16_RESIDUE`].
+pub fn compute_partial_crc16(crc: u16, data: &[u8]) -> u16 {
     let mut crc = crc;
     for byte in data.iter() {
-        let mut byte = *byte;
+        crc ^= u16::from(*byte);
         for _ in 0..8 {
-            let mix = (crc ^ byte) & 0x01;
+            let mix = crc & 0x01;
             crc >>= 1;
             if mix != 0x00 {
-                crc ^= 0x8C;
+                crc ^= 0xA001;
             }
-            byte >>= 1;
         }
     }
     crc
 }
 
+pub const CRC16_RESIDUE: u16 = 0xB001;
+
+/// The CRC16 a device transmits after the data, already inverted
+pub fn compute_crc16(data: &[u8]) -> u16 {
+    !compute_partial_crc16(0, data)
+}
+
+/// Fails with [`Error::Crc16Mismatch`] if the CRC16 received after the data, least
+/// significant byte first, does not match
+pub fn check_crc16<E: Debug>(data: &[u8], received: [u8; 2]) -> Result<(), Error<E>> {
+    ensure_correct_crc16(compute_partial_crc16(0, data), received)
+}
+
+/// Like [`check_crc16`] for a CRC computed with [`compute_partial_crc16`] over data
+/// received in several parts
+pub fn ensure_correct_crc16<E: Debug>(computed: u16, received: [u8; 2]) -> Result<(), Error<E>> {
+    if compute_partial_crc16(computed, &received) != CRC16_RESIDUE {
+        Err(Error::Crc16Mismatch(
+            !computed,
+            u16::from_le_bytes(received),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 impl Display for Device {
     fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
         write!(
@@ -617,9 +1548,49 @@ impl Display for Device {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl defmt::Format for Device {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}:{=u8:02x}",
+            self.address[0],
+            self.address[1],
+            self.address[2],
+            self.address[3],
+            self.address[4],
+            self.address[5],
+            self.address[6],
+            self.address[7],
+        )
+    }
+}
+
+/// Devices that can be configured to answer a conditional search
+/// ([`OneWire::search_next_alarmed`]) only under certain conditions, e.g. when a measured
+/// value exceeds a threshold or an input changed
+pub trait ConditionalSearch {
+    type Condition;
+
+    fn configure_conditional_search<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        condition: &Self::Condition,
+    ) -> Result<(), Error<O::Error>>;
+}
+
 pub trait Sensor {
     fn family_code() -> u8;
 
+    fn device(&self) -> &Device;
+
+    /// The unit of the measured values
+    fn unit(&self) -> Unit;
+
+    /// Converts a raw value into thousandths of the unit
+    fn raw_to_milli(&self, raw: u16) -> i32;
+
     /// returns the milliseconds required to wait until the measurement finished
     fn start_measurement<O: OpenDrainOutput>(
         &self,
@@ -628,15 +1599,502 @@ pub trait Sensor {
     ) -> Result<u16, Error<O::Error>>;
 
     /// returns the measured value
+    #[cfg(not(feature = "no-float"))]
     fn read_measurement<O: OpenDrainOutput>(
         &self,
         wire: &mut OneWire<O>,
         delay: &mut impl DelayUs<u16>,
     ) -> Result<f32, Error<O::Error>>;
 
+    /// returns the measured value in thousandths of its unit (e.g. millidegrees),
+    /// which does not require floating point support
+    fn read_measurement_milli<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<i32, Error<O::Error>> {
+        let raw = self.read_measurement_raw(wire, delay)?;
+        Ok(self.raw_to_milli(raw))
+    }
+
     fn read_measurement_raw<O: OpenDrainOutput>(
         &self,
         wire: &mut OneWire<O>,
         delay: &mut impl DelayUs<u16>,
     ) -> Result<u16, Error<O::Error>>;
+
+    /// Reads the measured value into a normalized [`Reading`], retrying once if the
+    /// received data is corrupted
+    fn read<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Reading, Error<O::Error>> {
+        let (raw, quality) = match self.read_measurement_raw(wire, delay) {
+            Ok(raw) => (raw, Quality::Fresh),
+            Err(Error::CrcMismatch(..)) | Err(Error::Crc16Mismatch(..)) => {
+                (self.read_measurement_raw(wire, delay)?, Quality::CrcRetried)
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Reading {
+            device: self.device().clone(),
+            raw,
+            milli: self.raw_to_milli(raw),
+            unit: self.unit(),
+            quality,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_crc16, check_crc8, compute_crc16, compute_partial_crc8, crc8_const, Device,
+        DeviceSearch, Error, InvalidCrc, OneWire, OpenDrainOutput, Operation, Speed, Timing,
+        TimingError,
+    };
+    use crate::family::{Capabilities, SupportedSpeed};
+    use crate::test_support::{
+        AddressAllocator, Clustered, Delay, LowPin, Pin, Seeded, SimDelay, SimPin, Simulator, Skew,
+    };
+    use core::str::FromStr;
+    use core::task::Poll;
+
+    fn search_all<const N: usize>(allocator: &mut impl AddressAllocator) {
+        let sim = Simulator::<N>::new(allocator);
+        let mut expected = sim.borrow().addresses();
+        expected.sort_unstable();
+
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut delay = SimDelay(&sim);
+        let mut search = DeviceSearch::new();
+        let mut found = [[0u8; 8]; N];
+        let mut count = 0;
+        while let Some(device) = wire.search_next(&mut search, &mut delay).unwrap() {
+            assert_eq!(0, compute_partial_crc8(0, &device.address));
+            found[count] = device.address;
+            count += 1;
+        }
+        found.sort_unstable();
+        assert_eq!(N, count);
+        assert_eq!(expected, found);
+
+        let mut search = DeviceSearch::new();
+        let mut count = 0;
+        loop {
+            match wire
+                .search_next_chunked(&mut search, &mut delay, 5)
+                .unwrap()
+            {
+                Poll::Ready(Some(device)) => {
+                    assert!(expected.contains(&device.address));
+                    count += 1;
+                }
+                Poll::Ready(None) => break,
+                Poll::Pending => {}
+            }
+        }
+        assert_eq!(N, count);
+    }
+
+    #[test]
+    fn test_search_seeded_slaves() {
+        search_all::<1>(&mut Seeded {
+            family: 0x28,
+            state: 1,
+        });
+        search_all::<300>(&mut Seeded {
+            family: 0x28,
+            state: 0xDEAD_BEEF,
+        });
+    }
+
+    #[test]
+    fn test_search_skewed_slaves() {
+        for skew in Skew::sweep() {
+            let sim = Simulator::<8>::new(&mut Seeded {
+                family: 0x28,
+                state: 42,
+            });
+            sim.borrow_mut().set_skew(skew);
+            let mut wire = OneWire::new(SimPin(&sim), false);
+            let report = wire.scan_report::<8>(&mut SimDelay(&sim)).unwrap();
+            assert!(report.is_healthy(), "{:?}: {}", skew, report);
+            assert_eq!(8, report.found(), "{:?}", skew);
+        }
+    }
+
+    #[test]
+    fn test_search_clustered_slaves() {
+        search_all::<256>(&mut Clustered {
+            base: [0x3A, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x00],
+        });
+    }
+
+    /// A pin without an external pull-up resistor, recording whether the internal pull-up
+    /// was on while the wire was released before each slot
+    struct FloatingPin {
+        low: bool,
+        pull_up: bool,
+        released: [Option<bool>; 8],
+        slots: usize,
+    }
+
+    impl OpenDrainOutput for FloatingPin {
+        type Error = core::convert::Infallible;
+
+        fn is_high(&self) -> Result<bool, Self::Error> {
+            Ok(!self.low && self.pull_up)
+        }
+
+        fn is_low(&self) -> Result<bool, Self::Error> {
+            self.is_high().map(|high| !high)
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.low = true;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.low = false;
+            Ok(())
+        }
+
+        fn set_internal_pull_up(&mut self, enabled: bool) -> Result<(), Self::Error> {
+            if !enabled && !self.low {
+                self.released[self.slots] = Some(self.pull_up);
+                self.slots += 1;
+            }
+            self.pull_up = enabled;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_internal_pull_up_between_slots() {
+        let pin = FloatingPin {
+            low: false,
+            pull_up: true,
+            released: [None; 8],
+            slots: 0,
+        };
+        let mut wire = OneWire::new(pin, false);
+        for _ in 0..8 {
+            wire.write_bit(&mut Delay, true).unwrap();
+        }
+        assert_eq!([Some(true); 8], wire.inner().released);
+        assert!(wire.inner().pull_up);
+        assert!(wire.inner().is_high().unwrap());
+    }
+
+    #[test]
+    fn test_reset_select() {
+        use crate::OneWireMaster;
+
+        let sim = Simulator::<3>::new(&mut Seeded {
+            family: 0x28,
+            state: 4,
+        });
+        let addresses = sim.borrow().addresses();
+        let device = Device {
+            address: addresses[1],
+        };
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut delay = SimDelay(&sim);
+        // the byte following the address is the function command
+        wire.reset_select_write_only(&mut delay, &device, &[0x4E])
+            .unwrap();
+        assert_eq!([None, Some(0x4E), None], sim.borrow().functions());
+        OneWireMaster::reset_select_write_only(&mut wire, &mut delay, &device, &[0x48]).unwrap();
+        assert_eq!([None, Some(0x48), None], sim.borrow().functions());
+
+        let mut busy = [0xFFu8; 1];
+        OneWireMaster::reset_select_write_read(&mut wire, &mut delay, &device, &[0x44], &mut busy)
+            .unwrap();
+        assert_eq!([0x00], busy);
+        let mut idle = [0u8; 1];
+        OneWireMaster::reset_select_read_only(&mut wire, &mut delay, &device, &mut idle).unwrap();
+        assert_eq!([0xFF], idle);
+        assert_eq!([None, Some(0xFF), None], sim.borrow().functions());
+    }
+
+    #[test]
+    fn test_reset_skip() {
+        let sim = Simulator::<3>::new(&mut Seeded {
+            family: 0x28,
+            state: 3,
+        });
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let mut delay = SimDelay(&sim);
+        wire.reset_skip_write_only(&mut delay, &[0xBE]).unwrap();
+        assert_eq!([Some(0xBE); 3], sim.borrow().functions());
+
+        // the read slots look like a function command of all ones to the devices
+        let mut idle = [0u8; 1];
+        wire.reset_skip_read_only(&mut delay, &mut idle).unwrap();
+        assert_eq!([0xFF], idle);
+        assert_eq!([Some(0xFF); 3], sim.borrow().functions());
+
+        // every thermometer holds the read slots low while converting
+        let mut busy = [0xFFu8; 1];
+        wire.reset_skip_write_read(&mut delay, &[0x44], &mut busy)
+            .unwrap();
+        assert_eq!([0x00], busy);
+        assert_eq!([Some(0x44); 3], sim.borrow().functions());
+    }
+
+    #[test]
+    fn test_read_rom() {
+        let sim = Simulator::<1>::new(&mut Seeded {
+            family: 0x28,
+            state: 7,
+        });
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let device = wire.read_rom(&mut SimDelay(&sim)).unwrap();
+        assert_eq!(sim.borrow().addresses()[0], device.address);
+
+        let sim = Simulator::<2>::new(&mut Seeded {
+            family: 0x28,
+            state: 7,
+        });
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        assert!(matches!(
+            wire.read_rom(&mut SimDelay(&sim)),
+            Err(Error::CrcMismatch(_, _))
+        ));
+
+        let sim = Simulator::<0>::new(&mut Seeded {
+            family: 0x28,
+            state: 7,
+        });
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        assert!(matches!(
+            wire.read_rom(&mut SimDelay(&sim)),
+            Err(Error::NoPresencePulse)
+        ));
+    }
+
+    const OVERDRIVE: SupportedSpeed = Capabilities {
+        eeprom: true,
+        overdrive: true,
+        alarm: false,
+        parasite: true,
+    }
+    .validated_speed(Speed::Overdrive);
+
+    #[test]
+    fn test_overdrive_falls_back() {
+        let mut wire = OneWire::new(Pin, false);
+        let result: Result<(), _> = wire.transaction_with_speed(OVERDRIVE, |wire| {
+            assert_eq!(Speed::Standard, wire.speed());
+            wire.skip(&mut Delay)?;
+            assert_eq!(Speed::Overdrive, wire.speed());
+            Err(Error::CommandFailed(0))
+        });
+        assert!(result.is_err());
+        assert_eq!(Speed::Standard, wire.speed());
+    }
+
+    #[test]
+    fn test_set_timing() {
+        let mut wire = OneWire::new(Pin, false);
+        let slow = Timing::STANDARD.with_write_low(6, 68);
+        assert_eq!(Ok(()), wire.set_timing(slow));
+        assert_eq!(
+            Err(TimingError::ReadSample(16)),
+            wire.set_timing(Timing {
+                read_sample: 13,
+                ..Timing::STANDARD
+            })
+        );
+        assert_eq!(&slow, wire.timing());
+        assert!(wire.set_overdrive_timing(Timing::STANDARD).is_err());
+        assert_eq!(&Timing::OVERDRIVE, wire.overdrive_timing());
+    }
+
+    #[test]
+    fn test_estimate_in_overdrive() {
+        let mut wire = OneWire::new(Pin, false);
+        let standard = Timing::STANDARD.estimate_us(Operation::ReadBytes(9));
+        let overdrive = Timing::OVERDRIVE.estimate_us(Operation::ReadBytes(9));
+        assert!(overdrive < standard);
+        wire.transaction_with_speed(OVERDRIVE, |wire| {
+            assert_eq!(standard, wire.estimate_us(Operation::ReadBytes(9)));
+            wire.skip(&mut Delay)?;
+            assert_eq!(overdrive, wire.estimate_us(Operation::ReadBytes(9)));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(standard, wire.estimate_us(Operation::ReadBytes(9)));
+    }
+
+    #[test]
+    fn test_strong_pull_up() {
+        /// Fails if the wire is driven low against the strong pull-up
+        #[derive(Default)]
+        struct PullUpPin {
+            strong: bool,
+            enabled: usize,
+        }
+
+        impl OpenDrainOutput for PullUpPin {
+            type Error = ();
+
+            fn is_high(&self) -> Result<bool, ()> {
+                Ok(true)
+            }
+
+            fn is_low(&self) -> Result<bool, ()> {
+                Ok(false)
+            }
+
+            fn set_low(&mut self) -> Result<(), ()> {
+                if self.strong {
+                    Err(())
+                } else {
+                    Ok(())
+                }
+            }
+
+            fn set_high(&mut self) -> Result<(), ()> {
+                Ok(())
+            }
+
+            fn set_strong_pull_up(&mut self, enabled: bool) -> Result<(), ()> {
+                self.enabled += usize::from(enabled && !self.strong);
+                self.strong = enabled;
+                Ok(())
+            }
+        }
+
+        let device = Device {
+            address: [0x28, 1, 2, 3, 4, 5, 6, 7],
+        };
+        let mut wire = OneWire::new(PullUpPin::default(), true);
+        wire.reset_select_write_only(&mut Delay, &device, &[0x44])
+            .unwrap();
+        assert!(wire.output.strong);
+        wire.reset_select_write_only(&mut Delay, &device, &[0x48])
+            .unwrap();
+        assert!(wire.output.strong);
+        wire.release_line();
+        assert!(!wire.output.strong);
+
+        let mut wire = OneWire::new(PullUpPin::default(), false);
+        wire.reset_select_write_only(&mut Delay, &device, &[0x44])
+            .unwrap();
+        assert_eq!(0, wire.output.enabled);
+    }
+
+    #[test]
+    fn test_release_line() {
+        let sim = Simulator::<0>::new(&mut Seeded {
+            family: 0x28,
+            state: 1,
+        });
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        let result: Result<(), _> = wire.transaction(|wire| {
+            wire.write_low()?;
+            Err(Error::CommandFailed(0))
+        });
+        assert!(result.is_err());
+        assert!(SimPin(&sim).is_high().unwrap());
+
+        wire.write_low().unwrap();
+        let pin = wire.release();
+        assert!(pin.is_high().unwrap());
+    }
+
+    #[test]
+    fn test_collision_detection() {
+        let write = |wire: &mut OneWire<LowPin>| {
+            wire.transaction(|wire| {
+                wire.write_bytes(&mut Delay, &[0xCC])?;
+                Ok(())
+            })
+        };
+        let mut wire = OneWire::new(LowPin, false);
+        assert!(write(&mut wire).is_ok());
+
+        wire.set_collision_detection(true);
+        assert!(matches!(write(&mut wire), Err(Error::BusContention)));
+        assert!(write(&mut wire).is_err());
+    }
+
+    #[test]
+    fn test_collision_detection_healthy_bus() {
+        let sim = Simulator::<2>::new(&mut Seeded {
+            family: 0x28,
+            state: 3,
+        });
+        let [first, _] = sim.borrow().addresses();
+        let mut wire = OneWire::new(SimPin(&sim), false);
+        wire.set_collision_detection(true);
+        let device = Device { address: first };
+        for _ in 0..2 {
+            wire.reset_select_write_only(&mut SimDelay(&sim), &device, &[0x44])
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_device_from_str() {
+        let device = Device::from_str("28:ff:64:1e:0f:21:c4:5a").unwrap();
+        assert_eq!(
+            device.address,
+            [0x28, 0xff, 0x64, 0x1e, 0x0f, 0x21, 0xc4, 0x5a]
+        );
+        assert!(Device::from_str("28:ff:64:1e:0f:21:c4").is_err());
+        assert!(Device::from_str("28:ff:64:1e:0f:21:c4:\u{e4}").is_err());
+        assert!(Device::from_str("2\u{e4}ff:64:1e:0f:21:c4:5a").is_err());
+    }
+
+    #[test]
+    fn test_device_try_new() {
+        let address = [0x28, 0xff, 0x64, 0x1e, 0x0f, 0x2d, 0x04, 0x1d];
+        let device = Device::try_new(address).unwrap();
+        assert!(device.is_valid());
+
+        let mut corrupted = address;
+        corrupted[6] ^= 0x40;
+        assert_eq!(
+            Err(InvalidCrc {
+                computed: crc8_const(&corrupted[..7]),
+                received: 0x1d,
+            }),
+            Device::try_new(corrupted)
+        );
+        assert!(!Device { address: corrupted }.is_valid());
+    }
+
+    #[test]
+    fn test_crc8() {
+        // CRC-8/MAXIM check value
+        assert_eq!(0xA1, crc8_const(b"123456789"));
+        assert!(check_crc8::<()>(b"123456789", 0xA1).is_ok());
+        assert!(matches!(
+            check_crc8::<()>(b"123456788", 0xA1),
+            Err(Error::CrcMismatch(_, 0xA1))
+        ));
+        for byte in 0..=u8::MAX {
+            assert_eq!(
+                super::crc8_bitwise(0x5A, byte),
+                compute_partial_crc8(0x5A, &[byte])
+            );
+        }
+    }
+
+    #[test]
+    fn test_crc16() {
+        // CRC-16/ARC check value 0xBB3D, inverted as transmitted
+        let crc = compute_crc16(b"123456789");
+        assert_eq!(0x44C2, crc);
+        assert!(check_crc16::<()>(b"123456789", crc.to_le_bytes()).is_ok());
+        assert!(matches!(
+            check_crc16::<()>(b"123456788", crc.to_le_bytes()),
+            Err(Error::Crc16Mismatch(_, 0x44C2))
+        ));
+    }
 }
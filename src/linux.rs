@@ -0,0 +1,290 @@
+//! Devices and DS18B20 scratchpads through the w1 subsystem of the Linux kernel (e.g. the
+//! `w1-gpio` master of a Raspberry Pi), so an application can be prototyped against the
+//! kernel master and later moved to a bit-banged bus on a MCU.
+//!
+//! The kernel runs the bus itself and only exposes what its family drivers implement: the
+//! devices found by its periodic search and, for thermometers, a scratchpad read after a
+//! conversion it triggers on every read of `w1_slave`.
+//!
+//! Thermometers are also available as [`Sensor`] through [`W1Sensor`], so code generic over
+//! sensors - like the [`Manager`](crate::manager::Manager) - runs unchanged. As the
+//! [`Sensor`] functions take a [`OneWire`] the kernel has no use for, [`KernelPin`] stands in
+//! for its pin.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::string::String;
+use std::vec::Vec;
+
+use core::fmt::Debug;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::ds18b20::{raw_to_millidegrees, ScratchpadView, TemperatureReading, DS18B20};
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::Unit;
+use crate::ADDRESS_BYTES;
+
+pub const SYSFS_DEVICES: &str = "/sys/bus/w1/devices";
+
+pub struct W1Bus {
+    root: PathBuf,
+}
+
+impl W1Bus {
+    pub fn new() -> Self {
+        Self::with_root(SYSFS_DEVICES)
+    }
+
+    /// Uses the device directories below `root` instead of [`SYSFS_DEVICES`]
+    pub fn with_root(root: impl AsRef<Path>) -> Self {
+        W1Bus {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The devices the kernel currently knows of, in no particular order
+    pub fn devices(&self) -> Result<Vec<Device>, Error<io::Error>> {
+        let mut devices = Vec::new();
+        for entry in fs::read_dir(&self.root)? {
+            if let Some(device) = entry?.file_name().to_str().and_then(device_from_name) {
+                devices.push(device);
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Reads the scratchpad into `buffer` and checks its CRC like
+    /// [`DS18B20::read_scratchpad`]. The kernel measures the temperature right before,
+    /// which blocks for the conversion time of the configured resolution.
+    pub fn read_scratchpad<'b>(
+        &self,
+        sensor: &DS18B20,
+        buffer: &'b mut [u8; 9],
+    ) -> Result<ScratchpadView<'b>, Error<io::Error>> {
+        let path = self
+            .root
+            .join(device_name(sensor.device()))
+            .join("w1_slave");
+        let content = fs::read_to_string(path)?;
+        let mut bytes = content
+            .lines()
+            .next()
+            .unwrap_or("")
+            .split_whitespace()
+            .map(|hex| u8::from_str_radix(hex, 16));
+        for byte in buffer.iter_mut() {
+            *byte = match bytes.next() {
+                Some(Ok(byte)) => byte,
+                _ => return Err(malformed()),
+            };
+        }
+        let view = ScratchpadView::new(buffer);
+        view.ensure_correct_crc()?;
+        Ok(view)
+    }
+
+    /// Like [`DS18B20::read_temperature`]
+    pub fn read_temperature(
+        &self,
+        sensor: &DS18B20,
+    ) -> Result<TemperatureReading, Error<io::Error>> {
+        let mut scratchpad = [0u8; 9];
+        Ok(self.read_scratchpad(sensor, &mut scratchpad)?.into())
+    }
+
+    /// The thermometer as [`Sensor`] read through this bus
+    pub fn sensor(&self, sensor: DS18B20) -> W1Sensor {
+        W1Sensor {
+            bus: W1Bus {
+                root: self.root.clone(),
+            },
+            sensor,
+        }
+    }
+}
+
+impl Default for W1Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Stands in for the pin of the [`OneWire`] passed to the [`Sensor`] functions of a
+/// [`W1Sensor`], which never access it. Any access fails, as the kernel runs the bus.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KernelPin;
+
+impl KernelPin {
+    fn unsupported<T>(&self) -> Result<T, io::Error> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the bus is run by the kernel",
+        ))
+    }
+}
+
+impl OpenDrainOutput for KernelPin {
+    type Error = io::Error;
+
+    fn is_high(&self) -> Result<bool, io::Error> {
+        self.unsupported()
+    }
+
+    fn is_low(&self) -> Result<bool, io::Error> {
+        self.unsupported()
+    }
+
+    fn set_low(&mut self) -> Result<(), io::Error> {
+        self.unsupported()
+    }
+
+    fn set_high(&mut self) -> Result<(), io::Error> {
+        self.unsupported()
+    }
+}
+
+/// A DS18B20 read through the kernel, see [`W1Bus::sensor`]. The kernel converts on every
+/// read, so there is nothing to wait for after [`Sensor::start_measurement`]. As the errors
+/// of the [`OneWire`] passed in can not hold an [`io::Error`], a missing device fails with
+/// [`Error::NoPresencePulse`] and any other failure to read with [`Error::Timeout`].
+pub struct W1Sensor {
+    bus: W1Bus,
+    sensor: DS18B20,
+}
+
+impl W1Sensor {
+    pub fn sensor(&self) -> &DS18B20 {
+        &self.sensor
+    }
+}
+
+impl Sensor for W1Sensor {
+    fn family_code() -> u8 {
+        crate::ds18b20::FAMILY_CODE
+    }
+
+    fn device(&self) -> &Device {
+        self.sensor.device()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Celsius
+    }
+
+    fn raw_to_milli(&self, raw: u16) -> i32 {
+        raw_to_millidegrees(raw)
+    }
+
+    fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        _wire: &mut OneWire<O>,
+        _delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        Ok(0)
+    }
+
+    #[cfg(not(feature = "no-float"))]
+    fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<f32, Error<O::Error>> {
+        self.read_measurement_raw(wire, delay)
+            .map(|raw| raw as i16 as f32 / 16_f32)
+    }
+
+    fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        _wire: &mut OneWire<O>,
+        _delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        match self.bus.read_temperature(&self.sensor) {
+            Ok(reading) => Ok(self.sensor.calibrated(reading)),
+            Err(e) => Err(sensor_error(e)),
+        }
+    }
+}
+
+fn sensor_error<E: Debug>(error: Error<io::Error>) -> Error<E> {
+    match error {
+        Error::CrcMismatch(computed, received) => Error::CrcMismatch(computed, received),
+        Error::PortError(e) if e.kind() == io::ErrorKind::NotFound => Error::NoPresencePulse,
+        _ => Error::Timeout,
+    }
+}
+
+fn malformed() -> Error<io::Error> {
+    Error::PortError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "malformed w1_slave content",
+    ))
+}
+
+/// The kernel names devices by their family code and serial number, e.g. `28-0316a2797fff`
+fn device_name(device: &Device) -> String {
+    let mut serial = [0u8; 8];
+    serial[..6].copy_from_slice(&device.address[1..7]);
+    std::format!(
+        "{:02x}-{:012x}",
+        device.family_code(),
+        u64::from_le_bytes(serial)
+    )
+}
+
+fn device_from_name(name: &str) -> Option<Device> {
+    let (family, serial) = name.split_once('-')?;
+    if family.len() != 2 || serial.len() != 12 {
+        return None;
+    }
+    let family = u8::from_str_radix(family, 16).ok()?;
+    let serial = u64::from_str_radix(serial, 16).ok()?.to_le_bytes();
+    let mut address = [0u8; ADDRESS_BYTES as usize];
+    address[0] = family;
+    address[1..7].copy_from_slice(&serial[..6]);
+    address[7] = crate::crc8_const(&address[..7]);
+    Some(Device { address })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sysfs() {
+        let root = std::env::temp_dir().join(std::format!("onewire-w1-{}", std::process::id()));
+        let slave = root.join("28-0316a2797fff");
+        fs::create_dir_all(&slave).unwrap();
+        fs::create_dir_all(root.join("w1_bus_master1")).unwrap();
+        fs::write(
+            slave.join("w1_slave"),
+            "72 01 4b 46 7f ff 0e 10 57 : crc=57 YES\n72 01 4b 46 7f ff 0e 10 57 t=23125\n",
+        )
+        .unwrap();
+
+        let bus = W1Bus::with_root(&root);
+        let devices = bus.devices().unwrap();
+        assert_eq!(1, devices.len());
+        assert_eq!("28-0316a2797fff", device_name(&devices[0]));
+        let sensor = DS18B20::new(devices[0].clone()).unwrap();
+        assert_eq!(0x0172, bus.read_temperature(&sensor).unwrap().raw());
+
+        let mut wire = OneWire::new(KernelPin, false);
+        let w1_sensor = bus.sensor(DS18B20::new(devices[0].clone()).unwrap());
+        assert_eq!(
+            23_125,
+            w1_sensor
+                .read_measurement_milli(&mut wire, &mut crate::test_support::Delay)
+                .unwrap()
+        );
+
+        fs::write(slave.join("w1_slave"), "72 01 4b\n").unwrap();
+        assert!(bus.read_temperature(&sensor).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,461 @@
+//! High level acquisition of a fixed set of sensors
+
+use hal::blocking::delay::DelayMs;
+use hal::blocking::delay::DelayUs;
+
+#[cfg(feature = "async")]
+use embedded_hal_async::delay::DelayNs;
+
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::{Quality, Reading};
+
+/// Result of a sensor in an acquisition cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The raw value measured in this cycle
+    Fresh(u16),
+    Failed,
+    /// The sensor failed too often and was skipped in this cycle
+    Quarantined,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Health {
+    consecutive_failures: u8,
+    skipped_cycles: u8,
+}
+
+/// Read statistics of a sensor, for spotting degrading probes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The cycle of the last successful read, see [`Manager::cycle`]
+    pub last_success: Option<u32>,
+    /// Reads failing with a CRC mismatch since the last successful read
+    pub consecutive_crc_failures: u16,
+    /// Reads attempted, successful or not
+    pub total_reads: u32,
+}
+
+/// Measures all sensors in cycles. Sensors failing `max_failures` times in a row are
+/// quarantined: they are skipped and only retried every `retry_after` cycles, so a single
+/// broken sensor does not slow down every cycle with its failures.
+pub struct Manager<S: Sensor, const N: usize> {
+    sensors: [S; N],
+    health: [Health; N],
+    stats: [Stats; N],
+    /// The raw value of the last successful read of each sensor
+    last: [Option<u16>; N],
+    cycle: u32,
+    /// The sensors started in the current cycle
+    due: [bool; N],
+    /// The end of the conversions of the cycle in progress, see [`Manager::tick`]
+    converting_until: Option<u32>,
+    max_failures: u8,
+    retry_after: u8,
+}
+
+impl<S: Sensor, const N: usize> Manager<S, N> {
+    pub fn new(sensors: [S; N], max_failures: u8, retry_after: u8) -> Self {
+        Manager {
+            sensors,
+            health: [Health::default(); N],
+            stats: [Stats::default(); N],
+            last: [None; N],
+            cycle: 0,
+            due: [false; N],
+            converting_until: None,
+            max_failures: max_failures.max(1),
+            retry_after,
+        }
+    }
+
+    pub fn sensors(&self) -> &[S; N] {
+        &self.sensors
+    }
+
+    pub fn is_quarantined(&self, index: usize) -> bool {
+        self.health
+            .get(index)
+            .is_some_and(|health| health.consecutive_failures >= self.max_failures)
+    }
+
+    /// The number of the next acquisition cycle, wrapping around
+    pub fn cycle(&self) -> u32 {
+        self.cycle
+    }
+
+    pub fn stats(&self, index: usize) -> Option<Stats> {
+        self.stats.get(index).copied()
+    }
+
+    /// The value of the sensor as normalized [`Reading`]: [`Quality::Fresh`] if it was read
+    /// in the last cycle, [`Quality::Quarantined`] with the last value read if the sensor is
+    /// quarantined. `None` if neither applies or it was never read successfully.
+    pub fn reading(&self, index: usize) -> Option<Reading> {
+        let sensor = self.sensors.get(index)?;
+        let raw = (*self.last.get(index)?)?;
+        let stats = self.stats.get(index)?;
+        let quality = if stats.last_success == Some(self.cycle.wrapping_sub(1)) {
+            Quality::Fresh
+        } else if self.is_quarantined(index) {
+            Quality::Quarantined
+        } else {
+            return None;
+        };
+        Some(Reading {
+            device: sensor.device().clone(),
+            raw,
+            milli: sensor.raw_to_milli(raw),
+            unit: sensor.unit(),
+            quality,
+        })
+    }
+
+    /// The statistics of all sensors, in the order of [`Manager::sensors`]
+    pub fn snapshot(&self) -> [Stats; N] {
+        self.stats
+    }
+
+    /// Lifts the quarantine of the sensor, e.g. after it has been replaced
+    pub fn reset_health(&mut self, index: usize) {
+        if let Some(health) = self.health.get_mut(index) {
+            *health = Health::default();
+        }
+    }
+
+    /// Whether the sensor is measured in the next cycle, updates the skip counter of
+    /// quarantined sensors
+    fn is_due(&mut self, index: usize) -> bool {
+        let quarantined = self.is_quarantined(index);
+        let retry_after = self.retry_after;
+        match self.health.get_mut(index) {
+            Some(health) if quarantined => {
+                if health.skipped_cycles >= retry_after {
+                    health.skipped_cycles = 0;
+                    true
+                } else {
+                    health.skipped_cycles += 1;
+                    false
+                }
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn record(&mut self, index: usize, success: bool) {
+        if let Some(health) = self.health.get_mut(index) {
+            if success {
+                *health = Health::default();
+            } else {
+                health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+            }
+        }
+    }
+
+    fn record_read<E: core::fmt::Debug>(&mut self, index: usize, result: &Result<u16, Error<E>>) {
+        let cycle = self.cycle;
+        if let Some(stats) = self.stats.get_mut(index) {
+            stats.total_reads = stats.total_reads.saturating_add(1);
+            match result {
+                Ok(raw) => {
+                    if let Some(last) = self.last.get_mut(index) {
+                        *last = Some(*raw);
+                    }
+                    stats.last_success = Some(cycle);
+                    stats.consecutive_crc_failures = 0;
+                }
+                Err(Error::CrcMismatch(..)) | Err(Error::Crc16Mismatch(..)) => {
+                    stats.consecutive_crc_failures =
+                        stats.consecutive_crc_failures.saturating_add(1);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// Starts the measurement of all due sensors, waits for the slowest one and reads
+    /// them. The status of each sensor is stored in the slot of `status` with its index.
+    pub fn acquire<O: OpenDrainOutput, D: DelayUs<u16> + DelayMs<u16>>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut D,
+        status: &mut [Status],
+    ) {
+        let wait_ms = self.start(wire, delay);
+        delay.delay_ms(wait_ms);
+        self.finish(wire, delay, status);
+    }
+
+    /// Like [`Manager::acquire`], but waits for the measurements with an async timer, so
+    /// the executor can run other tasks meanwhile
+    #[cfg(feature = "async")]
+    pub async fn acquire_async<O: OpenDrainOutput>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        timer: &mut impl DelayNs,
+        status: &mut [Status],
+    ) {
+        let wait_ms = self.start(wire, delay);
+        timer.delay_ms(u32::from(wait_ms)).await;
+        self.finish(wire, delay, status);
+    }
+
+    /// Drives the cycles from a periodic tick (e.g. a superloop or a periodic task)
+    /// instead of waiting: starts the measurements if no cycle is in progress and reads
+    /// them once `now_ms` passed the conversion time. Returns `true` if a cycle completed
+    /// and `status` was updated, see [`Manager::acquire`]. `now_ms` may wrap around.
+    pub fn tick<O: OpenDrainOutput>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        now_ms: u32,
+        status: &mut [Status],
+    ) -> bool {
+        match self.converting_until {
+            None => {
+                let wait_ms = self.start(wire, delay);
+                self.converting_until = Some(now_ms.wrapping_add(u32::from(wait_ms)));
+                false
+            }
+            // wrapping comparison, until is at most u16::MAX ms ahead
+            Some(until) if (now_ms.wrapping_sub(until) as i32) < 0 => false,
+            Some(_) => {
+                self.converting_until = None;
+                self.finish(wire, delay, status);
+                true
+            }
+        }
+    }
+
+    /// Starts the measurement of all due sensors, returns the milliseconds to wait until
+    /// all of them finished
+    fn start<O: OpenDrainOutput>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> u16 {
+        let mut due = [false; N];
+        let mut wait_ms = 0;
+        for (index, due) in due.iter_mut().enumerate() {
+            *due = self.is_due(index);
+            if !*due {
+                continue;
+            }
+            match self
+                .sensors
+                .get(index)
+                .map(|sensor| sensor.start_measurement(wire, delay))
+            {
+                Some(Ok(ms)) => wait_ms = wait_ms.max(ms),
+                _ => {
+                    *due = false;
+                    self.record(index, false);
+                }
+            }
+        }
+        self.due = due;
+        wait_ms
+    }
+
+    /// Reads the sensors started by [`Manager::start`]
+    fn finish<O: OpenDrainOutput>(
+        &mut self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+        status: &mut [Status],
+    ) {
+        let due = core::mem::replace(&mut self.due, [false; N]);
+        for (index, due) in due.iter().enumerate() {
+            let result = if *due {
+                let value = match self.sensors.get(index) {
+                    Some(sensor) => {
+                        let result = sensor.read_measurement_raw(wire, delay);
+                        self.record_read(index, &result);
+                        result.ok()
+                    }
+                    None => None,
+                };
+                self.record(index, value.is_some());
+                value.map_or(Status::Failed, Status::Fresh)
+            } else if self.is_quarantined(index) {
+                Status::Quarantined
+            } else {
+                Status::Failed
+            };
+            if let Some(status) = status.get_mut(index) {
+                *status = result;
+            }
+        }
+        self.cycle = self.cycle.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Manager, Stats, Status};
+    use crate::test_support::{Delay, Pin};
+    use crate::{Device, Error, OneWire, OpenDrainOutput, Sensor, Unit};
+    use crate::{Quality, Reading};
+    use core::cell::Cell;
+    use hal::blocking::delay::DelayUs;
+
+    struct FakeSensor {
+        device: Device,
+        broken: Cell<bool>,
+        reads: Cell<u8>,
+    }
+
+    impl Sensor for FakeSensor {
+        fn family_code() -> u8 {
+            0
+        }
+
+        fn device(&self) -> &Device {
+            &self.device
+        }
+
+        fn unit(&self) -> Unit {
+            Unit::Celsius
+        }
+
+        fn raw_to_milli(&self, raw: u16) -> i32 {
+            i32::from(raw)
+        }
+
+        fn start_measurement<O: OpenDrainOutput>(
+            &self,
+            _wire: &mut OneWire<O>,
+            _delay: &mut impl DelayUs<u16>,
+        ) -> Result<u16, Error<O::Error>> {
+            Ok(750)
+        }
+
+        #[cfg(not(feature = "no-float"))]
+        fn read_measurement<O: OpenDrainOutput>(
+            &self,
+            _wire: &mut OneWire<O>,
+            _delay: &mut impl DelayUs<u16>,
+        ) -> Result<f32, Error<O::Error>> {
+            Ok(0.0)
+        }
+
+        fn read_measurement_raw<O: OpenDrainOutput>(
+            &self,
+            _wire: &mut OneWire<O>,
+            _delay: &mut impl DelayUs<u16>,
+        ) -> Result<u16, Error<O::Error>> {
+            self.reads.set(self.reads.get() + 1);
+            if self.broken.get() {
+                Err(Error::CrcMismatch(0, 1))
+            } else {
+                Ok(0x0191)
+            }
+        }
+    }
+
+    #[test]
+    fn test_quarantine() {
+        let sensor = |broken| FakeSensor {
+            device: Device { address: [0; 8] },
+            broken: Cell::new(broken),
+            reads: Cell::new(0),
+        };
+        let mut manager = Manager::new([sensor(false), sensor(true)], 2, 3);
+        let mut wire = OneWire::new(Pin, false);
+        let mut status = [Status::Failed; 2];
+
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        assert_eq!(status, [Status::Fresh(0x0191), Status::Failed]);
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        assert_eq!(status, [Status::Fresh(0x0191), Status::Failed]);
+        for _ in 0..3 {
+            manager.acquire(&mut wire, &mut Delay, &mut status);
+            assert_eq!(status, [Status::Fresh(0x0191), Status::Quarantined]);
+        }
+        assert_eq!(manager.sensors()[1].reads.get(), 2);
+        // retried after three skipped cycles
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        assert_eq!(manager.sensors()[1].reads.get(), 3);
+
+        assert_eq!(6, manager.cycle());
+        assert_eq!(
+            manager.snapshot(),
+            [
+                Stats {
+                    last_success: Some(5),
+                    consecutive_crc_failures: 0,
+                    total_reads: 6,
+                },
+                Stats {
+                    last_success: None,
+                    consecutive_crc_failures: 3,
+                    total_reads: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tick() {
+        let sensor = FakeSensor {
+            device: Device { address: [0; 8] },
+            broken: Cell::new(false),
+            reads: Cell::new(0),
+        };
+        let mut manager = Manager::new([sensor], 2, 3);
+        let mut wire = OneWire::new(Pin, false);
+        let mut status = [Status::Failed; 1];
+
+        let start = u32::MAX - 100;
+        assert!(!manager.tick(&mut wire, &mut Delay, start, &mut status));
+        assert!(!manager.tick(&mut wire, &mut Delay, start.wrapping_add(749), &mut status));
+        assert_eq!(0, manager.sensors()[0].reads.get());
+        assert!(manager.tick(&mut wire, &mut Delay, start.wrapping_add(750), &mut status));
+        assert_eq!(status, [Status::Fresh(0x0191)]);
+        assert_eq!(1, manager.cycle());
+    }
+
+    #[test]
+    fn test_quarantined_reading() {
+        let sensor = FakeSensor {
+            device: Device { address: [0; 8] },
+            broken: Cell::new(false),
+            reads: Cell::new(0),
+        };
+        let mut manager = Manager::new([sensor], 2, 3);
+        let mut wire = OneWire::new(Pin, false);
+        let mut status = [Status::Failed; 1];
+        assert_eq!(None, manager.reading(0));
+
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        let fresh = Reading {
+            device: Device { address: [0; 8] },
+            raw: 0x0191,
+            milli: 0x0191,
+            unit: Unit::Celsius,
+            quality: Quality::Fresh,
+        };
+        assert_eq!(Some(fresh.clone()), manager.reading(0));
+
+        manager.sensors()[0].broken.set(true);
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        assert_eq!(None, manager.reading(0));
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        assert_eq!(status, [Status::Failed]);
+        assert_eq!(
+            Some(Reading {
+                quality: Quality::Quarantined,
+                ..fresh
+            }),
+            manager.reading(0)
+        );
+        manager.acquire(&mut wire, &mut Delay, &mut status);
+        assert_eq!(status, [Status::Quarantined]);
+        assert_eq!(Quality::Quarantined, manager.reading(0).unwrap().quality);
+    }
+}
@@ -0,0 +1,396 @@
+//! The protocol layer - addressing, searching and transferring bytes - on top of any master
+//! able to generate the reset pulse and the time slots. Besides bit-banging a pin with
+//! [`OneWire`], this allows bridges like the DS2482, a UART or a PIO state machine to drive
+//! the same device drivers by implementing [`OneWireMaster`].
+
+use core::fmt::Debug;
+use hal::blocking::delay::DelayUs;
+
+use crate::Command;
+use crate::Device;
+use crate::DeviceSearch;
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::ADDRESS_BITS;
+use crate::ADDRESS_BYTES;
+
+pub trait OneWireMaster {
+    type Error: Sized + Debug;
+
+    /// Performs a reset, `Ok(true)` if a device answered with a presence pulse
+    fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>>;
+
+    fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>>;
+
+    fn write_bit(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<Self::Error>>;
+
+    /// Reads a bit and its complement and writes the path to follow during a search: the bit
+    /// read if only devices with this value responded, `direction` otherwise. Returns both
+    /// bits read and the bit written. Masters like the DS2482 do this in a single command.
+    fn triplet(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        direction: bool,
+    ) -> Result<(bool, bool, bool), Error<Self::Error>> {
+        let bit0 = self.read_bit(delay)?;
+        let bit1 = self.read_bit(delay)?;
+        let written = if bit0 != bit1 { bit0 } else { direction };
+        self.write_bit(delay, written)?;
+        Ok((bit0, bit1, written))
+    }
+
+    /// Groups bus activity that must not be interleaved with other bus activity
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<Self::Error>>,
+    ) -> Result<T, Error<Self::Error>> {
+        f(self)
+    }
+
+    fn read_byte(&mut self, delay: &mut impl DelayUs<u16>) -> Result<u8, Error<Self::Error>> {
+        let mut byte = 0_u8;
+        for _ in 0..8 {
+            byte >>= 1;
+            if self.read_bit(delay)? {
+                byte |= 0x80;
+            }
+        }
+        Ok(byte)
+    }
+
+    fn write_byte(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        mut byte: u8,
+    ) -> Result<(), Error<Self::Error>> {
+        for _ in 0..8 {
+            self.write_bit(delay, (byte & 0x01) == 0x01)?;
+            byte >>= 1;
+        }
+        Ok(())
+    }
+
+    fn read_bytes(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        dst: &mut [u8],
+    ) -> Result<(), Error<Self::Error>> {
+        for d in dst {
+            *d = self.read_byte(delay)?;
+        }
+        Ok(())
+    }
+
+    fn write_bytes(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        bytes: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        for b in bytes {
+            self.write_byte(delay, *b)?;
+        }
+        Ok(())
+    }
+
+    /// Addresses all devices
+    fn skip(&mut self, delay: &mut impl DelayUs<u16>) -> Result<(), Error<Self::Error>> {
+        self.write_byte(delay, Command::SkipRom as u8)
+    }
+
+    fn select(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        device: &Device,
+    ) -> Result<(), Error<Self::Error>> {
+        self.write_byte(delay, Command::SelectRom as u8)?;
+        self.write_bytes(delay, &device.address)
+    }
+
+    fn reset_select_write_read(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        device: &Device,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.transaction(|master| {
+            master.reset(delay)?;
+            master.select(delay, device)?;
+            master.write_bytes(delay, write)?;
+            master.read_bytes(delay, read)
+        })
+    }
+
+    fn reset_select_read_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        device: &Device,
+        read: &mut [u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.reset_select_write_read(delay, device, &[], read)
+    }
+
+    fn reset_select_write_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        device: &Device,
+        write: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.reset_select_write_read(delay, device, write, &mut [])
+    }
+
+    /// See [`OneWire::reset_skip_write_read`]
+    fn reset_skip_write_read(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.transaction(|master| {
+            master.reset(delay)?;
+            master.skip(delay)?;
+            master.write_bytes(delay, write)?;
+            master.read_bytes(delay, read)
+        })
+    }
+
+    fn reset_skip_read_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        read: &mut [u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.reset_skip_write_read(delay, &[], read)
+    }
+
+    fn reset_skip_write_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        write: &[u8],
+    ) -> Result<(), Error<Self::Error>> {
+        self.reset_skip_write_read(delay, write, &mut [])
+    }
+
+    /// See [`OneWire::read_rom`]
+    fn read_rom(&mut self, delay: &mut impl DelayUs<u16>) -> Result<Device, Error<Self::Error>> {
+        self.transaction(|master| {
+            if !master.reset(delay)? {
+                return Err(Error::NoPresencePulse);
+            }
+            master.write_byte(delay, Command::ReadRom as u8)?;
+            let mut address = [0u8; ADDRESS_BYTES as usize];
+            master.read_bytes(delay, &mut address)?;
+            Device::checked(address)
+        })
+    }
+
+    fn search_next(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<Device>, Error<Self::Error>> {
+        self.transaction(|master| search_with_triplets(master, search, delay, Command::SearchNext))
+    }
+
+    fn search_next_alarmed(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<Device>, Error<Self::Error>> {
+        self.transaction(|master| {
+            search_with_triplets(master, search, delay, Command::SearchNextAlarmed)
+        })
+    }
+}
+
+fn search_with_triplets<M: OneWireMaster + ?Sized>(
+    master: &mut M,
+    rom: &mut DeviceSearch,
+    delay: &mut impl DelayUs<u16>,
+    cmd: Command,
+) -> Result<Option<Device>, Error<M::Error>> {
+    rom.progress = None;
+    let last_discrepancy = match rom.search_prepare() {
+        Some(last_discrepancy) => last_discrepancy,
+        None => return Ok(None),
+    };
+    if !master.reset(delay)? {
+        return Ok(None);
+    }
+    master.write_byte(delay, cmd as u8)?;
+    let last_discrepancy = match rom.search_continues(last_discrepancy) {
+        Some(last_discrepancy) => last_discrepancy,
+        None => return Ok(None),
+    };
+
+    let mut discrepancy_found = false;
+    for i in 0..ADDRESS_BITS {
+        let direction = rom.search_preferred_direction(i, last_discrepancy);
+        let (bit0, bit1, written) = master.triplet(delay, direction)?;
+        match rom.search_direction(i, last_discrepancy, &mut discrepancy_found, bit0, bit1) {
+            // the devices answered differently than on the previous search step
+            Some(bit) if bit != written => return Ok(None),
+            Some(_) => {}
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(rom.search_finish(discrepancy_found)))
+}
+
+/// Delegates to the bit-banging implementation, which keeps handling parasite power,
+/// overdrive speed and its slot checks
+impl<E: Debug, ODO: OpenDrainOutput<Error = E>> OneWireMaster for OneWire<ODO> {
+    type Error = E;
+
+    fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<E>> {
+        OneWire::reset(self, delay)
+    }
+
+    fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<E>> {
+        Ok(OneWire::read_bit(self, delay)?)
+    }
+
+    fn write_bit(&mut self, delay: &mut impl DelayUs<u16>, high: bool) -> Result<(), Error<E>> {
+        Ok(OneWire::write_bit(self, delay, high)?)
+    }
+
+    fn transaction<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Error<E>>,
+    ) -> Result<T, Error<E>> {
+        OneWire::transaction(self, f)
+    }
+
+    fn read_byte(&mut self, delay: &mut impl DelayUs<u16>) -> Result<u8, Error<E>> {
+        Ok(OneWire::read_byte(self, delay)?)
+    }
+
+    fn write_byte(&mut self, delay: &mut impl DelayUs<u16>, byte: u8) -> Result<(), Error<E>> {
+        Ok(OneWire::write_byte_powered(self, delay, byte, false)?)
+    }
+
+    fn read_bytes(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        dst: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        Ok(OneWire::read_bytes(self, delay, dst)?)
+    }
+
+    fn write_bytes(&mut self, delay: &mut impl DelayUs<u16>, bytes: &[u8]) -> Result<(), Error<E>> {
+        Ok(OneWire::write_bytes(self, delay, bytes)?)
+    }
+
+    fn skip(&mut self, delay: &mut impl DelayUs<u16>) -> Result<(), Error<E>> {
+        OneWire::skip(self, delay)
+    }
+
+    fn select(&mut self, delay: &mut impl DelayUs<u16>, device: &Device) -> Result<(), Error<E>> {
+        OneWire::select(self, delay, device)
+    }
+
+    fn reset_select_write_read(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        device: &Device,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        OneWire::reset_select_write_read(self, delay, device, write, read)
+    }
+
+    fn reset_skip_write_read(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        OneWire::reset_skip_write_read(self, delay, write, read)
+    }
+
+    fn reset_skip_read_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        read: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        OneWire::reset_skip_read_only(self, delay, read)
+    }
+
+    fn reset_skip_write_only(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        write: &[u8],
+    ) -> Result<(), Error<E>> {
+        OneWire::reset_skip_write_only(self, delay, write)
+    }
+
+    fn read_rom(&mut self, delay: &mut impl DelayUs<u16>) -> Result<Device, Error<E>> {
+        OneWire::read_rom(self, delay)
+    }
+
+    fn search_next(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<Device>, Error<E>> {
+        OneWire::search_next(self, search, delay)
+    }
+
+    fn search_next_alarmed(
+        &mut self,
+        search: &mut DeviceSearch,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Option<Device>, Error<E>> {
+        OneWire::search_next_alarmed(self, search, delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Seeded, SimDelay, SimPin, Simulator};
+
+    /// Only provides the slots, everything else is done by the provided methods
+    struct BitMaster<'a, const N: usize>(OneWire<SimPin<'a, N>>);
+
+    impl<'a, const N: usize> OneWireMaster for BitMaster<'a, N> {
+        type Error = core::convert::Infallible;
+
+        fn reset(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>> {
+            OneWire::reset(&mut self.0, delay)
+        }
+
+        fn read_bit(&mut self, delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Self::Error>> {
+            Ok(OneWire::read_bit(&mut self.0, delay)?)
+        }
+
+        fn write_bit(
+            &mut self,
+            delay: &mut impl DelayUs<u16>,
+            high: bool,
+        ) -> Result<(), Error<Self::Error>> {
+            Ok(OneWire::write_bit(&mut self.0, delay, high)?)
+        }
+    }
+
+    #[test]
+    fn test_search_with_triplets() {
+        let sim = Simulator::<12>::new(&mut Seeded {
+            family: 0x28,
+            state: 5,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut master = BitMaster(OneWire::new(SimPin(&sim), false));
+        let mut delay = SimDelay(&sim);
+        let mut found = 0;
+        for device in DeviceSearch::new().into_iter(&mut master, &mut delay) {
+            assert!(addresses.contains(&device.unwrap().address));
+            found += 1;
+        }
+        assert_eq!(12, found);
+    }
+}
@@ -0,0 +1,285 @@
+//! The MAX31826 is a thermometer with fixed 12 bit conversions and the location pins of the
+//! DS1825, whose family code it shares, plus 1 Kb of user EEPROM. The EEPROM is written in
+//! rows of 8 bytes through a second scratchpad: the row is written into the scratchpad, read
+//! back for verification and then copied into the EEPROM.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::check_crc8;
+use crate::family::Capabilities;
+use crate::memory::{check_range, Memory, PendingCopy};
+use crate::Device;
+use crate::Error;
+use crate::OneWire;
+use crate::OneWireMaster;
+use crate::OpenDrainOutput;
+use crate::Sensor;
+use crate::Unit;
+
+pub const FAMILY_CODE: u8 = crate::family::MAX31826;
+
+/// Conversions always have a resolution of 12 bit
+pub const CONVERSION_MS: u16 = 150;
+/// Size of the user EEPROM in bytes
+pub const EEPROM_SIZE: u16 = 128;
+/// The EEPROM is written in rows of this many bytes
+pub const ROW_SIZE: usize = 8;
+/// How long copying the second scratchpad into the EEPROM takes at most
+pub const EEPROM_WRITE_US: u16 = 25_000;
+
+/// Has to follow [`Command::CopyScratchpad2`] for the copy to start
+const COPY_KEY: u8 = 0xA5;
+
+#[repr(u8)]
+pub enum Command {
+    Convert = 0x44,
+    ReadScratchpad = 0xBE,
+    WriteScratchpad2 = 0x0F,
+    ReadScratchpad2 = 0xAA,
+    CopyScratchpad2 = 0x55,
+    ReadMemory = 0xF0,
+}
+
+/// The decoded temperature scratchpad
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scratchpad {
+    /// In sixteenths of a degree celsius
+    pub temperature_raw: u16,
+    /// The level of the address pins AD3-AD0 in the lower four bits
+    pub location: u8,
+}
+
+pub struct MAX31826 {
+    device: Device,
+}
+
+impl MAX31826 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: true,
+            overdrive: false,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<MAX31826, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE {
+            Err(Error::FamilyCodeMismatch(FAMILY_CODE, device.address[0]))
+        } else {
+            Ok(MAX31826 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reads the temperature scratchpad and checks its CRC
+    pub fn read_scratchpad<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Scratchpad, Error<O::Error>> {
+        let mut bytes = [0u8; 9];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadScratchpad as u8],
+            &mut bytes[..],
+        )?;
+        let [low, high, _, _, config, _, _, _, crc] = bytes;
+        check_crc8(&bytes[..8], crc)?;
+        Ok(Scratchpad {
+            temperature_raw: u16::from_le_bytes([low, high]),
+            location: config & 0x0F,
+        })
+    }
+
+    /// Writes a whole row into the EEPROM, `row` being the address of its first byte
+    pub fn write_row<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        row: u8,
+        bytes: &[u8; ROW_SIZE],
+    ) -> Result<(), Error<W::Error>> {
+        self.start_write_row(wire, delay, row, bytes)?
+            .wait(wire, delay)
+    }
+
+    /// Like [`MAX31826::write_row`], but returns once the copy into the EEPROM started
+    /// instead of waiting for its programming time
+    pub fn start_write_row<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        row: u8,
+        bytes: &[u8; ROW_SIZE],
+    ) -> Result<PendingCopy, Error<W::Error>> {
+        let mut command = [0u8; 2 + ROW_SIZE];
+        let [code, address, data @ ..] = &mut command;
+        *code = Command::WriteScratchpad2 as u8;
+        *address = row;
+        data.copy_from_slice(bytes);
+        wire.reset_select_write_only(delay, &self.device, &command)?;
+
+        let mut echo = [0u8; 1 + ROW_SIZE];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadScratchpad2 as u8],
+            &mut echo,
+        )?;
+        let [address, data @ ..] = echo;
+        if address != row || data != *bytes {
+            return Err(Error::CommandFailed(address));
+        }
+
+        wire.reset_select_write_only(
+            delay,
+            &self.device,
+            &[Command::CopyScratchpad2 as u8, COPY_KEY],
+        )?;
+        // the copy is not confirmed
+        Ok(PendingCopy::new(EEPROM_WRITE_US, false))
+    }
+}
+
+/// Copies as much of `data` into `row` as fits after `offset`, returns how many bytes
+fn merge_row(row: &mut [u8; ROW_SIZE], offset: usize, data: &[u8]) -> usize {
+    let mut count = 0;
+    for (byte, value) in row.iter_mut().skip(offset).zip(data) {
+        *byte = *value;
+        count += 1;
+    }
+    count
+}
+
+impl Memory for MAX31826 {
+    fn size(&self) -> u16 {
+        EEPROM_SIZE
+    }
+
+    fn read_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), EEPROM_SIZE)?;
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadMemory as u8, address as u8],
+            data,
+        )
+    }
+
+    /// Partially written rows are read first, as the EEPROM is only written in whole rows
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        check_range(address, data.len(), EEPROM_SIZE)?;
+        let mut address = address;
+        let mut data = data;
+        while !data.is_empty() {
+            let row = address & !(ROW_SIZE as u16 - 1);
+            let offset = usize::from(address - row);
+            let mut bytes = [0u8; ROW_SIZE];
+            if offset > 0 || data.len() < ROW_SIZE {
+                self.read_memory(wire, delay, row, &mut bytes)?;
+            }
+            let count = merge_row(&mut bytes, offset, data);
+            self.write_row(wire, delay, row as u8, &bytes)?;
+            address += count as u16;
+            data = data.get(count..).unwrap_or(&[]);
+        }
+        Ok(())
+    }
+}
+
+impl Sensor for MAX31826 {
+    fn family_code() -> u8 {
+        FAMILY_CODE
+    }
+
+    fn device(&self) -> &Device {
+        &self.device
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Celsius
+    }
+
+    fn raw_to_milli(&self, raw: u16) -> i32 {
+        i32::from(raw as i16) * 625 / 10
+    }
+
+    fn start_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        wire.reset_select_write_only(delay, &self.device, &[Command::Convert as u8])?;
+        Ok(CONVERSION_MS)
+    }
+
+    #[cfg(not(feature = "no-float"))]
+    fn read_measurement<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<f32, Error<O::Error>> {
+        self.read_measurement_raw(wire, delay)
+            .map(|raw| raw as i16 as f32 / 16_f32)
+    }
+
+    fn read_measurement_raw<O: OpenDrainOutput>(
+        &self,
+        wire: &mut OneWire<O>,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u16, Error<O::Error>> {
+        Ok(self.read_scratchpad(wire, delay)?.temperature_raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, Pin};
+
+    #[test]
+    fn test_merge_row() {
+        let mut row = [0xFF; ROW_SIZE];
+        assert_eq!(3, merge_row(&mut row, 5, &[1, 2, 3, 4]));
+        assert_eq!([0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3], row);
+        assert_eq!(2, merge_row(&mut row, 0, &[7, 8]));
+        assert_eq!([7, 8, 0xFF, 0xFF, 0xFF, 1, 2, 3], row);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let sensor = MAX31826::new(Device {
+            address: [FAMILY_CODE, 1, 2, 3, 4, 5, 6, 7],
+        })
+        .unwrap();
+        let mut wire = OneWire::new(Pin, false);
+        let mut data = [0u8; ROW_SIZE];
+        assert!(matches!(
+            sensor.read_memory(&mut wire, &mut Delay, EEPROM_SIZE - 4, &mut data),
+            Err(Error::OutOfRange)
+        ));
+        assert!(matches!(
+            sensor.write_memory(&mut wire, &mut Delay, u16::MAX - 4, &data),
+            Err(Error::OutOfRange)
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+use core::task::Poll;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::Error;
+use crate::OneWireMaster;
+
+/// The first byte of the alternating pattern sent after a successful copy
+const COPY_SUCCESS: u8 = 0xAA;
+
+/// Byte addressable memory of a 1-Wire device, e.g. an EEPROM
+pub trait Memory {
+    /// Size of the memory in bytes
+    fn size(&self) -> u16;
+
+    fn read_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>>;
+
+    /// Writes the data, taking care of page boundaries and programming times
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>>;
+}
+
+/// Fails with [`Error::OutOfRange`] unless the `len` bytes at `address` lie within the
+/// first `size` bytes
+#[cfg(any(
+    feature = "ds2431",
+    feature = "ds2433",
+    feature = "ds28ec20",
+    feature = "max31826"
+))]
+pub(crate) fn check_range<E: core::fmt::Debug>(
+    address: u16,
+    len: usize,
+    size: u16,
+) -> Result<(), Error<E>> {
+    match usize::from(address).checked_add(len) {
+        Some(end) if end <= usize::from(size) => Ok(()),
+        _ => Err(Error::OutOfRange),
+    }
+}
+
+/// An EEPROM programmed page by page through a scratchpad, for code generic over the
+/// memory devices
+pub trait OneWireEeprom: Memory {
+    /// The bytes programmed at once, writes not covering whole pages read the rest first
+    fn page_size(&self) -> u16;
+
+    /// How long programming one page takes at most
+    fn programming_us(&self) -> u16;
+
+    /// How long writing `len` bytes at `address` takes at most, not counting the bus
+    fn write_duration_us(&self, address: u16, len: u16) -> u32 {
+        if len == 0 {
+            return 0;
+        }
+        let page_size = self.page_size().max(1);
+        let first = address / page_size;
+        let last = address.saturating_add(len - 1) / page_size;
+        u32::from(last - first + 1) * u32::from(self.programming_us())
+    }
+}
+
+/// A copy into the EEPROM in progress, as returned by the `start_write_*` functions of the
+/// EEPROM drivers. The device must not be accessed while programming, so instead of
+/// blocking for the whole programming time the rest of it is waited for in steps of the
+/// caller's choice, e.g. to feed a watchdog in between.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingCopy {
+    remaining_us: u16,
+    /// Whether the device answers a successful copy with a confirmation
+    confirmed: bool,
+}
+
+impl PendingCopy {
+    /// A copy started right now, `confirmed` if the device answers a successful copy with
+    /// the alternating pattern starting with `0xAA`
+    pub fn new(programming_us: u16, confirmed: bool) -> Self {
+        PendingCopy {
+            remaining_us: programming_us,
+            confirmed,
+        }
+    }
+
+    /// The programming time still to wait for
+    pub fn remaining_us(&self) -> u16 {
+        self.remaining_us
+    }
+
+    /// Waits for at most `step_us` of the programming time, returns [`Poll::Pending`] while
+    /// the device is programming. Once it finished the result of the copy is checked,
+    /// failing with [`Error::CommandFailed`] if the device did not confirm it.
+    pub fn poll<W: OneWireMaster>(
+        &mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        step_us: u16,
+    ) -> Result<Poll<()>, Error<W::Error>> {
+        if self.remaining_us > 0 {
+            let step = step_us.clamp(1, self.remaining_us);
+            delay.delay_us(step);
+            self.remaining_us -= step;
+            if self.remaining_us > 0 {
+                return Ok(Poll::Pending);
+            }
+        }
+        if self.confirmed {
+            expect_copy_success(wire, delay)?;
+        }
+        Ok(Poll::Ready(()))
+    }
+
+    /// Waits for the rest of the programming time at once
+    pub fn wait<W: OneWireMaster>(
+        mut self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        let remaining_us = self.remaining_us;
+        match self.poll(wire, delay, remaining_us)? {
+            Poll::Ready(()) => Ok(()),
+            Poll::Pending => Err(Error::Timeout),
+        }
+    }
+}
+
+/// Reads the first byte of the pattern the device sends after copying the scratchpad
+pub(crate) fn expect_copy_success<W: OneWireMaster>(
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+) -> Result<(), Error<W::Error>> {
+    match wire.read_byte(delay)? {
+        COPY_SUCCESS => Ok(()),
+        other => Err(Error::CommandFailed(other)),
+    }
+}
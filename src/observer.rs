@@ -0,0 +1,252 @@
+//! Passive decoding of the traffic produced by another bus master, e.g. for protocol
+//! debugging or to build a simple sniffer. The line is only ever sampled and never driven.
+//!
+//! Pulse widths are measured by polling the line in intervals of `poll_us` microseconds,
+//! so they are only as accurate as the delay implementation and the time it takes to
+//! sample the pin.
+
+use hal::blocking::delay::DelayUs;
+
+use crate::OpenDrainOutput;
+
+/// Low pulses at least this long are a reset
+pub const RESET_MIN_US: u32 = 480;
+/// Low pulses shorter than this are a 1 in a write or read slot
+pub const SLOT_ONE_MAX_US: u32 = 15;
+/// Time after a reset pulse within which a presence pulse has to start and end
+pub const PRESENCE_WINDOW_US: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusEvent {
+    Reset { presence: bool },
+    Bit(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observed {
+    Reset { presence: bool },
+    Byte(u8),
+}
+
+pub struct Observer<P: OpenDrainOutput> {
+    pin: P,
+    poll_us: u16,
+}
+
+impl<E: core::fmt::Debug, P: OpenDrainOutput<Error = E>> Observer<P> {
+    pub fn new(pin: P, poll_us: u16) -> Self {
+        Observer {
+            pin,
+            poll_us: poll_us.max(1),
+        }
+    }
+
+    pub fn release(self) -> P {
+        self.pin
+    }
+
+    /// Polls the line until it is `low` or the timeout elapsed, returns the time waited
+    fn wait_for(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        low: bool,
+        timeout_us: u32,
+    ) -> Result<Option<u32>, E> {
+        let mut waited = 0;
+        while self.pin.is_low()? != low {
+            if waited >= timeout_us {
+                return Ok(None);
+            }
+            delay.delay_us(self.poll_us);
+            waited = waited.saturating_add(u32::from(self.poll_us));
+        }
+        Ok(Some(waited))
+    }
+
+    /// Waits up to `timeout_us` for the next reset or bit slot
+    pub fn next_event(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        timeout_us: u32,
+    ) -> Result<Option<BusEvent>, E> {
+        // do not mistake the end of a pulse for the beginning of one
+        if self.wait_for(delay, false, timeout_us)?.is_none() {
+            return Ok(None);
+        }
+        if self.wait_for(delay, true, timeout_us)?.is_none() {
+            return Ok(None);
+        }
+        let width = self.wait_for(delay, false, u32::MAX)?.unwrap_or(u32::MAX);
+
+        if width >= RESET_MIN_US {
+            let presence = match self.wait_for(delay, true, PRESENCE_WINDOW_US)? {
+                Some(waited) => self
+                    .wait_for(delay, false, PRESENCE_WINDOW_US.saturating_sub(waited))?
+                    .is_some(),
+                None => false,
+            };
+            Ok(Some(BusEvent::Reset { presence }))
+        } else {
+            Ok(Some(BusEvent::Bit(width < SLOT_ONE_MAX_US)))
+        }
+    }
+
+    /// Waits up to `timeout_us` for each bit of the next byte, a reset aborts the byte
+    pub fn next(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+        timeout_us: u32,
+    ) -> Result<Option<Observed>, E> {
+        let mut byte = 0_u8;
+        for _ in 0..8 {
+            match self.next_event(delay, timeout_us)? {
+                None => return Ok(None),
+                Some(BusEvent::Reset { presence }) => {
+                    return Ok(Some(Observed::Reset { presence }));
+                }
+                Some(BusEvent::Bit(bit)) => {
+                    byte >>= 1;
+                    if bit {
+                        byte |= 0x80;
+                    }
+                }
+            }
+        }
+        Ok(Some(Observed::Byte(byte)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    /// Traffic of another master as the durations of alternating low and high phases,
+    /// replayed against a clock advanced by [`Clock`]
+    struct Recording<'a> {
+        phases: [u32; 256],
+        len: usize,
+        now: &'a Cell<u32>,
+    }
+
+    impl<'a> Recording<'a> {
+        /// Starts with the idle line
+        fn new(now: &'a Cell<u32>) -> Self {
+            let mut recording = Recording {
+                phases: [0; 256],
+                len: 0,
+                now,
+            };
+            recording.push(0, 100);
+            recording
+        }
+
+        fn push(&mut self, low: u32, high: u32) {
+            self.phases[self.len] = low;
+            self.phases[self.len + 1] = high;
+            self.len += 2;
+        }
+
+        fn reset(&mut self, presence: bool) {
+            if presence {
+                self.push(480, 70);
+                self.push(120, 290);
+            } else {
+                self.push(480, 480);
+            }
+        }
+
+        fn byte(&mut self, byte: u8) {
+            for bit in 0..8 {
+                if byte >> bit & 1 != 0 {
+                    self.push(6, 64);
+                } else {
+                    self.push(60, 10);
+                }
+            }
+        }
+    }
+
+    impl OpenDrainOutput for Recording<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            self.is_low().map(|low| !low)
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            let mut end = 0;
+            for (index, phase) in self.phases[..self.len].iter().enumerate() {
+                end += phase;
+                if self.now.get() < end {
+                    return Ok(index & 1 == 0);
+                }
+            }
+            Ok(false)
+        }
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            unreachable!("the observer never drives the line")
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            unreachable!("the observer never drives the line")
+        }
+    }
+
+    struct Clock<'a>(&'a Cell<u32>);
+
+    impl DelayUs<u16> for Clock<'_> {
+        fn delay_us(&mut self, us: u16) {
+            self.0.set(self.0.get() + u32::from(us));
+        }
+    }
+
+    #[test]
+    fn test_decode_select() {
+        let now = Cell::new(0);
+        let address = [0x28, 0xFF, 0x64, 0x1E, 0x0F, 0x2D, 0x04, 0x1D];
+        let mut recording = Recording::new(&now);
+        recording.reset(true);
+        recording.byte(crate::Command::SelectRom as u8);
+        for byte in address {
+            recording.byte(byte);
+        }
+        recording.byte(0x44);
+        recording.reset(false);
+
+        let mut observer = Observer::new(recording, 1);
+        let mut clock = Clock(&now);
+        let mut next = || observer.next(&mut clock, 1_000).unwrap();
+        assert_eq!(Some(Observed::Reset { presence: true }), next());
+        assert_eq!(Some(Observed::Byte(0x55)), next());
+        for byte in address {
+            assert_eq!(Some(Observed::Byte(byte)), next());
+        }
+        assert_eq!(Some(Observed::Byte(0x44)), next());
+        assert_eq!(Some(Observed::Reset { presence: false }), next());
+        assert_eq!(None, next());
+    }
+
+    #[test]
+    fn test_reset_aborts_byte() {
+        let now = Cell::new(0);
+        let mut recording = Recording::new(&now);
+        recording.reset(true);
+        recording.byte(crate::Command::SkipRom as u8);
+        for _ in 0..3 {
+            recording.push(6, 64);
+        }
+        recording.reset(true);
+        recording.byte(crate::Command::SearchNext as u8);
+
+        let mut observer = Observer::new(recording, 1);
+        let mut clock = Clock(&now);
+        let mut next = || observer.next(&mut clock, 1_000).unwrap();
+        assert_eq!(Some(Observed::Reset { presence: true }), next());
+        assert_eq!(Some(Observed::Byte(0xCC)), next());
+        assert_eq!(Some(Observed::Reset { presence: true }), next());
+        assert_eq!(Some(Observed::Byte(0xF0)), next());
+    }
+}
@@ -0,0 +1,495 @@
+use core::fmt::Debug;
+use hal::digital::v2::InputPin;
+use hal::digital::v2::IoPin;
+use hal::digital::v2::OutputPin;
+use hal::digital::v2::PinState;
+use hal::digital::v2::StatefulOutputPin;
+
+use crate::OpenDrainOutput;
+
+/// Wraps a pin that is able to report its current output state and only writes to it
+/// if the requested state differs. This reduces the jitter on HALs where each write
+/// is a slow read-modify-write of the port register.
+pub struct StatefulPin<P> {
+    pin: P,
+}
+
+impl<P> StatefulPin<P> {
+    pub fn new(pin: P) -> Self {
+        StatefulPin { pin }
+    }
+
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<E: Debug, P: StatefulOutputPin<Error = E> + InputPin<Error = E>> OpenDrainOutput
+    for StatefulPin<P>
+{
+    type Error = E;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        InputPin::is_high(&self.pin)
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        InputPin::is_low(&self.pin)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        if self.pin.is_set_low()? {
+            Ok(())
+        } else {
+            self.pin.set_low()
+        }
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        if self.pin.is_set_high()? {
+            Ok(())
+        } else {
+            self.pin.set_high()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TriStateError<E: Debug> {
+    /// A previous mode switch failed and the HAL did not hand back the pin
+    PinLost,
+    PinError(E),
+}
+
+impl<E: Debug> From<E> for TriStateError<E> {
+    fn from(e: E) -> Self {
+        TriStateError::PinError(e)
+    }
+}
+
+enum Mode<I, O> {
+    Input(I),
+    Output(O),
+    Lost,
+}
+
+/// Wraps a pin that is switched between input and output mode (see [`IoPin`]) instead
+/// of emulating an open drain output. The bus is only ever driven low in output mode and
+/// is released by switching to input mode, so even pins without an open drain
+/// configuration do not fight the pull-up resistor.
+pub struct TriStatePin<I, O> {
+    mode: Mode<I, O>,
+}
+
+impl<E, I, O> TriStatePin<I, O>
+where
+    E: Debug,
+    I: InputPin<Error = E> + IoPin<I, O, Error = E>,
+    O: OutputPin<Error = E> + IoPin<I, O, Error = E>,
+{
+    pub fn new(pin: I) -> Self {
+        TriStatePin {
+            mode: Mode::Input(pin),
+        }
+    }
+
+    /// Returns the pin in input mode
+    pub fn release(self) -> Result<I, TriStateError<E>> {
+        match self.mode {
+            Mode::Input(pin) => Ok(pin),
+            Mode::Output(pin) => Ok(pin.into_input_pin()?),
+            Mode::Lost => Err(TriStateError::PinLost),
+        }
+    }
+}
+
+impl<E, I, O> OpenDrainOutput for TriStatePin<I, O>
+where
+    E: Debug,
+    I: InputPin<Error = E> + IoPin<I, O, Error = E>,
+    O: OutputPin<Error = E> + IoPin<I, O, Error = E>,
+{
+    type Error = TriStateError<E>;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        match &self.mode {
+            Mode::Input(pin) => Ok(pin.is_high()?),
+            // the bus is only driven while low
+            Mode::Output(_) => Ok(false),
+            Mode::Lost => Err(TriStateError::PinLost),
+        }
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.mode = match core::mem::replace(&mut self.mode, Mode::Lost) {
+            Mode::Input(pin) => Mode::Output(pin.into_output_pin(PinState::Low)?),
+            Mode::Output(mut pin) => {
+                pin.set_low()?;
+                Mode::Output(pin)
+            }
+            Mode::Lost => return Err(TriStateError::PinLost),
+        };
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.mode = match core::mem::replace(&mut self.mode, Mode::Lost) {
+            Mode::Input(pin) => Mode::Input(pin),
+            Mode::Output(pin) => Mode::Input(pin.into_input_pin()?),
+            Mode::Lost => return Err(TriStateError::PinLost),
+        };
+        Ok(())
+    }
+}
+
+/// Drives the bus through a transistor on one output pin and senses it on a separate input
+/// pin, e.g. for a 5V bus on a 3.3V MCU. The bus is released while the output pin is not
+/// in the state pulling it low.
+pub struct SplitPin<OUT, IN> {
+    output: OUT,
+    input: IN,
+    pull_low: PinState,
+}
+
+impl<E: Debug, OUT: OutputPin<Error = E>, IN: InputPin<Error = E>> SplitPin<OUT, IN> {
+    /// `pull_low` is the state of `output` that pulls the bus low, e.g. [`PinState::High`]
+    /// for an NPN transistor or N-channel MOSFET between the bus and ground
+    pub fn new(mut output: OUT, input: IN, pull_low: PinState) -> Result<Self, E> {
+        output.set_state(gate(pull_low, false))?;
+        Ok(SplitPin {
+            output,
+            input,
+            pull_low,
+        })
+    }
+
+    pub fn release(self) -> (OUT, IN) {
+        (self.output, self.input)
+    }
+}
+
+impl<E: Debug, OUT: OutputPin<Error = E>, IN: InputPin<Error = E>> OpenDrainOutput
+    for SplitPin<OUT, IN>
+{
+    type Error = E;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.input.is_high()
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.input.is_low()
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.output.set_state(gate(self.pull_low, true))
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.output.set_state(gate(self.pull_low, false))
+    }
+}
+
+/// Wraps a pin together with a second pin switching a strong pull-up (e.g. the gate of a
+/// MOSFET connecting the wire to VDD), which supplies parasite powered devices with enough
+/// current during conversions and EEPROM writes
+pub struct StrongPullUpPin<ODO, P> {
+    pin: ODO,
+    pull_up: P,
+    enabled: PinState,
+}
+
+impl<E: Debug, ODO: OpenDrainOutput<Error = E>, P: OutputPin<Error = E>> StrongPullUpPin<ODO, P> {
+    /// `enabled` is the state of `pull_up` that turns the strong pull-up on, e.g.
+    /// [`PinState::Low`] for the gate of a P-channel MOSFET. Both pins are moved in, so the
+    /// data pin can not double as the pull-up pin:
+    ///
+    /// ```compile_fail
+    /// # use core::convert::Infallible;
+    /// # use embedded_hal::digital::v2::{OutputPin, PinState};
+    /// # use onewire::pin::StrongPullUpPin;
+    /// # use onewire::OpenDrainOutput;
+    /// # struct Pin;
+    /// # impl OpenDrainOutput for Pin {
+    /// #     type Error = Infallible;
+    /// #     fn is_high(&self) -> Result<bool, Infallible> { Ok(true) }
+    /// #     fn is_low(&self) -> Result<bool, Infallible> { Ok(false) }
+    /// #     fn set_low(&mut self) -> Result<(), Infallible> { Ok(()) }
+    /// #     fn set_high(&mut self) -> Result<(), Infallible> { Ok(()) }
+    /// # }
+    /// # impl OutputPin for Pin {
+    /// #     type Error = Infallible;
+    /// #     fn set_low(&mut self) -> Result<(), Infallible> { Ok(()) }
+    /// #     fn set_high(&mut self) -> Result<(), Infallible> { Ok(()) }
+    /// # }
+    /// let data = Pin;
+    /// let pin = StrongPullUpPin::new(data, data, PinState::Low);
+    /// ```
+    pub fn new(pin: ODO, mut pull_up: P, enabled: PinState) -> Result<Self, E> {
+        pull_up.set_state(gate(enabled, false))?;
+        Ok(StrongPullUpPin {
+            pin,
+            pull_up,
+            enabled,
+        })
+    }
+
+    pub fn release(self) -> (ODO, P) {
+        (self.pin, self.pull_up)
+    }
+}
+
+/// The state of a pin switching a transistor on or off, given the state switching it on
+fn gate(active: PinState, on: bool) -> PinState {
+    match (active, on) {
+        (PinState::High, true) | (PinState::Low, false) => PinState::High,
+        _ => PinState::Low,
+    }
+}
+
+impl<E: Debug, ODO: OpenDrainOutput<Error = E>, P: OutputPin<Error = E>> OpenDrainOutput
+    for StrongPullUpPin<ODO, P>
+{
+    type Error = E;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.pin.is_high()
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.pin.is_low()
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.pin.begin_transaction()
+    }
+
+    fn end_transaction(&mut self) -> Result<(), Self::Error> {
+        self.pin.end_transaction()
+    }
+
+    fn set_internal_pull_up(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.pin.set_internal_pull_up(enabled)
+    }
+
+    fn set_strong_pull_up(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.pull_up.set_state(gate(self.enabled, enabled))
+    }
+
+    fn timestamp_us(&self) -> Option<u32> {
+        self.pin.timestamp_us()
+    }
+}
+
+/// Wraps a pin driving the bus through an inverting stage (e.g. a transistor pulling the
+/// bus low while the pin is high) and sensing it inverted, so both directions are flipped.
+/// The internal pull-up is never enabled, as it would pull the bus low through the stage.
+pub struct InvertedPin<P> {
+    pin: P,
+}
+
+impl<P> InvertedPin<P> {
+    pub fn new(pin: P) -> Self {
+        InvertedPin { pin }
+    }
+
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<P: OpenDrainOutput> OpenDrainOutput for InvertedPin<P> {
+    type Error = P::Error;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        self.pin.is_low()
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.pin.is_high()
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_high()
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.pin.set_low()
+    }
+
+    fn begin_transaction(&mut self) -> Result<(), Self::Error> {
+        self.pin.begin_transaction()
+    }
+
+    fn end_transaction(&mut self) -> Result<(), Self::Error> {
+        self.pin.end_transaction()
+    }
+
+    fn set_strong_pull_up(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.pin.set_strong_pull_up(enabled)
+    }
+
+    fn timestamp_us(&self) -> Option<u32> {
+        self.pin.timestamp_us()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    /// A pin behind an inverting stage: the bus is low while the pin is high or a device
+    /// holds it low, and the input of the pin reads the inverted bus. Records every level
+    /// driven on the pin and sensed by it.
+    struct Stage {
+        pin_high: bool,
+        device_low: bool,
+        driven: [Option<bool>; 4],
+        drives: usize,
+        sensed: Cell<[Option<bool>; 4]>,
+        senses: Cell<usize>,
+    }
+
+    impl Stage {
+        fn sense(&self) -> bool {
+            let level = self.pin_high || self.device_low;
+            let mut sensed = self.sensed.get();
+            sensed[self.senses.get()] = Some(level);
+            self.sensed.set(sensed);
+            self.senses.set(self.senses.get() + 1);
+            level
+        }
+
+        fn drive(&mut self, high: bool) {
+            self.pin_high = high;
+            self.driven[self.drives] = Some(high);
+            self.drives += 1;
+        }
+    }
+
+    impl OpenDrainOutput for Stage {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(self.sense())
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            Ok(!self.sense())
+        }
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.drive(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.drive(true);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_inverted() {
+        let mut pin = InvertedPin::new(Stage {
+            pin_high: false,
+            device_low: false,
+            driven: [None; 4],
+            drives: 0,
+            sensed: Cell::new([None; 4]),
+            senses: Cell::new(0),
+        });
+        // pulling the bus low drives the pin high, which the input senses as high
+        pin.set_low().unwrap();
+        assert!(pin.is_low().unwrap());
+        // releasing the bus drives the pin low and the pulled up bus reads low on the input
+        pin.set_high().unwrap();
+        assert!(pin.is_high().unwrap());
+        // a device holding the bus low while it is released
+        pin.pin.device_low = true;
+        assert!(!pin.is_high().unwrap());
+
+        let stage = pin.release();
+        assert_eq!([Some(true), Some(false), None, None], stage.driven);
+        assert_eq!(
+            [Some(true), Some(false), Some(true), None],
+            stage.sensed.get()
+        );
+    }
+
+    /// The gate of a transistor pulling the bus low while the gate is high
+    struct Gate<'a>(&'a Cell<bool>);
+
+    impl OutputPin for Gate<'_> {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    /// Senses the bus, which is low while the gate is on or a device holds it low
+    struct Sense<'a> {
+        gate: &'a Cell<bool>,
+        device_low: &'a Cell<bool>,
+    }
+
+    impl InputPin for Sense<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Infallible> {
+            Ok(!self.gate.get() && !self.device_low.get())
+        }
+
+        fn is_low(&self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let gate = Cell::new(true);
+        let device_low = Cell::new(false);
+        let sense = Sense {
+            gate: &gate,
+            device_low: &device_low,
+        };
+        let mut pin = SplitPin::new(Gate(&gate), sense, PinState::High).unwrap();
+        // the bus starts released
+        assert!(!gate.get());
+        assert!(pin.is_high().unwrap());
+
+        pin.set_low().unwrap();
+        assert!(gate.get());
+        assert!(pin.is_low().unwrap());
+
+        pin.set_high().unwrap();
+        assert!(!gate.get());
+        assert!(pin.is_high().unwrap());
+
+        // a device answering on the released bus is only seen through the input
+        device_low.set(true);
+        assert!(pin.is_low().unwrap());
+        assert!(!gate.get());
+    }
+}
@@ -0,0 +1,284 @@
+//! A 1-Wire master on a state machine of the RP2040 PIO, which generates the reset pulse
+//! and the time slots with exact timing in hardware.
+//!
+//! The state machine runs [`PROGRAM`], which executes one slot per word of its TX FIFO:
+//! it holds the wire low, releases it, samples it and waits for the recovery, then pushes
+//! the sampled bit into its RX FIFO. A word with its lowest bit cleared skips driving the
+//! wire, which samples the idle wire before a reset. It has to be set up by the application
+//! with its HAL:
+//! - the program installed, its jumps relocated to the install offset (as the HAL does)
+//!   and wrapping from [`WRAP_SOURCE`] to [`WRAP_TARGET`]
+//! - the wire as the only side-set pin (optional, pin directions) and as the input pin,
+//!   the output value of the pin set to low, so driving the pin direction pulls the wire
+//! - a clock divider for one cycle per microsecond (125 on 125 MHz)
+//! - the OSR shifting right and the ISR shifting left, without autopull and autopush
+
+use hal::blocking::delay::DelayUs;
+
+use crate::Error;
+use crate::OneWireMaster;
+use crate::Timing;
+use crate::TimingError;
+
+/// Assembled from:
+/// ```text
+/// .side_set 1 opt pindirs
+/// .wrap_target
+///     pull block
+///     out y, 1            ; whether to drive the wire low
+///     out x, 10
+///     jmp !y release
+///     nop side 1          ; drive low
+/// low:
+///     jmp x-- low
+/// release:
+///     out x, 10 side 0    ; release
+/// sample:
+///     jmp x-- sample
+///     in pins, 1
+///     out x, 10
+/// recovery:
+///     jmp x-- recovery
+///     push block
+/// .wrap
+/// ```
+pub const PROGRAM: [u16; 12] = [
+    0x80A0, 0x6041, 0x602A, 0x0066, 0xB842, 0x0045, 0x702A, 0x0047, 0x4001, 0x602A, 0x004A, 0x8020,
+];
+pub const WRAP_TARGET: u8 = 0;
+pub const WRAP_SOURCE: u8 = 11;
+pub const SIDE_SET_BITS: u8 = 1;
+
+/// When the presence pulse is sampled after the reset pulse
+const PRESENCE_SAMPLE_US: u16 = 70;
+/// Cycles the program spends on instructions around each of the waits
+const LOW_OVERHEAD: u16 = 2;
+const SAMPLE_OVERHEAD: u16 = 2;
+const RECOVERY_OVERHEAD: u16 = 7;
+const FIELD_MAX: u16 = 0x3FF;
+
+/// How often an empty RX FIFO is polled until the state machine is considered stalled
+const POLLS: u32 = 100_000;
+
+/// The FIFOs of the state machine, implemented by the TX and RX handles of the HAL
+pub trait StateMachineFifo {
+    /// Writes the word into the TX FIFO, `false` if it is full
+    fn write(&mut self, word: u32) -> bool;
+
+    /// Reads a word from the RX FIFO, `None` if it is empty
+    fn read(&mut self) -> Option<u32>;
+}
+
+#[derive(Debug)]
+pub enum PioError {
+    /// The state machine does not consume or produce words, it is presumably not running
+    Stalled,
+}
+
+pub struct PioMaster<F> {
+    fifo: F,
+    timing: Timing,
+}
+
+impl<F: StateMachineFifo> PioMaster<F> {
+    pub fn new(fifo: F) -> Self {
+        PioMaster {
+            fifo,
+            timing: Timing::STANDARD,
+        }
+    }
+
+    pub fn release(self) -> F {
+        self.fifo
+    }
+
+    /// Keeps the previous timing if the new one violates the specification, see
+    /// [`Timing::check`]
+    pub fn set_timing(&mut self, timing: Timing) -> Result<(), TimingError> {
+        timing.check()?;
+        self.timing = timing;
+        Ok(())
+    }
+
+    /// Runs a slot with the durations in microseconds, returns the sampled bit
+    fn slot(&mut self, low: u16, sample: u16, recovery: u16) -> Result<bool, Error<PioError>> {
+        self.run(encode(true, low, sample, recovery))
+    }
+
+    /// Samples the wire without driving it
+    fn sample(&mut self, sample: u16, recovery: u16) -> Result<bool, Error<PioError>> {
+        self.run(encode(false, 0, sample, recovery))
+    }
+
+    fn run(&mut self, word: u32) -> Result<bool, Error<PioError>> {
+        let mut polls = 0..POLLS;
+        while !self.fifo.write(word) {
+            polls.next().ok_or(PioError::Stalled)?;
+        }
+        loop {
+            if let Some(bit) = self.fifo.read() {
+                return Ok(bit & 1 != 0);
+            }
+            polls.next().ok_or(PioError::Stalled)?;
+        }
+    }
+}
+
+/// The word for a slot, each field counting down in the program. The wire is only driven
+/// low if `drive` is set.
+fn encode(drive: bool, low: u16, sample: u16, recovery: u16) -> u32 {
+    let field = |us: u16, overhead: u16| u32::from(us.saturating_sub(overhead).min(FIELD_MAX));
+    u32::from(drive)
+        | field(low, LOW_OVERHEAD) << 1
+        | field(sample, SAMPLE_OVERHEAD) << 11
+        | field(recovery, RECOVERY_OVERHEAD) << 21
+}
+
+impl<F: StateMachineFifo> OneWireMaster for PioMaster<F> {
+    type Error = PioError;
+
+    fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<PioError>> {
+        let timing = self.timing;
+        if !self.sample(2, 2)? {
+            // the wire is low before the reset pulse
+            return Err(Error::WireNotHigh);
+        }
+        let high = self.slot(timing.reset_low, PRESENCE_SAMPLE_US, timing.reset_recovery)?;
+        Ok(!high)
+    }
+
+    fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<PioError>> {
+        let timing = self.timing;
+        self.slot(timing.read_low, timing.read_sample, timing.read_recovery)
+    }
+
+    fn write_bit(
+        &mut self,
+        _delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<PioError>> {
+        let timing = self.timing;
+        if high {
+            self.slot(timing.write_one_low, 0, timing.write_one_recovery)?;
+        } else {
+            self.slot(timing.write_zero_low, 0, timing.write_zero_recovery)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Seeded, SimDelay, SimPin, Simulator};
+    use crate::{DeviceSearch, OpenDrainOutput};
+    use core::cell::RefCell;
+
+    /// Executes the slots like the program on a simulated bus
+    struct Fifo<'a, const N: usize> {
+        pin: SimPin<'a, N>,
+        delay: SimDelay<'a, N>,
+        rx: Option<u32>,
+        /// How often the wire was driven low
+        drives: usize,
+    }
+
+    impl<const N: usize> StateMachineFifo for Fifo<'_, N> {
+        fn write(&mut self, word: u32) -> bool {
+            let field = |shift: u32| ((word >> shift) & 0x3FF) as u16;
+            if word & 1 != 0 {
+                self.drives += 1;
+                self.pin.set_low().unwrap();
+                self.delay.delay_us(field(1) + LOW_OVERHEAD);
+                self.pin.set_high().unwrap();
+            }
+            self.delay.delay_us(field(11) + SAMPLE_OVERHEAD);
+            self.rx = Some(u32::from(self.pin.is_high().unwrap()));
+            self.delay.delay_us(field(21) + RECOVERY_OVERHEAD);
+            true
+        }
+
+        fn read(&mut self) -> Option<u32> {
+            self.rx.take()
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let sim = Simulator::<5>::new(&mut Seeded {
+            family: 0x28,
+            state: 21,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut master = PioMaster::new(Fifo {
+            pin: SimPin(&sim),
+            delay: SimDelay(&sim),
+            rx: None,
+            drives: 0,
+        });
+        let mut delay = SimDelay(&sim);
+        let mut found = 0;
+        for device in DeviceSearch::new().into_iter(&mut master, &mut delay) {
+            assert!(addresses.contains(&device.unwrap().address));
+            found += 1;
+        }
+        assert_eq!(5, found);
+    }
+
+    #[test]
+    fn test_reset_checks_idle_wire_without_driving() {
+        let sim = Simulator::<1>::new(&mut Seeded {
+            family: 0x28,
+            state: 3,
+        });
+        let mut master = PioMaster::new(Fifo {
+            pin: SimPin(&sim),
+            delay: SimDelay(&sim),
+            rx: None,
+            drives: 0,
+        });
+        assert!(master.reset(&mut SimDelay(&sim)).unwrap());
+        // only the reset pulse itself
+        assert_eq!(1, master.release().drives);
+    }
+
+    #[test]
+    fn test_set_timing() {
+        let sim = Simulator::<0>::new(&mut Seeded {
+            family: 0x28,
+            state: 1,
+        });
+        let mut master = PioMaster::new(Fifo {
+            pin: SimPin(&sim),
+            delay: SimDelay(&sim),
+            rx: None,
+            drives: 0,
+        });
+        assert!(master.set_timing(Timing::OVERDRIVE).is_err());
+        assert_eq!(Ok(()), master.set_timing(Timing::RELAXED));
+        assert_eq!(Timing::RELAXED, master.timing);
+    }
+
+    #[test]
+    fn test_stalled() {
+        struct Stopped;
+        impl StateMachineFifo for Stopped {
+            fn write(&mut self, _word: u32) -> bool {
+                false
+            }
+
+            fn read(&mut self) -> Option<u32> {
+                None
+            }
+        }
+        let sim: RefCell<Simulator<0>> = Simulator::new(&mut Seeded {
+            family: 0x28,
+            state: 1,
+        });
+        let mut master = PioMaster::new(Stopped);
+        assert!(matches!(
+            master.read_bit(&mut SimDelay(&sim)),
+            Err(Error::PortError(PioError::Stalled))
+        ));
+    }
+}
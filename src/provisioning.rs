@@ -0,0 +1,293 @@
+//! Writes and validates product data in the memory of a device that is part of the
+//! product itself (e.g. a DS2431 in a "smart cable"), so the product can be identified and
+//! its calibration restored wherever it is plugged in
+//!
+//! The stored record consists of a magic number, the length of the entries, the entries -
+//! each as tag, length and value - and an inverted CRC16 over all preceding bytes.
+
+use core::convert::{TryFrom, TryInto};
+use core::fmt::Debug;
+use hal::blocking::delay::DelayUs;
+
+use crate::memory::Memory;
+use crate::{compute_partial_crc16, CRC16_RESIDUE};
+use crate::{Error, OneWireMaster};
+
+const MAGIC: [u8; 2] = [b'O', b'P'];
+const HEADER_BYTES: usize = 4;
+const CRC_BYTES: usize = 2;
+
+/// Tag of the product id, a little endian u32
+pub const TAG_PRODUCT_ID: u8 = 0x01;
+/// Tag of the calibration constants, little endian i32s
+pub const TAG_CALIBRATION: u8 = 0x02;
+/// Tag of the manufacture date, year as little endian u16, month and day
+pub const TAG_MANUFACTURE_DATE: u8 = 0x03;
+/// Tags from here on are free for the application
+pub const TAG_APPLICATION: u8 = 0x80;
+
+#[derive(Debug)]
+pub enum ProvisioningError<E: Debug> {
+    Bus(Error<E>),
+    BufferTooSmall,
+    /// The memory does not contain a record (or it is corrupted)
+    NotFound,
+    /// An entry exceeds the record
+    Malformed,
+    /// The record read back after writing differs from the written one
+    VerifyFailed,
+}
+
+impl<E: Debug> From<Error<E>> for ProvisioningError<E> {
+    fn from(e: Error<E>) -> Self {
+        ProvisioningError::Bus(e)
+    }
+}
+
+/// Assembles the entries of a record in a buffer
+pub struct RecordBuilder<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> RecordBuilder<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        RecordBuilder {
+            buffer,
+            len: HEADER_BYTES,
+        }
+    }
+
+    /// Appends an entry, returns `false` if the buffer is too small for it and the record
+    /// trailer
+    pub fn push(&mut self, tag: u8, value: &[u8]) -> bool {
+        let value_len = match u8::try_from(value.len()) {
+            Ok(len) => len,
+            Err(_) => return false,
+        };
+        let end = self.len + 2 + value.len();
+        match self.buffer.get_mut(self.len..end + CRC_BYTES) {
+            Some(entry) => {
+                let (head, rest) = entry.split_at_mut(2);
+                head.copy_from_slice(&[tag, value_len]);
+                if let Some(dst) = rest.get_mut(..value.len()) {
+                    dst.copy_from_slice(value);
+                }
+                self.len = end;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn product_id(&mut self, id: u32) -> bool {
+        self.push(TAG_PRODUCT_ID, &id.to_le_bytes())
+    }
+
+    pub fn calibration(&mut self, constants: &[i32]) -> bool {
+        let mut value = [0u8; 4 * 8];
+        if constants.len() > 8 {
+            return false;
+        }
+        for (dst, constant) in value.chunks_exact_mut(4).zip(constants) {
+            dst.copy_from_slice(&constant.to_le_bytes());
+        }
+        self.push(
+            TAG_CALIBRATION,
+            value.get(..4 * constants.len()).unwrap_or(&[]),
+        )
+    }
+
+    pub fn manufacture_date(&mut self, year: u16, month: u8, day: u8) -> bool {
+        let [year_lo, year_hi] = year.to_le_bytes();
+        self.push(TAG_MANUFACTURE_DATE, &[year_lo, year_hi, month, day])
+    }
+
+    /// Completes the record with header and CRC, returns the bytes to store
+    fn finish(self) -> &'a [u8] {
+        let len_bytes = ((self.len - HEADER_BYTES) as u16).to_le_bytes();
+        if let Some(header) = self.buffer.get_mut(..HEADER_BYTES) {
+            header.copy_from_slice(&[MAGIC[0], MAGIC[1], len_bytes[0], len_bytes[1]]);
+        }
+        let crc = !compute_partial_crc16(0, self.buffer.get(..self.len).unwrap_or(&[]));
+        let total = self.len + CRC_BYTES;
+        if let Some(crc_bytes) = self.buffer.get_mut(self.len..total) {
+            crc_bytes.copy_from_slice(&crc.to_le_bytes());
+        }
+        self.buffer.get(..total).unwrap_or(&[])
+    }
+}
+
+/// Writes the record at `address` into the memory and reads it back to verify it, returns
+/// the number of bytes written
+pub fn provision<M: Memory, W: OneWireMaster>(
+    memory: &M,
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    address: u16,
+    record: RecordBuilder,
+) -> Result<usize, ProvisioningError<W::Error>> {
+    let record = record.finish();
+    if record.len() < HEADER_BYTES
+        || usize::from(address) + record.len() > usize::from(memory.size())
+    {
+        return Err(ProvisioningError::BufferTooSmall);
+    }
+    memory.write_memory(wire, delay, address, record)?;
+
+    let mut chunk = [0u8; 8];
+    for (offset, expected) in (address..)
+        .step_by(chunk.len())
+        .zip(record.chunks(chunk.len()))
+    {
+        let read = chunk
+            .get_mut(..expected.len())
+            .ok_or(ProvisioningError::BufferTooSmall)?;
+        memory.read_memory(wire, delay, offset, read)?;
+        if read != expected {
+            return Err(ProvisioningError::VerifyFailed);
+        }
+    }
+    Ok(record.len())
+}
+
+/// Reads and validates a record written by [`provision`] from `address`, `buffer` is used
+/// to read the entries
+pub fn read<'b, M: Memory, W: OneWireMaster>(
+    memory: &M,
+    wire: &mut W,
+    delay: &mut impl DelayUs<u16>,
+    address: u16,
+    buffer: &'b mut [u8],
+) -> Result<Record<'b>, ProvisioningError<W::Error>> {
+    let mut header = [0u8; HEADER_BYTES];
+    memory.read_memory(wire, delay, address, &mut header)?;
+    let [m0, m1, len_lo, len_hi] = header;
+    if [m0, m1] != MAGIC {
+        return Err(ProvisioningError::NotFound);
+    }
+    let len = usize::from(u16::from_le_bytes([len_lo, len_hi]));
+    if usize::from(address) + HEADER_BYTES + len + CRC_BYTES > usize::from(memory.size()) {
+        return Err(ProvisioningError::NotFound);
+    }
+    let record = buffer
+        .get_mut(..len + CRC_BYTES)
+        .ok_or(ProvisioningError::BufferTooSmall)?;
+    memory.read_memory(wire, delay, address + HEADER_BYTES as u16, record)?;
+
+    if compute_partial_crc16(compute_partial_crc16(0, &header), record) != CRC16_RESIDUE {
+        return Err(ProvisioningError::NotFound);
+    }
+    let entries = record.get(..len).ok_or(ProvisioningError::Malformed)?;
+    let record = Record { entries };
+    if record.iter().any(|entry| entry.is_none()) {
+        return Err(ProvisioningError::Malformed);
+    }
+    Ok(record)
+}
+
+/// The validated entries of a record
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a> {
+    entries: &'a [u8],
+}
+
+impl<'a> Record<'a> {
+    /// Iterates the tag and value of the entries, yields `None` and stops on a malformed entry
+    fn iter(&self) -> impl Iterator<Item = Option<(u8, &'a [u8])>> {
+        let mut rest = Some(self.entries);
+        core::iter::from_fn(move || {
+            let entries = rest?;
+            let (tag, len) = match entries {
+                [] => return None,
+                [tag, len, ..] => (*tag, usize::from(*len)),
+                [_] => {
+                    rest = None;
+                    return Some(None);
+                }
+            };
+            match entries.get(2..2 + len) {
+                Some(value) => {
+                    rest = entries.get(2 + len..);
+                    Some(Some((tag, value)))
+                }
+                None => {
+                    rest = None;
+                    Some(None)
+                }
+            }
+        })
+    }
+
+    /// Iterates the tag and value of all entries
+    pub fn entries(&self) -> impl Iterator<Item = (u8, &'a [u8])> {
+        self.iter().flatten()
+    }
+
+    /// The value of the first entry with the tag
+    pub fn get(&self, tag: u8) -> Option<&'a [u8]> {
+        self.entries()
+            .find(|(entry_tag, _)| *entry_tag == tag)
+            .map(|(_, value)| value)
+    }
+
+    pub fn product_id(&self) -> Option<u32> {
+        let value = self.get(TAG_PRODUCT_ID)?;
+        Some(u32::from_le_bytes(value.try_into().ok()?))
+    }
+
+    pub fn calibration(&self) -> impl Iterator<Item = i32> + 'a {
+        self.get(TAG_CALIBRATION)
+            .unwrap_or(&[])
+            .chunks_exact(4)
+            .filter_map(|chunk| Some(i32::from_le_bytes(chunk.try_into().ok()?)))
+    }
+
+    /// Year, month and day
+    pub fn manufacture_date(&self) -> Option<(u16, u8, u8)> {
+        match self.get(TAG_MANUFACTURE_DATE)? {
+            [year_lo, year_hi, month, day] => {
+                Some((u16::from_le_bytes([*year_lo, *year_hi]), *month, *day))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Delay, Pin, Ram};
+    use crate::OneWire;
+    use core::cell::RefCell;
+
+    #[test]
+    fn test_provision_read() {
+        let ram = Ram(RefCell::new([0xFF; 64]));
+        let mut wire = OneWire::new(Pin, false);
+        let mut buffer = [0u8; 48];
+        assert!(matches!(
+            read(&ram, &mut wire, &mut Delay, 2, &mut buffer),
+            Err(ProvisioningError::NotFound)
+        ));
+
+        let mut builder = RecordBuilder::new(&mut buffer);
+        assert!(builder.product_id(0x1234_5678));
+        assert!(builder.calibration(&[-250, 1_000_000]));
+        assert!(builder.manufacture_date(2021, 3, 14));
+        assert!(builder.push(TAG_APPLICATION, b"cable"));
+        assert!(!builder.push(TAG_APPLICATION, &[0; 40]));
+        let written = provision(&ram, &mut wire, &mut Delay, 2, builder).unwrap();
+        assert_eq!(4 + 6 + 10 + 6 + 7 + 2, written);
+
+        let mut buffer = [0u8; 48];
+        let record = read(&ram, &mut wire, &mut Delay, 2, &mut buffer).unwrap();
+        assert_eq!(Some(0x1234_5678), record.product_id());
+        assert!(record.calibration().eq([-250, 1_000_000].iter().copied()));
+        assert_eq!(Some((2021, 3, 14)), record.manufacture_date());
+        assert_eq!(Some(&b"cable"[..]), record.get(TAG_APPLICATION));
+
+        ram.0.borrow_mut()[12] ^= 0x01;
+        assert!(read(&ram, &mut wire, &mut Delay, 2, &mut buffer).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::Device;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Celsius,
+    Volt,
+    /// Relative humidity in percent
+    RelativeHumidity,
+    Count,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    /// Read successfully at the first attempt
+    Fresh,
+    /// Read successfully after the first attempt failed the CRC check
+    CrcRetried,
+    /// The device is quarantined, the value is from an earlier reading
+    Quarantined,
+}
+
+/// A measured value of any sensor in a normalized format
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    pub device: Device,
+    /// The value as reported by the device
+    pub raw: u16,
+    /// The value in thousandths of the unit
+    pub milli: i32,
+    pub unit: Unit,
+    pub quality: Quality,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LogEntry {
+    /// Timestamp in an application defined unit
+    pub tick: u32,
+    /// Index of the device in the device list of the application
+    pub device: u8,
+    pub raw: u16,
+}
+
+/// What to do when an entry is pushed into a full [`ReadingLog`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    OverwriteOldest,
+    DropNewest,
+}
+
+/// Ring buffer for up to `N` readings, e.g. to store samples between uploads
+#[derive(Debug, Clone)]
+pub struct ReadingLog<const N: usize> {
+    entries: [LogEntry; N],
+    start: usize,
+    len: usize,
+    policy: OverflowPolicy,
+    dropped: u32,
+}
+
+impl<const N: usize> ReadingLog<N> {
+    pub fn new(policy: OverflowPolicy) -> Self {
+        ReadingLog {
+            entries: [LogEntry::default(); N],
+            start: 0,
+            len: 0,
+            policy,
+            dropped: 0,
+        }
+    }
+
+    /// Returns whether the entry was stored, an entry which is overwritten or dropped
+    /// because of the [`OverflowPolicy`] is counted in [`ReadingLog::dropped`]
+    pub fn push(&mut self, tick: u32, device: u8, raw: u16) -> bool {
+        let entry = LogEntry { tick, device, raw };
+        if self.len < N {
+            if let Some(slot) = self.entries.get_mut((self.start + self.len) % N) {
+                *slot = entry;
+                self.len += 1;
+                return true;
+            }
+        } else if self.policy == OverflowPolicy::OverwriteOldest {
+            if let Some(slot) = self.entries.get_mut(self.start) {
+                *slot = entry;
+                self.start = (self.start + 1) % N;
+                self.dropped = self.dropped.saturating_add(1);
+                return true;
+            }
+        }
+        self.dropped = self.dropped.saturating_add(1);
+        false
+    }
+
+    /// Removes and returns the oldest entry
+    pub fn pop(&mut self) -> Option<LogEntry> {
+        if self.len == 0 {
+            return None;
+        }
+        let entry = *self.entries.get(self.start)?;
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        Some(entry)
+    }
+
+    /// Iterates from the oldest to the newest entry
+    pub fn iter(&self) -> impl Iterator<Item = &LogEntry> {
+        (0..self.len).filter_map(move |i| self.entries.get((self.start + i) % N))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Number of entries lost because the log was full
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.dropped = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OverflowPolicy, ReadingLog};
+
+    #[test]
+    fn test_overflow() {
+        let mut log = ReadingLog::<2>::new(OverflowPolicy::OverwriteOldest);
+        assert!(log.push(1, 0, 0x0191));
+        assert!(log.push(2, 1, 0x0192));
+        assert!(log.push(3, 0, 0x0193));
+        assert_eq!(log.iter().map(|e| e.tick).sum::<u32>(), 5);
+        assert_eq!(log.dropped(), 1);
+
+        let mut log = ReadingLog::<2>::new(OverflowPolicy::DropNewest);
+        assert!(log.push(1, 0, 0x0191));
+        assert!(log.push(2, 1, 0x0192));
+        assert!(!log.push(3, 0, 0x0193));
+        assert_eq!(log.pop().map(|e| e.tick), Some(1));
+        assert_eq!(log.pop().map(|e| e.tick), Some(2));
+        assert_eq!(log.pop(), None);
+    }
+}
@@ -0,0 +1,218 @@
+//! A 1-Wire master on the RMT peripheral of the ESP32, which generates the reset pulse and
+//! the time slots in hardware while a second channel records how the devices answered.
+//!
+//! This crate does not depend on any ESP32 HAL, so there is no ready-made backend: the
+//! application sets up the TX and RX channels with its HAL (e.g. esp-hal) on the same open
+//! drain pin, with a resolution of one tick per microsecond, and implements [`RmtChannels`]
+//! for them on top of the RMT driver of the HAL.
+
+use core::fmt::Debug;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::Error;
+use crate::OneWireMaster;
+use crate::Timing;
+use crate::TimingError;
+
+/// Low phases recorded at most in a slot, the pulse of the master and the answer of the
+/// devices
+const LOWS: usize = 4;
+
+/// Durations in microseconds: the wire is driven low and then released
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    pub low: u16,
+    pub high: u16,
+}
+
+pub trait RmtChannels {
+    type Error: Debug;
+
+    /// Sends the pulse on the TX channel while the RX channel records the wire. Writes the
+    /// duration of each phase the wire was low (starting with the pulse itself) into `lows`
+    /// and returns how many were recorded. A low phase that does not end until the pulse is
+    /// over is recorded with the time until then.
+    fn transceive(&mut self, pulse: Pulse, lows: &mut [u16]) -> Result<usize, Self::Error>;
+}
+
+pub struct RmtMaster<C> {
+    channels: C,
+    timing: Timing,
+}
+
+impl<C: RmtChannels> RmtMaster<C> {
+    pub fn new(channels: C) -> Self {
+        RmtMaster {
+            channels,
+            timing: Timing::STANDARD,
+        }
+    }
+
+    pub fn release(self) -> C {
+        self.channels
+    }
+
+    /// Keeps the previous timing if the new one violates the specification, see
+    /// [`Timing::check`]
+    pub fn set_timing(&mut self, timing: Timing) -> Result<(), TimingError> {
+        timing.check()?;
+        self.timing = timing;
+        Ok(())
+    }
+}
+
+impl<C: RmtChannels> OneWireMaster for RmtMaster<C> {
+    type Error = C::Error;
+
+    fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<C::Error>> {
+        let pulse = reset_pulse(&self.timing);
+        let mut lows = [0u16; LOWS];
+        let recorded = self.channels.transceive(pulse, &mut lows)?;
+        let lows = lows.get(..recorded).unwrap_or(&lows);
+        match lows.first() {
+            // the wire stayed low after the reset pulse
+            Some(low) if *low >= pulse.low.saturating_add(pulse.high) => Err(Error::WireNotHigh),
+            _ => Ok(lows.len() > 1),
+        }
+    }
+
+    fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<C::Error>> {
+        let timing = self.timing;
+        let pulse = Pulse {
+            low: timing.read_low,
+            high: timing.read_sample.saturating_add(timing.read_recovery),
+        };
+        let mut lows = [0u16; LOWS];
+        let recorded = self.channels.transceive(pulse, &mut lows)?;
+        // a device answering 0 stretches the pulse of the master past the sample point
+        let stretched = lows
+            .get(..recorded)
+            .and_then(<[u16]>::first)
+            .is_some_and(|low| *low > timing.read_low.saturating_add(timing.read_sample));
+        Ok(!stretched)
+    }
+
+    fn write_bit(
+        &mut self,
+        _delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<C::Error>> {
+        let timing = self.timing;
+        let pulse = if high {
+            Pulse {
+                low: timing.write_one_low,
+                high: timing.write_one_recovery,
+            }
+        } else {
+            Pulse {
+                low: timing.write_zero_low,
+                high: timing.write_zero_recovery,
+            }
+        };
+        self.channels.transceive(pulse, &mut [])?;
+        Ok(())
+    }
+}
+
+/// The reset pulse followed by the presence detection and the recovery, saturating for
+/// timings exceeding what a pulse can hold
+fn reset_pulse(timing: &Timing) -> Pulse {
+    Pulse {
+        low: timing.reset_low,
+        high: timing
+            .presence_poll
+            .saturating_mul(timing.presence_polls)
+            .saturating_add(timing.reset_recovery),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Seeded, SimDelay, SimPin, Simulator};
+    use crate::{DeviceSearch, OpenDrainOutput};
+    use core::convert::Infallible;
+
+    /// Sends the pulses on a simulated bus and records it in steps of a microsecond
+    struct Channels<'a, const N: usize> {
+        pin: SimPin<'a, N>,
+        delay: SimDelay<'a, N>,
+    }
+
+    impl<const N: usize> RmtChannels for Channels<'_, N> {
+        type Error = Infallible;
+
+        fn transceive(&mut self, pulse: Pulse, lows: &mut [u16]) -> Result<usize, Infallible> {
+            self.pin.set_low()?;
+            self.delay.delay_us(pulse.low);
+            self.pin.set_high()?;
+            let mut recorded = 0;
+            let mut low_since = Some(0);
+            for now in pulse.low..pulse.low + pulse.high {
+                match (self.pin.is_low()?, low_since) {
+                    (true, None) => low_since = Some(now),
+                    (false, Some(since)) => {
+                        if let Some(low) = lows.get_mut(recorded) {
+                            *low = now - since;
+                            recorded += 1;
+                        }
+                        low_since = None;
+                    }
+                    _ => {}
+                }
+                self.delay.delay_us(1);
+            }
+            if let (Some(since), Some(low)) = (low_since, lows.get_mut(recorded)) {
+                *low = pulse.low + pulse.high - since;
+                recorded += 1;
+            }
+            Ok(recorded)
+        }
+    }
+
+    #[test]
+    fn test_reset_pulse_saturates() {
+        let timing = Timing {
+            presence_poll: 1_000,
+            presence_polls: 100,
+            ..Timing::STANDARD
+        };
+        assert_eq!(u16::MAX, reset_pulse(&timing).high);
+    }
+
+    #[test]
+    fn test_search() {
+        let sim = Simulator::<4>::new(&mut Seeded {
+            family: 0x28,
+            state: 17,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut master = RmtMaster::new(Channels {
+            pin: SimPin(&sim),
+            delay: SimDelay(&sim),
+        });
+        let mut delay = SimDelay(&sim);
+        let mut found = 0;
+        for device in DeviceSearch::new().into_iter(&mut master, &mut delay) {
+            assert!(addresses.contains(&device.unwrap().address));
+            found += 1;
+        }
+        assert_eq!(4, found);
+    }
+
+    #[test]
+    fn test_set_timing() {
+        let sim = Simulator::<0>::new(&mut Seeded {
+            family: 0x28,
+            state: 1,
+        });
+        let mut master = RmtMaster::new(Channels {
+            pin: SimPin(&sim),
+            delay: SimDelay(&sim),
+        });
+        assert!(master.set_timing(Timing::OVERDRIVE).is_err());
+        assert_eq!(Ok(()), master.set_timing(Timing::RELAXED));
+        assert_eq!(Timing::RELAXED, master.timing);
+    }
+}
@@ -0,0 +1,465 @@
+//! Real-time clocks counting seconds in a 32 bit counter, the DS2417 and the DS2404. By
+//! convention the counter holds the Unix time, see [`DateTime`] for the conversion to the
+//! calendar. The DS2404 additionally has an interval timer (counting while its AUTO/MAN
+//! input is active), a power cycle counter and alarms for all of them, which are accessed
+//! through its scratchpad like its 512 bytes of SRAM.
+
+use core::convert::Infallible;
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family::Capabilities;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+
+pub const FAMILY_CODE_DS2417: u8 = crate::family::DS2417;
+pub const FAMILY_CODE_DS2404: u8 = crate::family::DS2404;
+
+/// Runs the oscillator of the DS2417, both bits have to be set
+const DS2417_OSCILLATOR: u8 = 0x0C;
+const DS2417_INTERVAL_SHIFT: u8 = 4;
+const DS2417_INTERRUPT: u8 = 0x80;
+
+/// The addresses of the registers of the DS2404, the counters are preceded by a byte
+/// counting 1/256 seconds
+const DS2404_CONTROL: u16 = 0x0201;
+const DS2404_CLOCK: u16 = 0x0202;
+const DS2404_INTERVAL_TIMER: u16 = 0x0207;
+const DS2404_CYCLE_COUNTER: u16 = 0x020C;
+const DS2404_CLOCK_ALARM: u16 = 0x0210;
+const DS2404_INTERVAL_ALARM: u16 = 0x0215;
+const DS2404_OSCILLATOR: u8 = 0x10;
+
+#[repr(u8)]
+pub enum Command {
+    /// DS2417 only
+    ReadClock = 0x66,
+    /// DS2417 only
+    WriteClock = 0x99,
+    WriteScratchpad = 0x0F,
+    ReadScratchpad = 0xAA,
+    CopyScratchpad = 0x55,
+    ReadMemory = 0xF0,
+}
+
+/// The period of the interrupt of the DS2417, which pulls the wire low for about a second
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interval {
+    Second1 = 0,
+    Seconds4 = 1,
+    Seconds32 = 2,
+    Seconds64 = 3,
+    Seconds2048 = 4,
+    Seconds4096 = 5,
+    Seconds65536 = 6,
+    Seconds131072 = 7,
+}
+
+impl Interval {
+    fn from_bits(bits: u8) -> Interval {
+        match bits & 0x07 {
+            0 => Interval::Second1,
+            1 => Interval::Seconds4,
+            2 => Interval::Seconds32,
+            3 => Interval::Seconds64,
+            4 => Interval::Seconds2048,
+            5 => Interval::Seconds4096,
+            6 => Interval::Seconds65536,
+            _ => Interval::Seconds131072,
+        }
+    }
+}
+
+/// The state of the DS2417
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    pub seconds: u32,
+    pub running: bool,
+    /// The period of the interrupt if it is enabled
+    pub interrupt: Option<Interval>,
+}
+
+impl Clock {
+    fn from_bytes(bytes: [u8; 5]) -> Clock {
+        let [control, seconds @ ..] = bytes;
+        Clock {
+            seconds: u32::from_le_bytes(seconds),
+            running: control & DS2417_OSCILLATOR == DS2417_OSCILLATOR,
+            interrupt: if control & DS2417_INTERRUPT != 0 {
+                Some(Interval::from_bits(control >> DS2417_INTERVAL_SHIFT))
+            } else {
+                None
+            },
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 5] {
+        let mut control = if self.running { DS2417_OSCILLATOR } else { 0 };
+        if let Some(interval) = self.interrupt {
+            control |= DS2417_INTERRUPT | (interval as u8) << DS2417_INTERVAL_SHIFT;
+        }
+        let [s0, s1, s2, s3] = self.seconds.to_le_bytes();
+        [control, s0, s1, s2, s3]
+    }
+}
+
+pub struct DS2417 {
+    device: Device,
+}
+
+impl DS2417 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: false,
+            alarm: false,
+            parasite: true,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2417, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE_DS2417 {
+            Err(Error::FamilyCodeMismatch(
+                FAMILY_CODE_DS2417,
+                device.address[0],
+            ))
+        } else {
+            Ok(DS2417 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn read_clock<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Clock, Error<W::Error>> {
+        let mut bytes = [0u8; 5];
+        wire.reset_select_write_read(delay, &self.device, &[Command::ReadClock as u8], &mut bytes)?;
+        Ok(Clock::from_bytes(bytes))
+    }
+
+    pub fn write_clock<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        clock: &Clock,
+    ) -> Result<(), Error<W::Error>> {
+        let [control, s0, s1, s2, s3] = clock.to_bytes();
+        wire.reset_select_write_only(
+            delay,
+            &self.device,
+            &[Command::WriteClock as u8, control, s0, s1, s2, s3],
+        )
+    }
+
+    /// Sets the seconds and starts the clock, keeping the interrupt as it is
+    pub fn set_seconds<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        seconds: u32,
+    ) -> Result<(), Error<W::Error>> {
+        let clock = self.read_clock(wire, delay)?;
+        self.write_clock(
+            wire,
+            delay,
+            &Clock {
+                seconds,
+                running: true,
+                ..clock
+            },
+        )
+    }
+}
+
+pub struct DS2404 {
+    device: Device,
+}
+
+impl DS2404 {
+    pub const fn capabilities() -> Capabilities {
+        Capabilities {
+            eeprom: false,
+            overdrive: true,
+            alarm: true,
+            parasite: false,
+        }
+    }
+
+    pub fn new(device: Device) -> Result<DS2404, Error<Infallible>> {
+        if device.address[0] != FAMILY_CODE_DS2404 {
+            Err(Error::FamilyCodeMismatch(
+                FAMILY_CODE_DS2404,
+                device.address[0],
+            ))
+        } else {
+            Ok(DS2404 { device })
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reads the SRAM or registers starting at `address`
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        let [low, high] = address.to_le_bytes();
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadMemory as u8, low, high],
+            data,
+        )
+    }
+
+    /// Writes the data through the scratchpad, which must not cross a 32 byte page. Fails
+    /// with [`Error::CommandFailed`] if the scratchpad did not receive the data.
+    pub fn write<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        let [low, high] = address.to_le_bytes();
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, &self.device)?;
+            wire.write_bytes(delay, &[Command::WriteScratchpad as u8, low, high])?;
+            wire.write_bytes(delay, data)
+        })?;
+
+        let mut header = [0u8; 3];
+        wire.reset_select_write_read(
+            delay,
+            &self.device,
+            &[Command::ReadScratchpad as u8],
+            &mut header,
+        )?;
+        let [target_low, target_high, es] = header;
+        let end = (address as u8)
+            .wrapping_add(data.len() as u8)
+            .wrapping_sub(1)
+            & 0x1F;
+        if [target_low, target_high] != [low, high] || es & 0x1F != end {
+            return Err(Error::CommandFailed(es));
+        }
+        wire.reset_select_write_only(
+            delay,
+            &self.device,
+            &[Command::CopyScratchpad as u8, low, high, es],
+        )
+    }
+
+    /// Starts or stops the oscillator driving all counters
+    pub fn set_running<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        running: bool,
+    ) -> Result<(), Error<W::Error>> {
+        let mut control = [0u8; 1];
+        self.read(wire, delay, DS2404_CONTROL, &mut control)?;
+        let [control] = control;
+        let control = if running {
+            control | DS2404_OSCILLATOR
+        } else {
+            control & !DS2404_OSCILLATOR
+        };
+        self.write(wire, delay, DS2404_CONTROL, &[control])
+    }
+
+    /// The seconds of the real-time clock
+    pub fn read_seconds<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u32, Error<W::Error>> {
+        self.read_counter(wire, delay, DS2404_CLOCK)
+    }
+
+    /// Sets the seconds of the real-time clock, clearing the fraction
+    pub fn set_seconds<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        seconds: u32,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_counter(wire, delay, DS2404_CLOCK, seconds)
+    }
+
+    /// The seconds the interval timer counted
+    pub fn read_interval_timer<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u32, Error<W::Error>> {
+        self.read_counter(wire, delay, DS2404_INTERVAL_TIMER)
+    }
+
+    pub fn reset_interval_timer<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_counter(wire, delay, DS2404_INTERVAL_TIMER, 0)
+    }
+
+    /// How often the device was powered up
+    pub fn read_cycle_counter<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<u32, Error<W::Error>> {
+        let mut bytes = [0u8; 4];
+        self.read(wire, delay, DS2404_CYCLE_COUNTER, &mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Sets the real-time clock alarm to the seconds
+    pub fn set_clock_alarm<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        seconds: u32,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_counter(wire, delay, DS2404_CLOCK_ALARM, seconds)
+    }
+
+    /// Sets the interval timer alarm to the seconds
+    pub fn set_interval_alarm<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        seconds: u32,
+    ) -> Result<(), Error<W::Error>> {
+        self.write_counter(wire, delay, DS2404_INTERVAL_ALARM, seconds)
+    }
+
+    fn read_counter<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+    ) -> Result<u32, Error<W::Error>> {
+        let mut bytes = [0u8; 5];
+        self.read(wire, delay, address, &mut bytes)?;
+        let [_fraction, seconds @ ..] = bytes;
+        Ok(u32::from_le_bytes(seconds))
+    }
+
+    fn write_counter<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        seconds: u32,
+    ) -> Result<(), Error<W::Error>> {
+        let [s0, s1, s2, s3] = seconds.to_le_bytes();
+        self.write(wire, delay, address, &[0, s0, s1, s2, s3])
+    }
+}
+
+/// A point in time in UTC, for the seconds of the clocks counting the Unix time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    /// `1` to `12`
+    pub month: u8,
+    /// `1` to `31`
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    pub fn from_unix(seconds: u32) -> DateTime {
+        let days = seconds / 86_400;
+        let time = seconds % 86_400;
+        // the civil date from the days since 1970-01-01, with years starting in March
+        let days = days + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days % 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + u32::from(month <= 2);
+        DateTime {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (time / 3600) as u8,
+            minute: (time / 60 % 60) as u8,
+            second: (time % 60) as u8,
+        }
+    }
+
+    /// The Unix time, `None` if it does not fit the 32 bit counter (or is before 1970)
+    pub fn to_unix(&self) -> Option<u32> {
+        let month = u32::from(self.month);
+        let year = u32::from(self.year).checked_sub(u32::from(month <= 2))?;
+        let era = year / 400;
+        let year_of_era = year % 400;
+        let month = if month > 2 { month - 3 } else { month + 9 };
+        let day_of_year = ((153 * month + 2) / 5 + u32::from(self.day)).checked_sub(1)?;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+        let time =
+            u32::from(self.hour) * 3600 + u32::from(self.minute) * 60 + u32::from(self.second);
+        days.checked_mul(86_400)?.checked_add(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_time() {
+        let date_time = DateTime {
+            year: 2023,
+            month: 11,
+            day: 14,
+            hour: 22,
+            minute: 13,
+            second: 20,
+        };
+        assert_eq!(date_time, DateTime::from_unix(1_700_000_000));
+        assert_eq!(Some(1_700_000_000), date_time.to_unix());
+        for seconds in [0, 68_169_600, 951_782_400, 4_294_967_295] {
+            assert_eq!(Some(seconds), DateTime::from_unix(seconds).to_unix());
+        }
+        assert_eq!(
+            None,
+            DateTime {
+                year: 1969,
+                ..date_time
+            }
+            .to_unix()
+        );
+    }
+
+    #[test]
+    fn test_clock() {
+        let clock = Clock::from_bytes([0x8C | 2 << 4, 0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(0x1234_5678, clock.seconds);
+        assert!(clock.running);
+        assert_eq!(Some(Interval::Seconds32), clock.interrupt);
+        assert_eq!([0x8C | 2 << 4, 0x78, 0x56, 0x34, 0x12], clock.to_bytes());
+    }
+}
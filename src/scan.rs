@@ -0,0 +1,286 @@
+//! Summary of all devices on a bus, e.g. for printing at boot or for health endpoints
+
+use core::fmt::{Debug, Display, Formatter};
+
+use hal::blocking::delay::DelayUs;
+
+use crate::family;
+use crate::labels::Labels;
+use crate::Device;
+use crate::DeviceSearch;
+use crate::Error;
+use crate::OneWire;
+use crate::OpenDrainOutput;
+use crate::Operation;
+
+/// Upper bound of search steps of a scan, a noisy bus could otherwise keep the search
+/// from ever finishing
+pub const MAX_SEARCH_STEPS: usize = 255;
+
+/// Result of [`OneWire::scan_report`]. Stores up to `N` devices, further devices are
+/// counted but dropped.
+#[derive(Debug, Clone)]
+pub struct ScanReport<const N: usize> {
+    devices: [Option<Device>; N],
+    found: usize,
+    crc_failures: usize,
+    search_steps: usize,
+    duration_estimate_us: u32,
+    wire_not_high: bool,
+    no_presence: bool,
+    aborted: bool,
+}
+
+impl<const N: usize> ScanReport<N> {
+    const NONE: Option<Device> = None;
+
+    fn empty() -> Self {
+        ScanReport {
+            devices: [Self::NONE; N],
+            found: 0,
+            crc_failures: 0,
+            search_steps: 0,
+            duration_estimate_us: 0,
+            wire_not_high: false,
+            no_presence: false,
+            aborted: false,
+        }
+    }
+
+    /// The stored devices in the order they were found
+    pub fn devices(&self) -> impl Iterator<Item = &Device> + '_ {
+        self.devices.iter().flatten()
+    }
+
+    /// The number of devices with a valid address found, including the ones that did not
+    /// fit into the report
+    pub fn found(&self) -> usize {
+        self.found
+    }
+
+    /// Whether more devices were found than the report can store
+    pub fn overflowed(&self) -> bool {
+        self.found > N
+    }
+
+    /// The distinct family codes of the stored devices with the number of devices of each
+    pub fn families(&self) -> impl Iterator<Item = (u8, usize)> + '_ {
+        self.devices()
+            .enumerate()
+            .filter(move |(index, device)| {
+                !self
+                    .devices()
+                    .take(*index)
+                    .any(|other| other.family_code() == device.family_code())
+            })
+            .map(move |(_, device)| {
+                let family_code = device.family_code();
+                let count = self
+                    .devices()
+                    .filter(|other| other.family_code() == family_code)
+                    .count();
+                (family_code, count)
+            })
+    }
+
+    /// The number of addresses with an invalid CRC read during the search
+    pub fn crc_failures(&self) -> usize {
+        self.crc_failures
+    }
+
+    pub fn search_steps(&self) -> usize {
+        self.search_steps
+    }
+
+    /// Estimated bus time of the scan in microseconds with the active timing
+    pub fn duration_estimate_us(&self) -> u32 {
+        self.duration_estimate_us
+    }
+
+    /// The wire did not go high, it is shorted or the pull-up resistor is missing
+    pub fn wire_not_high(&self) -> bool {
+        self.wire_not_high
+    }
+
+    /// No device answered the reset with a presence pulse
+    pub fn no_presence(&self) -> bool {
+        self.no_presence
+    }
+
+    /// The search did not finish within [`MAX_SEARCH_STEPS`]
+    pub fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Whether the scan finished without any anomaly
+    pub fn is_healthy(&self) -> bool {
+        !self.wire_not_high
+            && !self.no_presence
+            && !self.aborted
+            && !self.overflowed()
+            && self.crc_failures == 0
+    }
+}
+
+impl<const N: usize> ScanReport<N> {
+    /// Prints the report with the names of the devices in `labels`
+    pub fn labeled<'a, const M: usize>(&'a self, labels: &'a Labels<M>) -> LabeledReport<'a, N, M> {
+        LabeledReport {
+            report: self,
+            labels,
+        }
+    }
+
+    fn write_summary(
+        &self,
+        f: &mut Formatter<'_>,
+        name: impl Fn(&Device) -> Option<&'static str>,
+    ) -> core::fmt::Result {
+        write!(
+            f,
+            "{} device(s), {} CRC failure(s), {} search step(s), ~{}us",
+            self.found, self.crc_failures, self.search_steps, self.duration_estimate_us
+        )?;
+        if self.wire_not_high {
+            write!(f, ", wire not high")?;
+        }
+        if self.no_presence {
+            write!(f, ", no presence pulse")?;
+        }
+        if self.aborted {
+            write!(f, ", search aborted")?;
+        }
+        if self.overflowed() {
+            write!(f, ", {} device(s) not listed", self.found - N)?;
+        }
+        for device in self.devices() {
+            match name(device) {
+                Some(name) => write!(f, "\n  {} ({})", name, device)?,
+                None => write!(f, "\n  {}", device)?,
+            }
+            if let Some(name) = family::name(device.family_code()) {
+                write!(f, " {}", name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Display for ScanReport<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.write_summary(f, |_| None)
+    }
+}
+
+/// Result of [`ScanReport::labeled`]
+pub struct LabeledReport<'a, const N: usize, const M: usize> {
+    report: &'a ScanReport<N>,
+    labels: &'a Labels<M>,
+}
+
+impl<const N: usize, const M: usize> Display for LabeledReport<'_, N, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.report
+            .write_summary(f, |device| self.labels.name(device))
+    }
+}
+
+impl<E: Debug, ODO: OpenDrainOutput<Error = E>> OneWire<ODO> {
+    /// Searches the whole bus and summarizes the result. A wire that is not high or a
+    /// missing presence pulse are reported as flags instead of errors, only errors of the
+    /// pin itself are returned.
+    pub fn scan_report<const N: usize>(
+        &mut self,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<ScanReport<N>, E> {
+        let mut report = ScanReport::empty();
+        let step_us = self.estimate_us(Operation::SearchStep);
+
+        match self.transaction(|wire| wire.reset(delay)) {
+            Ok(true) => {}
+            Ok(false) => {
+                report.no_presence = true;
+                return Ok(report);
+            }
+            Err(Error::WireNotHigh) => {
+                report.wire_not_high = true;
+                return Ok(report);
+            }
+            Err(Error::PortError(e)) => return Err(e),
+            Err(_) => return Ok(report),
+        }
+
+        let mut search = DeviceSearch::new();
+        loop {
+            if report.search_steps >= MAX_SEARCH_STEPS {
+                report.aborted = true;
+                break;
+            }
+            report.search_steps += 1;
+            report.duration_estimate_us = report.duration_estimate_us.saturating_add(step_us);
+
+            let device = match self.search_next(&mut search, delay) {
+                Ok(Some(device)) => device,
+                Ok(None) => break,
+                Err(Error::WireNotHigh) => {
+                    report.wire_not_high = true;
+                    break;
+                }
+                Err(Error::PortError(e)) => return Err(e),
+                Err(_) => break,
+            };
+
+            if !device.is_valid() {
+                report.crc_failures += 1;
+                continue;
+            }
+            if let Some(slot) = report.devices.get_mut(report.found) {
+                *slot = Some(device);
+            }
+            report.found += 1;
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(address: [u8; 8]) -> Device {
+        Device { address }
+    }
+
+    #[test]
+    fn test_families() {
+        let mut report = ScanReport::<4>::empty();
+        report.devices = [
+            Some(device([0x28, 1, 0, 0, 0, 0, 0, 0])),
+            Some(device([0x3A, 2, 0, 0, 0, 0, 0, 0])),
+            Some(device([0x28, 3, 0, 0, 0, 0, 0, 0])),
+            None,
+        ];
+        report.found = 3;
+
+        let mut families = report.families();
+        assert_eq!(Some((0x28, 2)), families.next());
+        assert_eq!(Some((0x3A, 1)), families.next());
+        assert_eq!(None, families.next());
+        drop(families);
+        assert!(report.is_healthy());
+
+        report.found = 5;
+        assert!(report.overflowed());
+        assert!(!report.is_healthy());
+    }
+
+    #[test]
+    fn test_no_presence() {
+        let mut wire = OneWire::new(crate::test_support::Pin, false);
+        let report = wire
+            .scan_report::<4>(&mut crate::test_support::Delay)
+            .unwrap();
+        assert!(report.no_presence());
+        assert_eq!(0, report.found());
+    }
+}
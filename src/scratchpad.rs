@@ -0,0 +1,196 @@
+//! The scratchpad protocol of the EEPROMs of the DS2431 family (DS2431, DS2432, DS2433,
+//! DS28EC20): a block is written into the scratchpad, read back together with the target
+//! address and the E/S byte that authorizes the copy, and then copied into the EEPROM. The
+//! devices differ in the size of the scratchpad and the programming time, and the DS2432
+//! additionally requires a MAC for the copy.
+
+// the DS2432 copies the scratchpad itself, leaving the plain copy unused without the others
+#![cfg_attr(
+    not(any(feature = "ds2431", feature = "ds2433", feature = "ds28ec20")),
+    allow(dead_code)
+)]
+
+use hal::blocking::delay::DelayUs;
+
+use crate::memory::PendingCopy;
+use crate::Device;
+use crate::Error;
+use crate::OneWireMaster;
+use crate::{compute_partial_crc16, ensure_correct_crc16};
+
+#[repr(u8)]
+pub enum Command {
+    WriteScratchpad = 0x0F,
+    ReadScratchpad = 0xAA,
+    CopyScratchpad = 0x55,
+    ReadMemory = 0xF0,
+}
+
+/// What Read Scratchpad answers
+pub(crate) struct Scratchpad<const N: usize> {
+    /// The address the scratchpad is to be copied to
+    pub target: u16,
+    /// The ending offset, partial and copied flags
+    pub es: u8,
+    pub data: [u8; N],
+}
+
+/// An EEPROM with a scratchpad of `N` bytes
+pub(crate) struct ScratchpadEeprom<'a, const N: usize> {
+    pub device: &'a Device,
+    /// How long copying the scratchpad into the EEPROM takes at most
+    pub programming_us: u16,
+}
+
+impl<const N: usize> ScratchpadEeprom<'_, N> {
+    pub fn read<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        let [low, high] = address.to_le_bytes();
+        wire.reset_select_write_read(
+            delay,
+            self.device,
+            &[Command::ReadMemory as u8, low, high],
+            data,
+        )
+    }
+
+    /// Writes the block into the scratchpad, to be copied to `address`
+    pub fn write_scratchpad<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        block: &[u8; N],
+    ) -> Result<(), Error<W::Error>> {
+        let [low, high] = address.to_le_bytes();
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, self.device)?;
+            let command = [Command::WriteScratchpad as u8, low, high];
+            wire.write_bytes(delay, &command)?;
+            wire.write_bytes(delay, block)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(compute_partial_crc16(0, &command), block);
+            ensure_correct_crc16(computed, crc)
+        })
+    }
+
+    /// Reads the target address, the E/S byte and the scratchpad
+    pub fn read_scratchpad<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+    ) -> Result<Scratchpad<N>, Error<W::Error>> {
+        wire.transaction(|wire| {
+            wire.reset(delay)?;
+            wire.select(delay, self.device)?;
+            let command = [Command::ReadScratchpad as u8];
+            wire.write_bytes(delay, &command)?;
+            let mut header = [0u8; 3];
+            wire.read_bytes(delay, &mut header)?;
+            let mut scratchpad = [0u8; N];
+            wire.read_bytes(delay, &mut scratchpad)?;
+            let mut crc = [0u8; 2];
+            wire.read_bytes(delay, &mut crc)?;
+            let computed = compute_partial_crc16(0, &command);
+            let computed =
+                compute_partial_crc16(compute_partial_crc16(computed, &header), &scratchpad);
+            ensure_correct_crc16(computed, crc)?;
+            let [low, high, es] = header;
+            Ok(Scratchpad {
+                target: u16::from_le_bytes([low, high]),
+                es,
+                data: scratchpad,
+            })
+        })
+    }
+
+    /// Writes the block into the scratchpad and reads it back, returning the E/S byte that
+    /// authorizes the copy. Fails with [`Error::CommandFailed`] if the scratchpad did not
+    /// receive the block.
+    pub fn prepare_block<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        block: &[u8; N],
+    ) -> Result<u8, Error<W::Error>> {
+        debug_assert!(usize::from(address) & (N - 1) == 0);
+        self.write_scratchpad(wire, delay, address, block)?;
+        let scratchpad = self.read_scratchpad(wire, delay)?;
+        // the ending offset of a completely written block, without the partial or copied flag
+        let full_block = (N - 1) as u8;
+        if scratchpad.target != address || scratchpad.es != full_block || scratchpad.data != *block
+        {
+            return Err(Error::CommandFailed(scratchpad.es));
+        }
+        Ok(scratchpad.es)
+    }
+
+    /// Writes a whole block, `address` being the address of its first byte. Fails with
+    /// [`Error::CommandFailed`] if the scratchpad did not receive the block or the copy
+    /// failed, e.g. because the memory is write protected.
+    pub fn write_block<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        block: &[u8; N],
+    ) -> Result<(), Error<W::Error>> {
+        self.start_write_block(wire, delay, address, block)?
+            .wait(wire, delay)
+    }
+
+    /// Like [`ScratchpadEeprom::write_block`], but returns once the copy started
+    pub fn start_write_block<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        block: &[u8; N],
+    ) -> Result<PendingCopy, Error<W::Error>> {
+        let es = self.prepare_block(wire, delay, address, block)?;
+        let [low, high] = address.to_le_bytes();
+        wire.reset_select_write_only(
+            delay,
+            self.device,
+            &[Command::CopyScratchpad as u8, low, high, es],
+        )?;
+        Ok(PendingCopy::new(self.programming_us, true))
+    }
+
+    /// Writes the data block by block, reading partially written blocks first
+    pub fn write<W: OneWireMaster>(
+        &self,
+        wire: &mut W,
+        delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        let mut address = address;
+        let mut data = data;
+        while !data.is_empty() {
+            let start = address & !(N as u16 - 1);
+            let offset = usize::from(address - start);
+            let mut block = [0u8; N];
+            if offset > 0 || data.len() < N {
+                self.read(wire, delay, start, &mut block)?;
+            }
+            let mut count = 0;
+            for (byte, value) in block.iter_mut().skip(offset).zip(data) {
+                *byte = *value;
+                count += 1;
+            }
+            self.write_block(wire, delay, start, &block)?;
+            address += count as u16;
+            data = data.get(count..).unwrap_or(&[]);
+        }
+        Ok(())
+    }
+}
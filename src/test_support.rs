@@ -0,0 +1,727 @@
+//! Fakes for the hardware abstractions used in tests
+
+use crate::memory::Memory;
+use crate::{compute_partial_crc8, Error, OneWireMaster, OpenDrainOutput};
+use core::cell::RefCell;
+use core::convert::Infallible;
+use hal::blocking::delay::{DelayMs, DelayUs};
+
+/// A pin on an idle bus without any devices
+pub struct Pin;
+
+impl OpenDrainOutput for Pin {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// A pin on a bus held low by someone else
+pub struct LowPin;
+
+impl OpenDrainOutput for LowPin {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(true)
+    }
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// A [`Memory`] that is not on the bus at all
+pub struct Ram(pub RefCell<[u8; 64]>);
+
+impl Memory for Ram {
+    fn size(&self) -> u16 {
+        64
+    }
+
+    fn read_memory<W: OneWireMaster>(
+        &self,
+        _wire: &mut W,
+        _delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &mut [u8],
+    ) -> Result<(), Error<W::Error>> {
+        let address = usize::from(address);
+        data.copy_from_slice(&self.0.borrow()[address..address + data.len()]);
+        Ok(())
+    }
+
+    fn write_memory<W: OneWireMaster>(
+        &self,
+        _wire: &mut W,
+        _delay: &mut impl DelayUs<u16>,
+        address: u16,
+        data: &[u8],
+    ) -> Result<(), Error<W::Error>> {
+        let address = usize::from(address);
+        self.0.borrow_mut()[address..address + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+pub struct Delay;
+
+impl DelayUs<u16> for Delay {
+    fn delay_us(&mut self, _us: u16) {}
+}
+
+impl DelayMs<u16> for Delay {
+    fn delay_ms(&mut self, _ms: u16) {}
+}
+
+/// Assigns the addresses of the virtual slaves of a [`Simulator`]
+pub trait AddressAllocator {
+    fn allocate(&mut self, index: usize) -> [u8; 8];
+}
+
+/// Pseudo random addresses of a single family, deterministic for a seed
+pub struct Seeded {
+    pub family: u8,
+    pub state: u32,
+}
+
+impl AddressAllocator for Seeded {
+    fn allocate(&mut self, _index: usize) -> [u8; 8] {
+        let mut address = [self.family, 0, 0, 0, 0, 0, 0, 0];
+        for byte in address[1..7].iter_mut() {
+            // xorshift32
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 17;
+            self.state ^= self.state << 5;
+            *byte = self.state as u8;
+        }
+        address[7] = compute_partial_crc8(0, &address[..7]);
+        address
+    }
+}
+
+/// Addresses sharing all but the last serial bits, so the search forks as late as possible
+pub struct Clustered {
+    pub base: [u8; 8],
+}
+
+impl AddressAllocator for Clustered {
+    fn allocate(&mut self, index: usize) -> [u8; 8] {
+        let mut address = self.base;
+        address[5] ^= index as u8;
+        address[6] ^= (index >> 8) as u8;
+        address[7] = compute_partial_crc8(0, &address[..7]);
+        address
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SlaveState {
+    Idle,
+    Command {
+        byte: u8,
+        bits: u8,
+    },
+    Search {
+        bit: u8,
+        phase: u8,
+    },
+    ReadRom {
+        bit: u8,
+    },
+    MatchRom {
+        bit: u8,
+    },
+    /// Receiving the function command following Skip ROM or Match ROM
+    Function {
+        byte: u8,
+        bits: u8,
+    },
+    /// A DS18B20 holding read slots low until the conversion completed, which it never does
+    Converting,
+    /// Found by a search or selected, a DS2405 answers with the level of its PIO
+    Addressed,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slave {
+    address: [u8; 8],
+    state: SlaveState,
+    /// Whether the output transistor of a DS2405 is on, toggled by every Match ROM
+    pio_on: bool,
+    /// The function command received after Skip ROM or Match ROM since the last reset
+    function: Option<u8>,
+}
+
+impl Slave {
+    fn address_bit(&self, bit: u8) -> bool {
+        self.address[usize::from(bit / 8)] & (1 << (bit % 8)) != 0
+    }
+
+    /// Processes a time slot in which the master wrote the given bit (a read slot looks
+    /// like writing a 1), returns false if the slave pulled the wire low
+    fn slot(&mut self, master: bool) -> bool {
+        match self.state {
+            SlaveState::Idle => true,
+            SlaveState::Command { byte, bits } => {
+                let byte = byte | (u8::from(master) << bits);
+                self.state = match (bits, byte) {
+                    (7, 0xF0) => SlaveState::Search { bit: 0, phase: 0 },
+                    (7, 0x33) => SlaveState::ReadRom { bit: 0 },
+                    (7, 0x55) => SlaveState::MatchRom { bit: 0 },
+                    (7, 0xCC) => SlaveState::Function { byte: 0, bits: 0 },
+                    (7, _) => SlaveState::Idle,
+                    _ => SlaveState::Command {
+                        byte,
+                        bits: bits + 1,
+                    },
+                };
+                true
+            }
+            SlaveState::ReadRom { bit } => {
+                self.state = if bit == 63 {
+                    SlaveState::Idle
+                } else {
+                    SlaveState::ReadRom { bit: bit + 1 }
+                };
+                self.address_bit(bit)
+            }
+            SlaveState::Search { bit, phase: 0 } => {
+                self.state = SlaveState::Search { bit, phase: 1 };
+                self.address_bit(bit)
+            }
+            SlaveState::Search { bit, phase: 1 } => {
+                self.state = SlaveState::Search { bit, phase: 2 };
+                !self.address_bit(bit)
+            }
+            SlaveState::Search { bit, .. } => {
+                self.state = if master != self.address_bit(bit) {
+                    SlaveState::Idle
+                } else if bit == 63 {
+                    SlaveState::Addressed
+                } else {
+                    SlaveState::Search {
+                        bit: bit + 1,
+                        phase: 0,
+                    }
+                };
+                true
+            }
+            SlaveState::MatchRom { bit } => {
+                self.state = if master != self.address_bit(bit) {
+                    SlaveState::Idle
+                } else if bit == 63 {
+                    if self.address[0] == crate::family::DS2405 {
+                        self.pio_on = !self.pio_on;
+                        SlaveState::Addressed
+                    } else {
+                        SlaveState::Function { byte: 0, bits: 0 }
+                    }
+                } else {
+                    SlaveState::MatchRom { bit: bit + 1 }
+                };
+                true
+            }
+            SlaveState::Function { byte, bits } => {
+                let byte = byte | (u8::from(master) << bits);
+                self.state = if bits < 7 {
+                    SlaveState::Function {
+                        byte,
+                        bits: bits + 1,
+                    }
+                } else {
+                    self.function = Some(byte);
+                    if byte == 0x44 && self.address[0] == crate::family::DS18B20 {
+                        SlaveState::Converting
+                    } else {
+                        SlaveState::Idle
+                    }
+                };
+                true
+            }
+            SlaveState::Converting => false,
+            SlaveState::Addressed => {
+                // the PIO reads low while the transistor is on
+                self.address[0] != crate::family::DS2405 || !self.pio_on
+            }
+        }
+    }
+}
+
+/// When the virtual slaves answer, in microseconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Skew {
+    /// From the end of the reset pulse until the presence pulse
+    pub presence_wait: u64,
+    pub presence_len: u64,
+    /// From the start of a read slot until the slave pulls the wire low
+    pub read_pull_delay: u64,
+    /// From the start of a read slot until the slave releases the wire
+    pub read_hold: u64,
+}
+
+impl Skew {
+    pub const NOMINAL: Skew = Skew {
+        presence_wait: 15,
+        presence_len: 120,
+        read_pull_delay: 0,
+        read_hold: 60,
+    };
+
+    /// All combinations of the edges of the windows the specification allows
+    pub fn sweep() -> impl Iterator<Item = Skew> {
+        [15, 60].iter().copied().flat_map(|presence_wait| {
+            [60, 240].iter().copied().flat_map(move |presence_len| {
+                [0, 10].iter().copied().flat_map(move |read_pull_delay| {
+                    [15, 60].iter().copied().map(move |read_hold| Skew {
+                        presence_wait,
+                        presence_len,
+                        read_pull_delay,
+                        read_hold,
+                    })
+                })
+            })
+        })
+    }
+}
+
+/// A bus of `N` virtual slaves that answer resets and searches, driven by the low pulses
+/// of a [`SimPin`] and the time passed to a [`SimDelay`]
+pub struct Simulator<const N: usize> {
+    slaves: [Slave; N],
+    now: u64,
+    low_since: Option<u64>,
+    presence: core::ops::Range<u64>,
+    /// The wire level the slaves drive in the current read slot
+    level: bool,
+    pulled: core::ops::Range<u64>,
+    skew: Skew,
+}
+
+impl<const N: usize> Simulator<N> {
+    pub fn new(allocator: &mut impl AddressAllocator) -> RefCell<Self> {
+        RefCell::new(Simulator {
+            slaves: core::array::from_fn(|index| Slave {
+                address: allocator.allocate(index),
+                state: SlaveState::Idle,
+                pio_on: false,
+                function: None,
+            }),
+            now: 0,
+            low_since: None,
+            presence: 0..0,
+            level: true,
+            pulled: 0..0,
+            skew: Skew::NOMINAL,
+        })
+    }
+
+    pub fn set_skew(&mut self, skew: Skew) {
+        self.skew = skew;
+    }
+
+    pub fn addresses(&self) -> [[u8; 8]; N] {
+        core::array::from_fn(|index| self.slaves[index].address)
+    }
+
+    /// The function command each slave received after Skip ROM or Match ROM since the last
+    /// reset
+    pub fn functions(&self) -> [Option<u8>; N] {
+        core::array::from_fn(|index| self.slaves[index].function)
+    }
+
+    fn release(&mut self) {
+        if let Some(since) = self.low_since.take() {
+            let low = self.now - since;
+            if low >= 400 {
+                for slave in self.slaves.iter_mut() {
+                    slave.state = SlaveState::Command { byte: 0, bits: 0 };
+                    slave.function = None;
+                }
+                if N > 0 {
+                    let start = self.now + self.skew.presence_wait;
+                    self.presence = start..start + self.skew.presence_len;
+                }
+            } else {
+                let master = low < 15;
+                let mut level = true;
+                for slave in self.slaves.iter_mut() {
+                    level &= slave.slot(master);
+                }
+                self.level = level;
+                self.pulled = since + self.skew.read_pull_delay..since + self.skew.read_hold;
+            }
+        }
+    }
+
+    fn is_high(&self) -> bool {
+        if self.low_since.is_some() || self.presence.contains(&self.now) {
+            false
+        } else if self.pulled.contains(&self.now) {
+            self.level
+        } else {
+            true
+        }
+    }
+}
+
+pub struct SimPin<'a, const N: usize>(pub &'a RefCell<Simulator<N>>);
+
+impl<const N: usize> OpenDrainOutput for SimPin<'_, N> {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Infallible> {
+        Ok(self.0.borrow().is_high())
+    }
+
+    fn is_low(&self) -> Result<bool, Infallible> {
+        Ok(!self.0.borrow().is_high())
+    }
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        let mut sim = self.0.borrow_mut();
+        if sim.low_since.is_none() {
+            sim.low_since = Some(sim.now);
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().release();
+        Ok(())
+    }
+}
+
+pub struct SimDelay<'a, const N: usize>(pub &'a RefCell<Simulator<N>>);
+
+impl<const N: usize> DelayUs<u16> for SimDelay<'_, N> {
+    fn delay_us(&mut self, us: u16) {
+        self.0.borrow_mut().now += u64::from(us);
+    }
+}
+
+#[cfg(feature = "async")]
+impl<const N: usize> embedded_hal_async::delay::DelayNs for SimDelay<'_, N> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().now += u64::from(ns / 1000);
+    }
+}
+
+/// Polls the future to completion, for futures that never wait for a wake up
+#[cfg(feature = "async")]
+pub fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    let mut future = core::pin::pin!(future);
+    let mut context = core::task::Context::from_waker(core::task::Waker::noop());
+    loop {
+        if let core::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// An EEPROM of the DS2431 family with a scratchpad of `N` bytes, answering the memory
+/// function commands after being selected. With `protection`, the memory is write protected
+/// in blocks of the given size by a 0x55 in the protection byte at the given address plus
+/// the block index.
+#[cfg(any(feature = "ds2431", feature = "ds2433", feature = "ds28ec20"))]
+pub struct FakeEeprom<const N: usize> {
+    pub memory: [u8; 0xA20],
+    protection: Option<(usize, usize)>,
+    scratchpad: [u8; N],
+    target: u16,
+    es: u8,
+    received: [u8; 48],
+    bits: usize,
+    output: [u8; 0xA20],
+    output_len: usize,
+    read: usize,
+}
+
+#[cfg(any(feature = "ds2431", feature = "ds2433", feature = "ds28ec20"))]
+impl<const N: usize> FakeEeprom<N> {
+    pub fn new(protection: Option<(usize, usize)>) -> Self {
+        FakeEeprom {
+            memory: [0xFF; 0xA20],
+            protection,
+            scratchpad: [0; N],
+            target: 0,
+            es: 0,
+            received: [0; 48],
+            bits: 0,
+            output: [0; 0xA20],
+            output_len: 0,
+            read: 0,
+        }
+    }
+
+    fn answer(&mut self, bytes: &[u8]) {
+        self.output[..bytes.len()].copy_from_slice(bytes);
+        self.output_len = bytes.len();
+    }
+
+    fn is_protected(&self, target: usize) -> bool {
+        self.protection.is_some_and(|(protection, block)| {
+            target < protection && self.memory[protection + target / block] == 0x55
+        })
+    }
+
+    /// Handles the `index`th byte after the reset, following Match ROM and the address
+    fn receive(&mut self, index: usize) {
+        use crate::compute_partial_crc16;
+
+        let bytes = self.received;
+        let function = &bytes[9..=index];
+        match *function {
+            [0x0F, low, high] => self.target = u16::from_le_bytes([low, high]),
+            [0x0F, _, _, ref data @ ..] if data.len() == N => {
+                self.scratchpad.copy_from_slice(data);
+                self.es = (N - 1) as u8;
+                self.answer(&(!compute_partial_crc16(0, function)).to_le_bytes());
+            }
+            [0xAA] => {
+                let [low, high] = self.target.to_le_bytes();
+                let mut answer = [0u8; 0xA20];
+                answer[..3].copy_from_slice(&[low, high, self.es]);
+                answer[3..3 + N].copy_from_slice(&self.scratchpad);
+                let crc = compute_partial_crc16(0, &[0xAA]);
+                let crc = !compute_partial_crc16(crc, &answer[..3 + N]);
+                answer[3 + N..5 + N].copy_from_slice(&crc.to_le_bytes());
+                self.answer(&answer[..5 + N]);
+            }
+            [0x55, low, high, es] => {
+                let target = usize::from(u16::from_le_bytes([low, high]));
+                if es == self.es
+                    && u16::from_le_bytes([low, high]) == self.target
+                    && !self.is_protected(target)
+                {
+                    self.memory[target..target + N].copy_from_slice(&self.scratchpad);
+                    self.answer(&[0xAA]);
+                } else {
+                    self.answer(&[0xFF]);
+                }
+            }
+            [0xF0, low, high] => {
+                let target = usize::from(u16::from_le_bytes([low, high]));
+                let memory = self.memory;
+                self.answer(&memory[target..]);
+            }
+            [0xA5, low, high] => {
+                // up to the end of the page with the CRC16 of the command, then whole pages
+                let mut target = usize::from(u16::from_le_bytes([low, high]));
+                let mut answer = [0u8; 0xA20];
+                let mut len = 0;
+                let mut crc = compute_partial_crc16(0, function);
+                for _ in 0..2 {
+                    let end = (target | 31) + 1;
+                    let page = &self.memory[target..end];
+                    crc = !compute_partial_crc16(crc, page);
+                    answer[len..len + page.len()].copy_from_slice(page);
+                    answer[len + page.len()..len + page.len() + 2]
+                        .copy_from_slice(&crc.to_le_bytes());
+                    len += page.len() + 2;
+                    target = end;
+                    crc = 0;
+                }
+                self.answer(&answer[..len]);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(any(feature = "ds2431", feature = "ds2433", feature = "ds28ec20"))]
+impl<const N: usize> OneWireMaster for FakeEeprom<N> {
+    type Error = Infallible;
+
+    fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+        self.bits = 0;
+        self.read = 0;
+        self.output_len = 0;
+        Ok(true)
+    }
+
+    fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+        let bit = self.read;
+        self.read += 1;
+        if bit >= 8 * self.output_len {
+            return Ok(true);
+        }
+        Ok(self.output[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    fn write_bit(
+        &mut self,
+        _delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<Infallible>> {
+        let index = self.bits / 8;
+        self.received[index] = self.received[index] >> 1 | u8::from(high) << 7;
+        self.bits += 1;
+        if self.bits & 7 == 0 && index >= 9 {
+            self.receive(index);
+        }
+        Ok(())
+    }
+}
+
+/// A device scripted byte by byte for [`ScriptedWire`]
+#[cfg(any(
+    feature = "ds2409",
+    feature = "ds2413",
+    feature = "ds28e17",
+    feature = "ds28ea00"
+))]
+pub trait Script {
+    /// Called whenever a byte is complete, with all bytes written since the last reset. The
+    /// bits queued on `reply` are read next.
+    fn receive(&mut self, written: &[u8], reply: &mut Reply);
+
+    /// The bit read while no reply is queued, with the bits written since the reset (the
+    /// last byte possibly incomplete) and the number of bits read before. The idle bus
+    /// reads 1.
+    fn idle_bit(&mut self, _written: &[u8], _reads: usize) -> bool {
+        true
+    }
+}
+
+/// The bits a [`Script`] answers with, read least significant bit first
+#[cfg(any(
+    feature = "ds2409",
+    feature = "ds2413",
+    feature = "ds28e17",
+    feature = "ds28ea00"
+))]
+#[derive(Default)]
+pub struct Reply {
+    bits: u128,
+    len: u32,
+}
+
+#[cfg(any(
+    feature = "ds2409",
+    feature = "ds2413",
+    feature = "ds28e17",
+    feature = "ds28ea00"
+))]
+impl Reply {
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.bits |= u128::from(*byte) << self.len;
+            self.len += 8;
+        }
+    }
+
+    fn next(&mut self) -> Option<bool> {
+        if self.len == 0 {
+            return None;
+        }
+        let bit = self.bits & 1 != 0;
+        self.bits >>= 1;
+        self.len -= 1;
+        Some(bit)
+    }
+}
+
+/// A [`crate::OneWireMaster`] assembling the written bits into bytes for its [`Script`]
+/// and reading back the replies, with a device present on every reset
+#[cfg(any(
+    feature = "ds2409",
+    feature = "ds2413",
+    feature = "ds28e17",
+    feature = "ds28ea00"
+))]
+pub struct ScriptedWire<S> {
+    pub script: S,
+    written: [u8; 32],
+    bits: usize,
+    reads: usize,
+    reply: Reply,
+}
+
+#[cfg(any(
+    feature = "ds2409",
+    feature = "ds2413",
+    feature = "ds28e17",
+    feature = "ds28ea00"
+))]
+impl<S: Script> ScriptedWire<S> {
+    pub fn new(script: S) -> Self {
+        ScriptedWire {
+            script,
+            written: [0; 32],
+            bits: 0,
+            reads: 0,
+            reply: Reply::default(),
+        }
+    }
+
+    /// The bytes written since the last reset
+    pub fn written(&self) -> &[u8] {
+        &self.written[..self.bits / 8]
+    }
+}
+
+#[cfg(any(
+    feature = "ds2409",
+    feature = "ds2413",
+    feature = "ds28e17",
+    feature = "ds28ea00"
+))]
+impl<S: Script> OneWireMaster for ScriptedWire<S> {
+    type Error = Infallible;
+
+    fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+        self.written = [0; 32];
+        self.bits = 0;
+        self.reads = 0;
+        self.reply = Reply::default();
+        Ok(true)
+    }
+
+    fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<Infallible>> {
+        let bit = match self.reply.next() {
+            Some(bit) => bit,
+            None => {
+                let written = &self.written[..self.bits.div_ceil(8)];
+                self.script.idle_bit(written, self.reads)
+            }
+        };
+        self.reads += 1;
+        Ok(bit)
+    }
+
+    fn write_bit(
+        &mut self,
+        _delay: &mut impl DelayUs<u16>,
+        high: bool,
+    ) -> Result<(), Error<Infallible>> {
+        self.written[self.bits / 8] |= u8::from(high) << (self.bits % 8);
+        self.bits += 1;
+        if self.bits & 7 == 0 {
+            let written = &self.written[..self.bits / 8];
+            self.script.receive(written, &mut self.reply);
+        }
+        Ok(())
+    }
+}
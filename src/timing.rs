@@ -0,0 +1,410 @@
+/// Durations in microseconds of the phases of reset pulses and read/write slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// How long the bus is held low to issue a reset
+    pub reset_low: u16,
+    /// Interval at which the bus is sampled for a presence pulse after a reset
+    pub presence_poll: u16,
+    /// Number of times the bus is sampled for a presence pulse
+    pub presence_polls: u16,
+    /// Wait after sampling for the presence pulse, until the reset is complete
+    pub reset_recovery: u16,
+    /// How long the bus is held low to start a read slot
+    pub read_low: u16,
+    /// Wait after releasing the bus before sampling it in a read slot
+    pub read_sample: u16,
+    /// Wait after sampling, until the read slot is complete
+    pub read_recovery: u16,
+    /// How long the bus is held low to write a 1
+    pub write_one_low: u16,
+    /// Wait after writing a 1, until the write slot is complete
+    pub write_one_recovery: u16,
+    /// How long the bus is held low to write a 0
+    pub write_zero_low: u16,
+    /// Wait after writing a 0, until the write slot is complete
+    pub write_zero_recovery: u16,
+}
+
+impl Timing {
+    pub const STANDARD: Timing = Timing {
+        reset_low: 480,
+        presence_poll: 10,
+        presence_polls: 7,
+        reset_recovery: 410,
+        read_low: 3,
+        // sample late in the slot, slow devices pull the wire low only after a few us
+        read_sample: 10,
+        read_recovery: 53,
+        write_one_low: 10,
+        write_one_recovery: 55,
+        write_zero_low: 65,
+        write_zero_recovery: 5,
+    };
+
+    /// Standard speed timing for delays that cannot wait less than about 5us: the read
+    /// slot is sampled right after releasing the bus and the write-1 slot is held low
+    /// shorter, so that both stay within the specification even if each delay takes up to
+    /// 7us
+    pub const RELAXED: Timing = Timing {
+        reset_low: 480,
+        presence_poll: 10,
+        presence_polls: 7,
+        reset_recovery: 410,
+        read_low: 1,
+        read_sample: 0,
+        read_recovery: 65,
+        write_one_low: 1,
+        write_one_recovery: 64,
+        write_zero_low: 65,
+        write_zero_recovery: 5,
+    };
+
+    /// Timing of overdrive speed, only understood by devices that were addressed with an
+    /// overdrive ROM command, see [`crate::OneWire::transaction_with_speed`]
+    pub const OVERDRIVE: Timing = Timing {
+        reset_low: 70,
+        presence_poll: 2,
+        presence_polls: 5,
+        reset_recovery: 40,
+        read_low: 1,
+        read_sample: 1,
+        read_recovery: 7,
+        write_one_low: 1,
+        write_one_recovery: 8,
+        write_zero_low: 8,
+        write_zero_recovery: 3,
+    };
+
+    /// Replaces the low times of the write slots while keeping the duration of the slots.
+    /// The result is not checked against the specification, follow it with
+    /// [`Timing::validated`] in a constant or with [`Timing::check`] at runtime.
+    ///
+    /// ```compile_fail
+    /// use onewire::Timing;
+    /// const TIMING: Timing = Timing::STANDARD.with_write_low(10, 20).validated();
+    /// ```
+    pub const fn with_write_low(mut self, write_one_low: u16, write_zero_low: u16) -> Timing {
+        let one_slot = self.write_one_low.saturating_add(self.write_one_recovery);
+        let zero_slot = self.write_zero_low.saturating_add(self.write_zero_recovery);
+        self.write_one_low = write_one_low;
+        self.write_one_recovery = one_slot.saturating_sub(write_one_low);
+        self.write_zero_low = write_zero_low;
+        self.write_zero_recovery = zero_slot.saturating_sub(write_zero_low);
+        self
+    }
+
+    /// Checks the reset pulse and the time slots against the windows of the 1-Wire
+    /// specification for standard speed
+    pub const fn check(&self) -> Result<(), TimingError> {
+        self.check_for(Speed::Standard)
+    }
+
+    /// Like [`Timing::check`], but panics on a violation, which turns it into a build error
+    /// when used in a constant
+    ///
+    /// ```
+    /// use onewire::Timing;
+    /// const TIMING: Timing = Timing::STANDARD.with_write_low(6, 68).validated();
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use onewire::Timing;
+    /// const TIMING: Timing = Timing {
+    ///     reset_low: 120,
+    ///     ..Timing::STANDARD
+    /// }
+    /// .validated();
+    /// ```
+    pub const fn validated(self) -> Timing {
+        self.validated_for(Speed::Standard)
+    }
+
+    /// Like [`Timing::validated`] for the given speed, see [`Timing::check_for`]
+    pub const fn validated_for(self, speed: Speed) -> Timing {
+        assert!(
+            self.check_for(speed).is_ok(),
+            "timing violates the 1-Wire specification"
+        );
+        self
+    }
+
+    /// Checks that a CPU clocked with `cpu_hz` can generate the short intervals within the
+    /// slots, each of which has to last at least one clock cycle, e.g. overdrive speed is
+    /// out of reach below 1 MHz. The CRC is computed outside of the slots, so the
+    /// `crc8-table` feature does not change this bound.
+    pub const fn check_clock(&self, cpu_hz: u32) -> Result<(), TimingError> {
+        let mut intervals: &[u16] = &[self.read_low, self.read_sample, self.write_one_low];
+        while let [interval, rest @ ..] = intervals {
+            if *interval > 0 && (*interval as u64) * (cpu_hz as u64) < 1_000_000 {
+                return Err(TimingError::Clock(*interval));
+            }
+            intervals = rest;
+        }
+        Ok(())
+    }
+
+    /// Like [`Timing::validated_for`], but also panics if the CPU clocked with `cpu_hz`
+    /// is too slow for the timing, see [`Timing::check_clock`]
+    ///
+    /// ```
+    /// use onewire::{Speed, Timing};
+    /// const TIMING: Timing = Timing::OVERDRIVE.validated_for_clock(Speed::Overdrive, 16_000_000);
+    /// ```
+    ///
+    /// ```compile_fail
+    /// use onewire::{Speed, Timing};
+    /// const TIMING: Timing = Timing::OVERDRIVE.validated_for_clock(Speed::Overdrive, 500_000);
+    /// ```
+    pub const fn validated_for_clock(self, speed: Speed, cpu_hz: u32) -> Timing {
+        assert!(
+            self.check_clock(cpu_hz).is_ok(),
+            "timing is too short for the CPU clock"
+        );
+        self.validated_for(speed)
+    }
+
+    /// Checks the reset pulse and the time slots against the windows of the 1-Wire
+    /// specification for the given speed
+    pub const fn check_for(&self, speed: Speed) -> Result<(), TimingError> {
+        // (write-1 low, write-0 low, slot), each as [min, max)
+        let (one_low, zero_low, slot) = match speed {
+            Speed::Standard => (
+                (WRITE_ONE_LOW_MIN, WRITE_ONE_LOW_MAX),
+                (WRITE_ZERO_LOW_MIN, WRITE_ZERO_LOW_MAX),
+                (SLOT_MIN, SLOT_MAX),
+            ),
+            Speed::Overdrive => ((1, 2), (6, 16), (6, 16)),
+        };
+        let (reset_low_min, read_sample_max) = match speed {
+            Speed::Standard => (RESET_LOW_MIN, READ_SAMPLE_MAX),
+            Speed::Overdrive => (OVERDRIVE_RESET_LOW_MIN, OVERDRIVE_READ_SAMPLE_MAX),
+        };
+        let read_sample = self.read_low.saturating_add(self.read_sample);
+        let one_slot = self.write_one_low.saturating_add(self.write_one_recovery);
+        let zero_slot = self.write_zero_low.saturating_add(self.write_zero_recovery);
+        if self.write_one_low < one_low.0 || self.write_one_low >= one_low.1 {
+            Err(TimingError::WriteOneLow(self.write_one_low))
+        } else if self.write_zero_low < zero_low.0 || self.write_zero_low >= zero_low.1 {
+            Err(TimingError::WriteZeroLow(self.write_zero_low))
+        } else if one_slot < slot.0 || one_slot >= slot.1 {
+            Err(TimingError::WriteOneSlot(one_slot))
+        } else if zero_slot < slot.0 || zero_slot >= slot.1 {
+            Err(TimingError::WriteZeroSlot(zero_slot))
+        } else if self.write_one_recovery < RECOVERY_MIN || self.write_zero_recovery < RECOVERY_MIN
+        {
+            Err(TimingError::Recovery)
+        } else if self.reset_low < reset_low_min {
+            Err(TimingError::ResetLow(self.reset_low))
+        } else if read_sample > read_sample_max {
+            Err(TimingError::ReadSample(read_sample))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// How much an interrupt may delay sampling a read slot, before the device stops
+    /// holding its bit on the wire
+    pub fn read_latency_budget_us(&self) -> u16 {
+        READ_SAMPLE_MAX.saturating_sub(self.read_low.saturating_add(self.read_sample))
+    }
+
+    /// How much an interrupt may stretch the low time of a write-1 slot, before the
+    /// device samples it as a 0
+    pub fn write_latency_budget_us(&self) -> u16 {
+        (WRITE_ONE_LOW_MAX - 1).saturating_sub(self.write_one_low)
+    }
+
+    /// The maximum latency in microseconds an interrupt may add to a time slot: interrupt
+    /// handlers that are not masked during bus activity must finish within it
+    pub fn interrupt_latency_budget_us(&self) -> u16 {
+        self.read_latency_budget_us()
+            .min(self.write_latency_budget_us())
+    }
+
+    pub fn reset_us(&self) -> u32 {
+        u32::from(self.reset_low)
+            + u32::from(self.presence_poll) * u32::from(self.presence_polls)
+            + u32::from(self.reset_recovery)
+    }
+
+    pub fn read_slot_us(&self) -> u32 {
+        u32::from(self.read_low) + u32::from(self.read_sample) + u32::from(self.read_recovery)
+    }
+
+    /// The duration of the longer one of both write slots
+    pub fn write_slot_us(&self) -> u32 {
+        let one = u32::from(self.write_one_low) + u32::from(self.write_one_recovery);
+        let zero = u32::from(self.write_zero_low) + u32::from(self.write_zero_recovery);
+        one.max(zero)
+    }
+
+    /// Estimates the bus time in microseconds an operation takes. The estimate is based on
+    /// the slowest write slot and does not include the overhead of driving and sampling the
+    /// pin, so it is a lower bound on slow MCUs.
+    pub fn estimate_us(&self, operation: Operation) -> u32 {
+        let read_bytes = |n: usize| (n as u32).saturating_mul(8 * self.read_slot_us());
+        let write_bytes = |n: usize| (n as u32).saturating_mul(8 * self.write_slot_us());
+        match operation {
+            Operation::Reset => self.reset_us(),
+            Operation::Select => write_bytes(1 + crate::ADDRESS_BYTES as usize),
+            Operation::ReadBytes(n) => read_bytes(n),
+            Operation::WriteBytes(n) => write_bytes(n),
+            Operation::SearchStep => {
+                self.reset_us()
+                    + write_bytes(1)
+                    + u32::from(crate::ADDRESS_BITS)
+                        * (2 * self.read_slot_us() + self.write_slot_us())
+            }
+        }
+    }
+}
+
+/// Minimal low time of a write-1 slot at standard speed
+pub const WRITE_ONE_LOW_MIN: u16 = 1;
+/// Exclusive maximal low time of a write-1 slot at standard speed, the device samples
+/// the bus after 15us at the earliest
+pub const WRITE_ONE_LOW_MAX: u16 = 15;
+/// Latest point in time after the start of a read slot at which the bit of the device
+/// is guaranteed to be valid at standard speed
+pub const READ_SAMPLE_MAX: u16 = 15;
+/// Like [`READ_SAMPLE_MAX`] at overdrive speed
+pub const OVERDRIVE_READ_SAMPLE_MAX: u16 = 2;
+/// Minimal low time of a write-0 slot at standard speed
+pub const WRITE_ZERO_LOW_MIN: u16 = 60;
+/// Exclusive maximal low time of a write-0 slot at standard speed
+pub const WRITE_ZERO_LOW_MAX: u16 = 120;
+/// Minimal duration of a time slot at standard speed
+pub const SLOT_MIN: u16 = 60;
+/// Exclusive maximal duration of a time slot at standard speed
+pub const SLOT_MAX: u16 = 120;
+/// Minimal recovery time between two time slots
+pub const RECOVERY_MIN: u16 = 1;
+/// Minimal low time of a reset pulse at standard speed
+pub const RESET_LOW_MIN: u16 = 480;
+/// Like [`RESET_LOW_MIN`] at overdrive speed
+pub const OVERDRIVE_RESET_LOW_MIN: u16 = 70;
+
+// the built-in timings must not need a runtime check
+const _: Timing = Timing::STANDARD.validated();
+const _: Timing = Timing::RELAXED.validated();
+const _: Timing = Timing::OVERDRIVE.validated_for(Speed::Overdrive);
+
+/// Bus speed of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Speed {
+    #[default]
+    Standard,
+    /// About ten times the standard speed, supported by some EEPROMs and iButtons
+    Overdrive,
+}
+
+/// Part of a [`Timing`] outside of the window of the specification, with the offending
+/// duration in microseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingError {
+    WriteOneLow(u16),
+    WriteZeroLow(u16),
+    WriteOneSlot(u16),
+    WriteZeroSlot(u16),
+    /// The bus is not released long enough between two slots
+    Recovery,
+    /// The reset pulse is too short for the devices to recognize it
+    ResetLow(u16),
+    /// The read slot is sampled the given microseconds after its start, after the devices
+    /// may have released the bus
+    ReadSample(u16),
+    /// An interval of the given microseconds is shorter than a cycle of the CPU, see
+    /// [`Timing::check_clock`]
+    Clock(u16),
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing::STANDARD
+    }
+}
+
+/// Bus operations for [`Timing::estimate_us`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Reset,
+    /// Match ROM command followed by the address
+    Select,
+    ReadBytes(usize),
+    WriteBytes(usize),
+    /// Finding the next device with a [`crate::DeviceSearch`]
+    SearchStep,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Operation, Speed, Timing, TimingError};
+
+    #[test]
+    fn test_latency_budget() {
+        assert_eq!(2, Timing::STANDARD.read_latency_budget_us());
+        assert_eq!(4, Timing::STANDARD.write_latency_budget_us());
+        assert_eq!(2, Timing::STANDARD.interrupt_latency_budget_us());
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(Ok(()), Timing::STANDARD.check());
+        assert_eq!(Ok(()), Timing::OVERDRIVE.check_for(Speed::Overdrive));
+        assert!(Timing::OVERDRIVE.check().is_err());
+        assert_eq!(Ok(()), Timing::RELAXED.check());
+
+        let timing = Timing::STANDARD.with_write_low(6, 68);
+        assert_eq!(59, timing.write_one_recovery);
+        assert_eq!(2, timing.write_zero_recovery);
+
+        let mut timing = Timing::STANDARD;
+        timing.write_one_low = 15;
+        assert_eq!(Err(TimingError::WriteOneLow(15)), timing.check());
+        timing = Timing::STANDARD;
+        timing.write_zero_recovery = 60;
+        assert_eq!(Err(TimingError::WriteZeroSlot(125)), timing.check());
+        timing = Timing::STANDARD;
+        timing.write_zero_recovery = 0;
+        assert_eq!(Err(TimingError::Recovery), timing.check());
+        timing = Timing::STANDARD;
+        timing.read_sample = 13;
+        assert_eq!(Err(TimingError::ReadSample(16)), timing.check());
+        timing = Timing::STANDARD;
+        timing.reset_low = 470;
+        assert_eq!(Err(TimingError::ResetLow(470)), timing.check());
+    }
+
+    #[test]
+    fn test_check_clock() {
+        assert_eq!(Ok(()), Timing::OVERDRIVE.check_clock(1_000_000));
+        assert_eq!(
+            Err(TimingError::Clock(1)),
+            Timing::OVERDRIVE.check_clock(800_000)
+        );
+        assert_eq!(Ok(()), Timing::STANDARD.check_clock(400_000));
+        assert_eq!(Ok(()), Timing::RELAXED.check_clock(1_000_000));
+        assert_eq!(
+            Err(TimingError::Clock(3)),
+            Timing::STANDARD.check_clock(300_000)
+        );
+    }
+
+    #[test]
+    fn test_with_write_low_out_of_spec() {
+        let timing = Timing::STANDARD.with_write_low(10, 20);
+        assert_eq!(Err(TimingError::WriteZeroLow(20)), timing.check());
+    }
+
+    #[test]
+    fn test_estimate() {
+        let timing = Timing::STANDARD;
+        assert_eq!(timing.estimate_us(Operation::Reset), 960);
+        assert_eq!(timing.estimate_us(Operation::Select), 9 * 8 * 70);
+        assert_eq!(timing.estimate_us(Operation::ReadBytes(9)), 9 * 8 * 66);
+        assert_eq!(
+            timing.estimate_us(Operation::SearchStep),
+            960 + 8 * 70 + 64 * (2 * 66 + 70)
+        );
+    }
+}
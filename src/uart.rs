@@ -0,0 +1,175 @@
+//! A 1-Wire master on a UART with its TX and RX connected to the wire (TX open drain or
+//! through a diode). Each time slot is a single UART frame whose start bit is the low
+//! pulse: the frame is sent and the one read back tells what the devices answered. The
+//! reset pulse is a frame at 9600 baud, the slots frames at 115200 baud. The timing is
+//! generated by the UART, so no delay has to be precise.
+
+use core::fmt::Debug;
+
+use hal::blocking::delay::DelayUs;
+use hal::serial;
+
+use crate::Error;
+use crate::OneWireMaster;
+
+pub const RESET_BAUD_RATE: u32 = 9_600;
+pub const SLOT_BAUD_RATE: u32 = 115_200;
+
+/// Holds the wire low for about 520us at 9600 baud, the devices answer within the high bits
+const RESET_FRAME: u8 = 0xF0;
+/// Only the start bit is low: a write-1 or read slot of about 9us at 115200 baud
+const ONE_FRAME: u8 = 0xFF;
+/// A write-0 slot of about 78us at 115200 baud
+const ZERO_FRAME: u8 = 0x00;
+
+/// Changing the baud rate is not part of the serial traits of embedded-hal
+pub trait BaudRate {
+    type Error;
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), Self::Error>;
+}
+
+pub struct UartMaster<U> {
+    uart: U,
+}
+
+impl<E: Debug, U> UartMaster<U>
+where
+    U: serial::Read<u8, Error = E> + serial::Write<u8, Error = E> + BaudRate<Error = E>,
+{
+    /// The UART has to be configured for 8 data bits, no parity and 1 stop bit
+    pub fn new(uart: U) -> Self {
+        UartMaster { uart }
+    }
+
+    pub fn release(self) -> U {
+        self.uart
+    }
+
+    /// Sends the frame and returns the one read back from the wire
+    fn exchange(&mut self, frame: u8) -> Result<u8, E> {
+        nb::block!(self.uart.write(frame))?;
+        nb::block!(self.uart.read())
+    }
+}
+
+impl<E: Debug, U> OneWireMaster for UartMaster<U>
+where
+    U: serial::Read<u8, Error = E> + serial::Write<u8, Error = E> + BaudRate<Error = E>,
+{
+    type Error = E;
+
+    fn reset(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<E>> {
+        self.uart.set_baud_rate(RESET_BAUD_RATE)?;
+        let echo = self.exchange(RESET_FRAME);
+        self.uart.set_baud_rate(SLOT_BAUD_RATE)?;
+        match echo? {
+            // the wire stayed low for the whole frame
+            0x00 => Err(Error::WireNotHigh),
+            echo => Ok(echo != RESET_FRAME),
+        }
+    }
+
+    fn read_bit(&mut self, _delay: &mut impl DelayUs<u16>) -> Result<bool, Error<E>> {
+        // a device answering 0 stretches the low pulse into the data bits
+        Ok(self.exchange(ONE_FRAME)? == ONE_FRAME)
+    }
+
+    fn write_bit(&mut self, _delay: &mut impl DelayUs<u16>, high: bool) -> Result<(), Error<E>> {
+        let frame = if high { ONE_FRAME } else { ZERO_FRAME };
+        self.exchange(frame)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{Seeded, SimDelay, SimPin, Simulator};
+    use crate::{DeviceSearch, OneWire};
+    use core::convert::Infallible;
+
+    /// Translates the frames into slots on a simulated bus
+    struct Uart<'a, const N: usize> {
+        wire: OneWire<SimPin<'a, N>>,
+        delay: SimDelay<'a, N>,
+        baud_rate: u32,
+        echo: Option<u8>,
+    }
+
+    impl<const N: usize> BaudRate for Uart<'_, N> {
+        type Error = Infallible;
+
+        fn set_baud_rate(&mut self, baud_rate: u32) -> Result<(), Infallible> {
+            self.baud_rate = baud_rate;
+            Ok(())
+        }
+    }
+
+    impl<const N: usize> serial::Write<u8> for Uart<'_, N> {
+        type Error = Infallible;
+
+        fn write(&mut self, frame: u8) -> nb::Result<(), Infallible> {
+            let wire = &mut self.wire;
+            let delay = &mut self.delay;
+            let echo = match (self.baud_rate, frame) {
+                (RESET_BAUD_RATE, RESET_FRAME) => {
+                    if OneWireMaster::reset(wire, delay).unwrap() {
+                        0xE0
+                    } else {
+                        RESET_FRAME
+                    }
+                }
+                (SLOT_BAUD_RATE, ONE_FRAME) => {
+                    if OneWireMaster::read_bit(wire, delay).unwrap() {
+                        ONE_FRAME
+                    } else {
+                        0xFE
+                    }
+                }
+                (SLOT_BAUD_RATE, ZERO_FRAME) => {
+                    OneWireMaster::write_bit(wire, delay, false).unwrap();
+                    ZERO_FRAME
+                }
+                unexpected => panic!("unexpected {:?}", unexpected),
+            };
+            self.echo = Some(echo);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl<const N: usize> serial::Read<u8> for Uart<'_, N> {
+        type Error = Infallible;
+
+        fn read(&mut self) -> nb::Result<u8, Infallible> {
+            self.echo.take().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let sim = Simulator::<7>::new(&mut Seeded {
+            family: 0x28,
+            state: 13,
+        });
+        let addresses = sim.borrow().addresses();
+        let mut master = UartMaster::new(Uart {
+            wire: OneWire::new(SimPin(&sim), false),
+            delay: SimDelay(&sim),
+            baud_rate: SLOT_BAUD_RATE,
+            echo: None,
+        });
+        let mut delay = SimDelay(&sim);
+        let mut found = 0;
+        for device in DeviceSearch::new().into_iter(&mut master, &mut delay) {
+            assert!(addresses.contains(&device.unwrap().address));
+            found += 1;
+        }
+        assert_eq!(7, found);
+        assert_eq!(SLOT_BAUD_RATE, master.release().baud_rate);
+    }
+}